@@ -4,6 +4,23 @@ mod m20251116_000001_create_users_table;
 mod m20251116_000002_create_user_providers_table;
 mod m20251116_000003_create_provider_models_table;
 mod m20251116_000004_create_conversations_tables;
+mod m20261018_000001_add_user_provider_options;
+mod m20261018_000002_add_message_usage;
+mod m20261018_000003_add_user_provider_budget;
+mod m20261018_000004_add_message_status;
+mod m20261018_000005_add_message_error;
+mod m20261018_000006_add_session_fallback_models;
+mod m20261018_000007_add_message_tool_calls;
+mod m20261018_000008_create_mcp_servers_table;
+mod m20261018_000009_create_message_attachments_table;
+mod m20261018_000010_add_message_reasoning;
+mod m20261018_000011_add_generation_params;
+mod m20261018_000012_add_session_system_prompt;
+mod m20261018_000013_add_context_management;
+mod m20261018_000014_add_message_tree;
+mod m20261018_000015_create_api_keys_tables;
+mod m20261018_000016_create_personal_access_tokens_table;
+mod m20261018_000017_create_spending_records_table;
 
 pub struct Migrator;
 
@@ -15,6 +32,23 @@ impl MigratorTrait for Migrator {
             Box::new(m20251116_000002_create_user_providers_table::Migration),
             Box::new(m20251116_000003_create_provider_models_table::Migration),
             Box::new(m20251116_000004_create_conversations_tables::Migration),
+            Box::new(m20261018_000001_add_user_provider_options::Migration),
+            Box::new(m20261018_000002_add_message_usage::Migration),
+            Box::new(m20261018_000003_add_user_provider_budget::Migration),
+            Box::new(m20261018_000004_add_message_status::Migration),
+            Box::new(m20261018_000005_add_message_error::Migration),
+            Box::new(m20261018_000006_add_session_fallback_models::Migration),
+            Box::new(m20261018_000007_add_message_tool_calls::Migration),
+            Box::new(m20261018_000008_create_mcp_servers_table::Migration),
+            Box::new(m20261018_000009_create_message_attachments_table::Migration),
+            Box::new(m20261018_000010_add_message_reasoning::Migration),
+            Box::new(m20261018_000011_add_generation_params::Migration),
+            Box::new(m20261018_000012_add_session_system_prompt::Migration),
+            Box::new(m20261018_000013_add_context_management::Migration),
+            Box::new(m20261018_000014_add_message_tree::Migration),
+            Box::new(m20261018_000015_create_api_keys_tables::Migration),
+            Box::new(m20261018_000016_create_personal_access_tokens_table::Migration),
+            Box::new(m20261018_000017_create_spending_records_table::Migration),
         ]
     }
 }
\ No newline at end of file
@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20251116_000003_create_provider_models_table::ProviderModels;
+use crate::m20251116_000004_create_conversations_tables::ConversationMessages;
+
+#[derive(DeriveIden)]
+enum ConversationMessagesUsage {
+    ProviderModelId,
+    PromptTokens,
+    CompletionTokens,
+    Cost,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationMessages::Table)
+                    .add_column(ColumnDef::new(ConversationMessagesUsage::ProviderModelId).uuid().null())
+                    .add_column(ColumnDef::new(ConversationMessagesUsage::PromptTokens).integer().null())
+                    .add_column(ColumnDef::new(ConversationMessagesUsage::CompletionTokens).integer().null())
+                    .add_column(ColumnDef::new(ConversationMessagesUsage::Cost).decimal().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk_conversation_messages_provider_model")
+                    .from(ConversationMessages::Table, ConversationMessagesUsage::ProviderModelId)
+                    .to(ProviderModels::Table, ProviderModels::Id)
+                    .on_delete(ForeignKeyAction::SetNull)
+                    .on_update(ForeignKeyAction::Cascade)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name("fk_conversation_messages_provider_model")
+                    .table(ConversationMessages::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationMessages::Table)
+                    .drop_column(ConversationMessagesUsage::ProviderModelId)
+                    .drop_column(ConversationMessagesUsage::PromptTokens)
+                    .drop_column(ConversationMessagesUsage::CompletionTokens)
+                    .drop_column(ConversationMessagesUsage::Cost)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20251116_000002_create_user_providers_table::UserProviders;
+
+#[derive(DeriveIden)]
+enum UserProvidersBudget {
+    Budget,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserProviders::Table)
+                    .add_column(ColumnDef::new(UserProvidersBudget::Budget).json().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserProviders::Table)
+                    .drop_column(UserProvidersBudget::Budget)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20251116_000004_create_conversations_tables::ConversationMessages;
+
+#[derive(DeriveIden)]
+enum ConversationMessagesStatus {
+    Status,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationMessages::Table)
+                    .add_column(
+                        ColumnDef::new(ConversationMessagesStatus::Status)
+                            .string()
+                            .not_null()
+                            .default("complete"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationMessages::Table)
+                    .drop_column(ConversationMessagesStatus::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20251116_000004_create_conversations_tables::ConversationSessions;
+
+#[derive(DeriveIden)]
+enum ConversationSessionsFallbacks {
+    FallbackModelIds,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationSessions::Table)
+                    .add_column(ColumnDef::new(ConversationSessionsFallbacks::FallbackModelIds).json().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationSessions::Table)
+                    .drop_column(ConversationSessionsFallbacks::FallbackModelIds)
+                    .to_owned(),
+            )
+            .await
+    }
+}
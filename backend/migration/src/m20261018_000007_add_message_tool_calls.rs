@@ -0,0 +1,39 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20251116_000004_create_conversations_tables::ConversationMessages;
+
+#[derive(DeriveIden)]
+enum ConversationMessagesTools {
+    ToolCalls,
+    ToolCallId,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationMessages::Table)
+                    .add_column(ColumnDef::new(ConversationMessagesTools::ToolCalls).json().null())
+                    .add_column(ColumnDef::new(ConversationMessagesTools::ToolCallId).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationMessages::Table)
+                    .drop_column(ConversationMessagesTools::ToolCalls)
+                    .drop_column(ConversationMessagesTools::ToolCallId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
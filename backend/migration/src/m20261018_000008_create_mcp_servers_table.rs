@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20251116_000001_create_users_table::Users;
+
+#[derive(DeriveIden)]
+pub enum McpServers {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Transport,
+    Command,
+    Args,
+    Env,
+    Url,
+    Headers,
+    Enabled,
+    AllowedTools,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(McpServers::Table)
+                .if_not_exists()
+                .col(ColumnDef::new(McpServers::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(McpServers::UserId).uuid().not_null())
+                .col(ColumnDef::new(McpServers::Name).string().not_null())
+                .col(ColumnDef::new(McpServers::Transport).string().not_null())
+                .col(ColumnDef::new(McpServers::Command).string().null())
+                .col(ColumnDef::new(McpServers::Args).json().null())
+                .col(ColumnDef::new(McpServers::Env).json().null())
+                .col(ColumnDef::new(McpServers::Url).string().null())
+                .col(ColumnDef::new(McpServers::Headers).json().null())
+                .col(ColumnDef::new(McpServers::Enabled).boolean().not_null().default(true))
+                .col(ColumnDef::new(McpServers::AllowedTools).json().null())
+                .col(ColumnDef::new(McpServers::CreatedAt).timestamp_with_time_zone().not_null()
+                    .default(Expr::current_timestamp()))
+                .col(ColumnDef::new(McpServers::UpdatedAt).timestamp_with_time_zone().not_null()
+                    .default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_mcp_servers_user_id")
+                        .from(McpServers::Table, McpServers::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                        .on_update(ForeignKeyAction::Cascade)
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_mcp_servers_user_id_name")
+                .table(McpServers::Table)
+                .col(McpServers::UserId)
+                .col(McpServers::Name)
+                .unique()
+                .to_owned()
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_index(
+            Index::drop()
+                .name("idx_mcp_servers_user_id_name")
+                .table(McpServers::Table)
+                .to_owned(),
+        ).await?;
+        manager.drop_table(Table::drop().table(McpServers::Table).to_owned()).await
+    }
+}
@@ -0,0 +1,80 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20251116_000001_create_users_table::Users,
+    m20251116_000004_create_conversations_tables::ConversationMessages,
+};
+
+#[derive(DeriveIden)]
+pub enum MessageAttachments {
+    Table,
+    Id,
+    UserId,
+    MessageId,
+    FileName,
+    MimeType,
+    Size,
+    Data,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(MessageAttachments::Table)
+                .if_not_exists()
+                .col(ColumnDef::new(MessageAttachments::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(MessageAttachments::UserId).uuid().not_null())
+                .col(ColumnDef::new(MessageAttachments::MessageId).uuid().null())
+                .col(ColumnDef::new(MessageAttachments::FileName).string().not_null())
+                .col(ColumnDef::new(MessageAttachments::MimeType).string().not_null())
+                .col(ColumnDef::new(MessageAttachments::Size).integer().not_null())
+                .col(ColumnDef::new(MessageAttachments::Data).binary().not_null())
+                .col(ColumnDef::new(MessageAttachments::CreatedAt).timestamp_with_time_zone().not_null()
+                    .default(Expr::current_timestamp()))
+                .col(ColumnDef::new(MessageAttachments::UpdatedAt).timestamp_with_time_zone().not_null()
+                    .default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_message_attachments_user_id")
+                        .from(MessageAttachments::Table, MessageAttachments::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                        .on_update(ForeignKeyAction::Cascade)
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_message_attachments_message_id")
+                        .from(MessageAttachments::Table, MessageAttachments::MessageId)
+                        .to(ConversationMessages::Table, ConversationMessages::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                        .on_update(ForeignKeyAction::Cascade)
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_message_attachments_message_id")
+                .table(MessageAttachments::Table)
+                .col(MessageAttachments::MessageId)
+                .to_owned()
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_index(
+            Index::drop()
+                .name("idx_message_attachments_message_id")
+                .table(MessageAttachments::Table)
+                .to_owned(),
+        ).await?;
+        manager.drop_table(Table::drop().table(MessageAttachments::Table).to_owned()).await
+    }
+}
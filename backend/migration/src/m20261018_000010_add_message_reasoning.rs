@@ -0,0 +1,39 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20251116_000004_create_conversations_tables::ConversationMessages;
+
+#[derive(DeriveIden)]
+enum ConversationMessagesReasoning {
+    Reasoning,
+    ReasoningTokens,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationMessages::Table)
+                    .add_column(ColumnDef::new(ConversationMessagesReasoning::Reasoning).text().null())
+                    .add_column(ColumnDef::new(ConversationMessagesReasoning::ReasoningTokens).integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationMessages::Table)
+                    .drop_column(ConversationMessagesReasoning::Reasoning)
+                    .drop_column(ConversationMessagesReasoning::ReasoningTokens)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20251116_000003_create_provider_models_table::ProviderModels,
+    m20251116_000004_create_conversations_tables::{ConversationMessages, ConversationSessions},
+};
+
+#[derive(DeriveIden)]
+enum GenerationParams {
+    GenerationParams,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProviderModels::Table)
+                    .add_column(ColumnDef::new(GenerationParams::GenerationParams).json().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationSessions::Table)
+                    .add_column(ColumnDef::new(GenerationParams::GenerationParams).json().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationMessages::Table)
+                    .add_column(ColumnDef::new(GenerationParams::GenerationParams).json().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationMessages::Table)
+                    .drop_column(GenerationParams::GenerationParams)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationSessions::Table)
+                    .drop_column(GenerationParams::GenerationParams)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProviderModels::Table)
+                    .drop_column(GenerationParams::GenerationParams)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20251116_000003_create_provider_models_table::ProviderModels,
+    m20251116_000004_create_conversations_tables::ConversationSessions,
+};
+
+#[derive(DeriveIden)]
+enum ProviderModelsContext {
+    ContextLength,
+}
+
+#[derive(DeriveIden)]
+enum ConversationSessionsContext {
+    ContextStrategy,
+    ContextSummary,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProviderModels::Table)
+                    .add_column(ColumnDef::new(ProviderModelsContext::ContextLength).integer().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationSessions::Table)
+                    .add_column(ColumnDef::new(ConversationSessionsContext::ContextStrategy).json().null())
+                    .add_column(ColumnDef::new(ConversationSessionsContext::ContextSummary).json().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationSessions::Table)
+                    .drop_column(ConversationSessionsContext::ContextStrategy)
+                    .drop_column(ConversationSessionsContext::ContextSummary)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ProviderModels::Table)
+                    .drop_column(ProviderModelsContext::ContextLength)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,97 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20251116_000004_create_conversations_tables::{ConversationMessages, ConversationSessions};
+
+#[derive(DeriveIden)]
+enum ConversationMessagesTree {
+    ParentId,
+}
+
+#[derive(DeriveIden)]
+enum ConversationSessionsTree {
+    ActiveMessageId,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationMessages::Table)
+                    .add_column(ColumnDef::new(ConversationMessagesTree::ParentId).uuid().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationSessions::Table)
+                    .add_column(ColumnDef::new(ConversationSessionsTree::ActiveMessageId).uuid().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_conversation_messages_parent_id")
+                    .table(ConversationMessages::Table)
+                    .col(ConversationMessagesTree::ParentId)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Existing conversations become a single branch, each message the
+        // child of the one before it, with the latest message active
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"UPDATE conversation_messages AS m SET parent_id = ordered.previous_id
+            FROM (
+                SELECT id, LAG(id) OVER (PARTITION BY session_id ORDER BY created_at, id) AS previous_id
+                FROM conversation_messages
+            ) AS ordered
+            WHERE m.id = ordered.id"#,
+        )
+        .await?;
+        db.execute_unprepared(
+            r#"UPDATE conversation_sessions AS s SET active_message_id = (
+                SELECT m.id FROM conversation_messages AS m
+                WHERE m.session_id = s.id
+                ORDER BY m.created_at DESC, m.id DESC
+                LIMIT 1
+            )"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_conversation_messages_parent_id")
+                    .table(ConversationMessages::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationSessions::Table)
+                    .drop_column(ConversationSessionsTree::ActiveMessageId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ConversationMessages::Table)
+                    .drop_column(ConversationMessagesTree::ParentId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,126 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20251116_000001_create_users_table::Users,
+    m20251116_000003_create_provider_models_table::ProviderModels,
+};
+
+#[derive(DeriveIden)]
+pub enum ApiKeys {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Prefix,
+    KeyHash,
+    LastUsedAt,
+    RevokedAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum ApiKeyUsage {
+    Table,
+    Id,
+    ApiKeyId,
+    ProviderModelId,
+    Model,
+    PromptTokens,
+    CompletionTokens,
+    ReasoningTokens,
+    Cost,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(ApiKeys::Table)
+                .if_not_exists()
+                .col(ColumnDef::new(ApiKeys::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(ApiKeys::UserId).uuid().not_null())
+                .col(ColumnDef::new(ApiKeys::Name).string().not_null())
+                .col(ColumnDef::new(ApiKeys::Prefix).string().not_null())
+                .col(ColumnDef::new(ApiKeys::KeyHash).string().not_null().unique_key())
+                .col(ColumnDef::new(ApiKeys::LastUsedAt).timestamp_with_time_zone().null())
+                .col(ColumnDef::new(ApiKeys::RevokedAt).timestamp_with_time_zone().null())
+                .col(ColumnDef::new(ApiKeys::CreatedAt).timestamp_with_time_zone().not_null()
+                    .default(Expr::current_timestamp()))
+                .col(ColumnDef::new(ApiKeys::UpdatedAt).timestamp_with_time_zone().not_null()
+                    .default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_api_keys_user_id")
+                        .from(ApiKeys::Table, ApiKeys::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                        .on_update(ForeignKeyAction::Cascade)
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_api_keys_user_id")
+                .table(ApiKeys::Table)
+                .col(ApiKeys::UserId)
+                .to_owned()
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(ApiKeyUsage::Table)
+                .if_not_exists()
+                .col(ColumnDef::new(ApiKeyUsage::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(ApiKeyUsage::ApiKeyId).uuid().not_null())
+                .col(ColumnDef::new(ApiKeyUsage::ProviderModelId).uuid().null())
+                .col(ColumnDef::new(ApiKeyUsage::Model).string().not_null())
+                .col(ColumnDef::new(ApiKeyUsage::PromptTokens).integer().null())
+                .col(ColumnDef::new(ApiKeyUsage::CompletionTokens).integer().null())
+                .col(ColumnDef::new(ApiKeyUsage::ReasoningTokens).integer().null())
+                .col(ColumnDef::new(ApiKeyUsage::Cost).decimal().null())
+                .col(ColumnDef::new(ApiKeyUsage::CreatedAt).timestamp_with_time_zone().not_null()
+                    .default(Expr::current_timestamp()))
+                .col(ColumnDef::new(ApiKeyUsage::UpdatedAt).timestamp_with_time_zone().not_null()
+                    .default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_api_key_usage_api_key_id")
+                        .from(ApiKeyUsage::Table, ApiKeyUsage::ApiKeyId)
+                        .to(ApiKeys::Table, ApiKeys::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                        .on_update(ForeignKeyAction::Cascade)
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_api_key_usage_provider_model_id")
+                        .from(ApiKeyUsage::Table, ApiKeyUsage::ProviderModelId)
+                        .to(ProviderModels::Table, ProviderModels::Id)
+                        .on_delete(ForeignKeyAction::SetNull)
+                        .on_update(ForeignKeyAction::Cascade)
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_api_key_usage_api_key_id_created_at")
+                .table(ApiKeyUsage::Table)
+                .col(ApiKeyUsage::ApiKeyId)
+                .col(ApiKeyUsage::CreatedAt)
+                .to_owned()
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(ApiKeyUsage::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(ApiKeys::Table).to_owned()).await
+    }
+}
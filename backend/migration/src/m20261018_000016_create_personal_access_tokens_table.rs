@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20251116_000001_create_users_table::Users;
+
+#[derive(DeriveIden)]
+pub enum PersonalAccessTokens {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Prefix,
+    TokenHash,
+    Scopes,
+    ExpiresAt,
+    LastUsedAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(PersonalAccessTokens::Table)
+                .if_not_exists()
+                .col(ColumnDef::new(PersonalAccessTokens::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(PersonalAccessTokens::UserId).uuid().not_null())
+                .col(ColumnDef::new(PersonalAccessTokens::Name).string().not_null())
+                .col(ColumnDef::new(PersonalAccessTokens::Prefix).string().not_null())
+                .col(ColumnDef::new(PersonalAccessTokens::TokenHash).string().not_null().unique_key())
+                .col(ColumnDef::new(PersonalAccessTokens::Scopes).json().not_null())
+                .col(ColumnDef::new(PersonalAccessTokens::ExpiresAt).timestamp_with_time_zone().null())
+                .col(ColumnDef::new(PersonalAccessTokens::LastUsedAt).timestamp_with_time_zone().null())
+                .col(ColumnDef::new(PersonalAccessTokens::CreatedAt).timestamp_with_time_zone().not_null()
+                    .default(Expr::current_timestamp()))
+                .col(ColumnDef::new(PersonalAccessTokens::UpdatedAt).timestamp_with_time_zone().not_null()
+                    .default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_personal_access_tokens_user_id")
+                        .from(PersonalAccessTokens::Table, PersonalAccessTokens::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                        .on_update(ForeignKeyAction::Cascade)
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_personal_access_tokens_user_id")
+                .table(PersonalAccessTokens::Table)
+                .col(PersonalAccessTokens::UserId)
+                .to_owned()
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(PersonalAccessTokens::Table).to_owned()).await
+    }
+}
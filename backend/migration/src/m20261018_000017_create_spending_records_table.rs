@@ -0,0 +1,105 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20251116_000001_create_users_table::Users;
+
+/// Every billed model call, kept apart from conversations and provider
+/// models so that deleting either doesn't give the spending back.
+#[derive(DeriveIden)]
+pub enum SpendingRecords {
+    Table,
+    Id,
+    UserId,
+    ProviderId,
+    ProviderModelId,
+    Model,
+    Source,
+    PromptTokens,
+    CompletionTokens,
+    Cost,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(SpendingRecords::Table)
+                .if_not_exists()
+                .col(ColumnDef::new(SpendingRecords::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(SpendingRecords::UserId).uuid().not_null())
+                .col(ColumnDef::new(SpendingRecords::ProviderId).uuid().null())
+                .col(ColumnDef::new(SpendingRecords::ProviderModelId).uuid().null())
+                .col(ColumnDef::new(SpendingRecords::Model).string().not_null())
+                .col(ColumnDef::new(SpendingRecords::Source).string().not_null())
+                .col(ColumnDef::new(SpendingRecords::PromptTokens).integer().null())
+                .col(ColumnDef::new(SpendingRecords::CompletionTokens).integer().null())
+                .col(ColumnDef::new(SpendingRecords::Cost).decimal().not_null())
+                .col(ColumnDef::new(SpendingRecords::CreatedAt).timestamp_with_time_zone().not_null()
+                    .default(Expr::current_timestamp()))
+                .col(ColumnDef::new(SpendingRecords::UpdatedAt).timestamp_with_time_zone().not_null()
+                    .default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .name("fk_spending_records_user_id")
+                        .from(SpendingRecords::Table, SpendingRecords::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade)
+                        .on_update(ForeignKeyAction::Cascade)
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_spending_records_user_id_created_at")
+                .table(SpendingRecords::Table)
+                .col(SpendingRecords::UserId)
+                .col(SpendingRecords::CreatedAt)
+                .to_owned()
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_spending_records_provider_id_created_at")
+                .table(SpendingRecords::Table)
+                .col(SpendingRecords::ProviderId)
+                .col(SpendingRecords::CreatedAt)
+                .to_owned()
+        ).await?;
+
+        // Spending so far is carried over from priced replies and API requests
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"INSERT INTO spending_records
+                (id, user_id, provider_id, provider_model_id, model, source, prompt_tokens, completion_tokens, cost, created_at, updated_at)
+            SELECT m.id, s.user_id, pm.provider_id, m.provider_model_id, COALESCE(pm.model_id, ''), 'reply',
+                m.prompt_tokens, m.completion_tokens, m.cost, m.created_at, m.created_at
+            FROM conversation_messages AS m
+            JOIN conversation_sessions AS s ON s.id = m.session_id
+            LEFT JOIN provider_models AS pm ON pm.id = m.provider_model_id
+            WHERE m.cost IS NOT NULL"#,
+        )
+        .await?;
+        db.execute_unprepared(
+            r#"INSERT INTO spending_records
+                (id, user_id, provider_id, provider_model_id, model, source, prompt_tokens, completion_tokens, cost, created_at, updated_at)
+            SELECT u.id, k.user_id, pm.provider_id, u.provider_model_id, u.model, 'api',
+                u.prompt_tokens, u.completion_tokens, u.cost, u.created_at, u.created_at
+            FROM api_key_usage AS u
+            JOIN api_keys AS k ON k.id = u.api_key_id
+            LEFT JOIN provider_models AS pm ON pm.id = u.provider_model_id
+            WHERE u.cost IS NOT NULL"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(SpendingRecords::Table).to_owned()).await
+    }
+}
@@ -0,0 +1,474 @@
+use async_trait::async_trait;
+use eventsource_stream::Eventsource;
+use futures::stream::{BoxStream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clients::llm_client::{ensure_success, into_event_stream, send_error, stream_error, ChatMessagePayload, ChatStreamEvent, FinishReason, LlmClient, StreamChunk, TokenUsage, ToolCallChunk, ToolDefinition},
+    error::{AppError, Result, UpstreamError},
+    models::{provider_model::GenerationParams, user_provider::Model as ProviderModel},
+};
+
+pub const ANTHROPIC_VERSION: &str = "2023-06-01";
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock {
+    Text { text: String },
+    Image { source: ImageSource },
+    ToolUse { id: String, name: String, input: serde_json::Value },
+    ToolResult { tool_use_id: String, content: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ImageSource {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    media_type: String,
+    data: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AnthropicMessage {
+    role: String,
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AnthropicTool<'a> {
+    name: &'a str,
+    description: &'a str,
+    input_schema: &'a serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Thinking {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    budget_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AnthropicRequestPayload<'a> {
+    model: String,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<AnthropicMessage>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<AnthropicTool<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<Thinking>,
+}
+
+/// Covers the `text_delta`, `thinking_delta` and `input_json_delta` content block deltas.
+#[derive(Debug, Deserialize)]
+struct TextDelta {
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    thinking: Option<String>,
+    #[serde(default)]
+    partial_json: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlockStart {
+    #[serde(rename = "type")]
+    kind: String,
+    id: Option<String>,
+    name: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MessageDeltaBody {
+    stop_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamError {
+    #[serde(rename = "type")]
+    kind: String,
+    message: String,
+}
+
+/// Counts Anthropic reports in `message_start` and again, cumulatively, in
+/// `message_delta`; later events may leave out fields that haven't changed.
+#[derive(Debug, Default, Deserialize)]
+struct AnthropicUsage {
+    input_tokens: Option<u32>,
+    cache_creation_input_tokens: Option<u32>,
+    cache_read_input_tokens: Option<u32>,
+    output_tokens: Option<u32>,
+}
+
+impl AnthropicUsage {
+    fn update(&mut self, other: AnthropicUsage) {
+        self.input_tokens = other.input_tokens.or(self.input_tokens);
+        self.cache_creation_input_tokens = other.cache_creation_input_tokens.or(self.cache_creation_input_tokens);
+        self.cache_read_input_tokens = other.cache_read_input_tokens.or(self.cache_read_input_tokens);
+        self.output_tokens = other.output_tokens.or(self.output_tokens);
+    }
+
+    /// `input_tokens` leaves out the cached part of the prompt, which counts
+    /// towards it but is priced apart.
+    fn total(&self) -> TokenUsage {
+        let cache_read_tokens = self.cache_read_input_tokens.unwrap_or_default();
+        let cache_write_tokens = self.cache_creation_input_tokens.unwrap_or_default();
+        TokenUsage {
+            prompt_tokens: self.input_tokens.unwrap_or_default() + cache_read_tokens + cache_write_tokens,
+            completion_tokens: self.output_tokens.unwrap_or_default(),
+            // Thinking is billed as output but not broken out in the usage
+            reasoning_tokens: 0,
+            cache_read_tokens,
+            cache_write_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageStart {
+    #[serde(default)]
+    usage: AnthropicUsage,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicStreamEvent {
+    MessageStart { message: MessageStart },
+    ContentBlockStart { index: usize, content_block: ContentBlockStart },
+    ContentBlockDelta { index: usize, delta: TextDelta },
+    MessageDelta {
+        #[serde(default)]
+        delta: MessageDeltaBody,
+        #[serde(default)]
+        usage: AnthropicUsage,
+    },
+    MessageStop,
+    Error { error: StreamError },
+    #[serde(other)]
+    Other,
+}
+
+/// Builds a URL under the Anthropic API root, accepting bases with or without `/v1`.
+pub fn endpoint(provider: &ProviderModel, path: &str) -> String {
+    let base = provider.url.trim_end_matches('/');
+    if base.ends_with("/v1") {
+        format!("{}/{}", base, path)
+    } else {
+        format!("{}/v1/{}", base, path)
+    }
+}
+
+pub fn authorize(req: reqwest::RequestBuilder, provider: &ProviderModel) -> reqwest::RequestBuilder {
+    let req = req.header("anthropic-version", ANTHROPIC_VERSION);
+    match provider.key.clone() {
+        Some(k) => req.header("x-api-key", k),
+        None => req,
+    }
+}
+
+/// Anthropic takes the system prompt as a top-level field and expects strictly
+/// alternating user/assistant turns, so system messages are hoisted and
+/// consecutive messages from the same role are merged. Tool results are sent
+/// as `tool_result` blocks in a user turn.
+fn split_messages(messages: Vec<ChatMessagePayload>) -> (Option<String>, Vec<AnthropicMessage>) {
+    let mut system_parts: Vec<String> = Vec::new();
+    let mut merged: Vec<AnthropicMessage> = Vec::new();
+
+    for message in messages {
+        if message.role == "system" {
+            system_parts.push(message.content);
+            continue;
+        }
+
+        let mut blocks = Vec::new();
+        let role = match message.tool_call_id {
+            Some(tool_use_id) => {
+                blocks.push(ContentBlock::ToolResult { tool_use_id, content: message.content });
+                "user".to_string()
+            }
+            None => {
+                for image in message.images {
+                    let source = ImageSource { kind: "base64", media_type: image.mime_type, data: image.data };
+                    blocks.push(ContentBlock::Image { source });
+                }
+                if !message.content.is_empty() {
+                    blocks.push(ContentBlock::Text { text: message.content });
+                }
+                message.role
+            }
+        };
+        for call in message.tool_calls {
+            let input = serde_json::from_str(&call.arguments).unwrap_or_else(|_| serde_json::json!({}));
+            blocks.push(ContentBlock::ToolUse { id: call.id, name: call.name, input });
+        }
+
+        match merged.last_mut() {
+            Some(last) if last.role == role => last.content.extend(blocks),
+            _ => merged.push(AnthropicMessage { role, content: blocks }),
+        }
+    }
+
+    let system = if system_parts.is_empty() { None } else { Some(system_parts.join("\n\n")) };
+    (system, merged)
+}
+
+/// Parses one event, keeping the running `usage` so every usage event carries the full total.
+fn parse_event(data: &str, usage: &mut AnthropicUsage) -> Result<StreamChunk> {
+    let event = serde_json::from_str::<AnthropicStreamEvent>(data).map_err(|e| {
+        AppError::Internal(format!("Failed to parse SSE data: {} | Data: {}", e, data))
+    })?;
+
+    match event {
+        AnthropicStreamEvent::MessageStart { message } => {
+            usage.update(message.usage);
+            Ok(StreamChunk::Events(vec![ChatStreamEvent::Usage(usage.total())]))
+        }
+        AnthropicStreamEvent::ContentBlockStart { index, content_block } if content_block.kind == "tool_use" => {
+            Ok(StreamChunk::Events(vec![ChatStreamEvent::ToolCall(ToolCallChunk {
+                index,
+                id: content_block.id,
+                name: content_block.name,
+                arguments: String::new(),
+            })]))
+        }
+        AnthropicStreamEvent::ContentBlockDelta { index, delta } => {
+            if let Some(partial_json) = delta.partial_json {
+                return Ok(StreamChunk::Events(vec![ChatStreamEvent::ToolCall(ToolCallChunk {
+                    index,
+                    id: None,
+                    name: None,
+                    arguments: partial_json,
+                })]));
+            }
+            match delta.thinking.filter(|t| !t.is_empty()) {
+                Some(thinking) => Ok(StreamChunk::Events(vec![ChatStreamEvent::Reasoning(thinking)])),
+                None => Ok(StreamChunk::text(delta.text.unwrap_or_default())),
+            }
+        }
+        AnthropicStreamEvent::MessageDelta { delta, usage: reported } => {
+            usage.update(reported);
+            let mut events = vec![ChatStreamEvent::Usage(usage.total())];
+            if let Some(reason) = delta.stop_reason {
+                events.push(ChatStreamEvent::Finish(FinishReason::parse(&reason)));
+            }
+            Ok(StreamChunk::Events(events))
+        }
+        AnthropicStreamEvent::MessageStop => Ok(StreamChunk::Stop),
+        AnthropicStreamEvent::Error { error } => Err(AppError::Upstream(UpstreamError {
+            status: error_status(&error.kind),
+            retry_after: None,
+            message: format!("Stream error: {} {}", error.kind, error.message),
+        })),
+        AnthropicStreamEvent::ContentBlockStart { .. } | AnthropicStreamEvent::Other => Ok(StreamChunk::skip()),
+    }
+}
+
+/// Maps the error types Anthropic sends inside a stream onto the HTTP status
+/// they would have had, so overload and rate limiting can be retried.
+fn error_status(kind: &str) -> Option<u16> {
+    Some(match kind {
+        "invalid_request_error" => 400,
+        "authentication_error" => 401,
+        "permission_error" => 403,
+        "not_found_error" => 404,
+        "request_too_large" => 413,
+        "rate_limit_error" => 429,
+        "overloaded_error" => 529,
+        _ => 500,
+    })
+}
+
+fn decode_events<S, B, E>(body: S) -> BoxStream<'static, Result<ChatStreamEvent>>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    let chunks = body.eventsource().scan(AnthropicUsage::default(), |usage, event| {
+        let chunk = match event {
+            Ok(event) => parse_event(&event.data, usage),
+            Err(e) => Err(stream_error(e)),
+        };
+        futures::future::ready(Some(chunk))
+    });
+    into_event_stream(chunks)
+}
+
+#[derive(Clone)]
+pub struct AnthropicLlmClient {
+    http: reqwest::Client,
+}
+
+impl AnthropicLlmClient {
+    pub fn new(http: reqwest::Client) -> Self {
+        Self { http }
+    }
+}
+
+#[async_trait]
+impl LlmClient for AnthropicLlmClient {
+    async fn chat(
+        &self,
+        provider: &ProviderModel,
+        model_id: &str,
+        messages: Vec<ChatMessagePayload>,
+        tools: &[ToolDefinition],
+        params: &GenerationParams,
+    ) -> Result<BoxStream<'static, Result<ChatStreamEvent>>> {
+        // A turn that continues after tool results would have to replay the signed
+        // thinking block that preceded the calls, which isn't stored, so it runs without
+        let continues_tools = messages.last().is_some_and(|m| m.tool_call_id.is_some());
+        let thinking = params
+            .reasoning_effort
+            .filter(|_| !continues_tools)
+            .map(|effort| Thinking { kind: "enabled", budget_tokens: effort.budget_tokens() });
+        let max_tokens = params
+            .max_tokens
+            .unwrap_or(DEFAULT_MAX_TOKENS + thinking.as_ref().map_or(0, |t| t.budget_tokens));
+
+        let (system, messages) = split_messages(messages);
+        let tools = tools
+            .iter()
+            .map(|t| AnthropicTool { name: &t.name, description: &t.description, input_schema: &t.parameters })
+            .collect();
+
+        let payload = AnthropicRequestPayload {
+            model: model_id.to_string(),
+            max_tokens,
+            system,
+            messages,
+            stream: true,
+            tools,
+            temperature: params.temperature,
+            top_p: params.top_p,
+            stop_sequences: params.stop.as_deref(),
+            thinking,
+        };
+
+        let req = self.http.post(endpoint(provider, "messages")).json(&payload);
+        let resp = authorize(req, provider)
+            .send()
+            .await
+            .map_err(send_error)?;
+        let resp = ensure_success(resp).await?;
+
+        Ok(decode_events(resp.bytes_stream()))
+    }
+
+    fn supports_tools(&self, _provider: &ProviderModel) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::provider_model, repositories::conversation_message_repo::MessageUsage};
+    use futures::stream;
+    use rust_decimal::Decimal;
+
+    fn priced_model() -> provider_model::Model {
+        let now = chrono::Utc::now().fixed_offset();
+        provider_model::Model {
+            id: uuid::Uuid::now_v7(),
+            provider_id: uuid::Uuid::now_v7(),
+            model_id: "claude-sonnet-4-5".to_string(),
+            name: "Claude Sonnet 4.5".to_string(),
+            input_price_per_million: Decimal::from(3),
+            output_price_per_million: Decimal::from(15),
+            generation_params: None,
+            context_length: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// A reply to a prompt that was partly written to and partly read from the cache.
+    const TRANSCRIPT: &str = concat!(
+        "event: message_start\n",
+        "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_01\",\"type\":\"message\",\"role\":\"assistant\",\"content\":[],\"model\":\"claude-sonnet-4-5\",\"stop_reason\":null,\"stop_sequence\":null,\"usage\":{\"input_tokens\":12,\"cache_creation_input_tokens\":1024,\"cache_read_input_tokens\":2048,\"output_tokens\":1}}}\n\n",
+        "event: content_block_start\n",
+        "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+        "event: ping\n",
+        "data: {\"type\":\"ping\"}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hello\"}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\" there\"}}\n\n",
+        "event: content_block_stop\n",
+        "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+        "event: message_delta\n",
+        "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\",\"stop_sequence\":null},\"usage\":{\"input_tokens\":12,\"output_tokens\":35}}\n\n",
+        "event: message_stop\n",
+        "data: {\"type\":\"message_stop\"}\n\n",
+    );
+
+    async fn replay(chunk_size: usize) -> Vec<ChatStreamEvent> {
+        let chunks: Vec<std::result::Result<Vec<u8>, std::io::Error>> = TRANSCRIPT
+            .as_bytes()
+            .chunks(chunk_size)
+            .map(|c| Ok(c.to_vec()))
+            .collect();
+        decode_events(stream::iter(chunks))
+            .map(|event| event.expect("event"))
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn prices_cached_prompt_tokens_at_their_own_rates() {
+        for chunk_size in [7, 64, TRANSCRIPT.len()] {
+            let events = replay(chunk_size).await;
+
+            let mut usage = TokenUsage::default();
+            let mut text = String::new();
+            for event in &events {
+                match event {
+                    ChatStreamEvent::Usage(u) => usage.merge(*u),
+                    ChatStreamEvent::Delta(d) => text.push_str(d),
+                    _ => {}
+                }
+            }
+
+            assert_eq!(text, "Hello there");
+            assert_eq!(
+                usage,
+                TokenUsage {
+                    prompt_tokens: 12 + 1024 + 2048,
+                    completion_tokens: 35,
+                    reasoning_tokens: 0,
+                    cache_read_tokens: 2048,
+                    cache_write_tokens: 1024,
+                }
+            );
+
+            // $3 and $15 per million; cache reads at 0.1x and writes at 1.25x the input price
+            let cost = MessageUsage::priced(&priced_model(), usage).cost;
+            let expected = (Decimal::from(12 * 3 + 35 * 15) + Decimal::new(2048 * 3, 1) + Decimal::new(1024 * 3 * 125, 2))
+                / Decimal::from(1_000_000);
+            assert_eq!(cost, expected);
+            assert!(events.iter().any(|e| matches!(e, ChatStreamEvent::Finish(FinishReason::Stop))));
+        }
+    }
+
+    #[test]
+    fn later_usage_events_keep_the_cached_prompt_tokens() {
+        let mut usage = AnthropicUsage::default();
+        usage.update(AnthropicUsage { input_tokens: Some(5), cache_read_input_tokens: Some(100), ..Default::default() });
+        usage.update(AnthropicUsage { input_tokens: Some(5), output_tokens: Some(9), ..Default::default() });
+        assert_eq!(
+            usage.total(),
+            TokenUsage { prompt_tokens: 105, completion_tokens: 9, cache_read_tokens: 100, ..Default::default() }
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use futures::stream::{self, Stream, StreamExt};
+
+use crate::{
+    clients::llm_client::stream_error,
+    error::{AppError, Result},
+};
+
+const PRELUDE_LEN: usize = 12;
+const TRAILER_LEN: usize = 4;
+
+/// A decoded `application/vnd.amazon.eventstream` message. Only string-typed
+/// headers are kept since those carry the message and event types.
+#[derive(Debug)]
+pub struct EventMessage {
+    pub headers: HashMap<String, String>,
+    pub payload: Vec<u8>,
+}
+
+impl EventMessage {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+fn read_u16(buf: &[u8], at: usize) -> Result<u16> {
+    buf.get(at..at + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(|| AppError::Internal("Truncated event stream header".to_string()))
+}
+
+fn read_u32(buf: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
+
+fn parse_headers(mut buf: &[u8]) -> Result<HashMap<String, String>> {
+    let truncated = || AppError::Internal("Truncated event stream header".to_string());
+    let mut headers = HashMap::new();
+
+    while !buf.is_empty() {
+        let name_len = buf[0] as usize;
+        let name = buf.get(1..1 + name_len).ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(name).to_string();
+        let mut at = 1 + name_len;
+        let value_type = *buf.get(at).ok_or_else(truncated)?;
+        at += 1;
+
+        let value_len = match value_type {
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            9 => 16,
+            6 | 7 => {
+                let len = read_u16(buf, at)? as usize;
+                at += 2;
+                len
+            }
+            other => return Err(AppError::Internal(format!("Unknown event stream header type: {}", other))),
+        };
+        let value = buf.get(at..at + value_len).ok_or_else(truncated)?;
+        if value_type == 7 {
+            headers.insert(name, String::from_utf8_lossy(value).to_string());
+        }
+        buf = &buf[at + value_len..];
+    }
+
+    Ok(headers)
+}
+
+/// Pops one complete message off the front of `buf`, if enough bytes have arrived.
+fn decode_message(buf: &mut Vec<u8>) -> Result<Option<EventMessage>> {
+    if buf.len() < PRELUDE_LEN {
+        return Ok(None);
+    }
+
+    let total_len = read_u32(buf, 0) as usize;
+    let headers_len = read_u32(buf, 4) as usize;
+    let prelude_crc = read_u32(buf, 8);
+
+    if crc32fast::hash(&buf[..8]) != prelude_crc {
+        return Err(AppError::Internal("Event stream prelude checksum mismatch".to_string()));
+    }
+    if total_len < PRELUDE_LEN + headers_len + TRAILER_LEN {
+        return Err(AppError::Internal("Invalid event stream message length".to_string()));
+    }
+    if buf.len() < total_len {
+        return Ok(None);
+    }
+
+    let message_crc = read_u32(buf, total_len - TRAILER_LEN);
+    if crc32fast::hash(&buf[..total_len - TRAILER_LEN]) != message_crc {
+        return Err(AppError::Internal("Event stream message checksum mismatch".to_string()));
+    }
+
+    let message: Vec<u8> = buf.drain(..total_len).collect();
+    let headers = parse_headers(&message[PRELUDE_LEN..PRELUDE_LEN + headers_len])?;
+    let payload = message[PRELUDE_LEN + headers_len..total_len - TRAILER_LEN].to_vec();
+
+    Ok(Some(EventMessage { headers, payload }))
+}
+
+/// Decodes the binary AWS event stream framing of a response body.
+pub fn decode(resp: reqwest::Response) -> impl Stream<Item = Result<EventMessage>> {
+    decode_bytes(resp.bytes_stream())
+}
+
+fn decode_bytes<S, B, E>(bytes: S) -> impl Stream<Item = Result<EventMessage>>
+where
+    S: Stream<Item = std::result::Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    let bytes = Box::pin(bytes);
+    stream::unfold((bytes, Vec::new(), false), |(mut bytes, mut buf, done)| async move {
+        if done {
+            return None;
+        }
+        loop {
+            match decode_message(&mut buf) {
+                Ok(Some(message)) => return Some((Ok(message), (bytes, buf, false))),
+                Ok(None) => {}
+                Err(e) => return Some((Err(e), (bytes, buf, true))),
+            }
+            match bytes.next().await {
+                Some(Ok(chunk)) => buf.extend_from_slice(chunk.as_ref()),
+                Some(Err(e)) => {
+                    return Some((Err(stream_error(e)), (bytes, buf, true)))
+                }
+                None if buf.is_empty() => return None,
+                None => {
+                    return Some((
+                        Err(AppError::Internal("Event stream ended mid-message".to_string())),
+                        (bytes, buf, true),
+                    ))
+                }
+            }
+        }
+    })
+}
+
+
+/// Frames a message with string headers, as Bedrock would send it.
+#[cfg(test)]
+pub fn encode(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+    let mut encoded_headers = Vec::new();
+    for (name, value) in headers {
+        encoded_headers.push(name.len() as u8);
+        encoded_headers.extend_from_slice(name.as_bytes());
+        encoded_headers.push(7);
+        encoded_headers.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        encoded_headers.extend_from_slice(value.as_bytes());
+    }
+
+    let total_len = PRELUDE_LEN + encoded_headers.len() + payload.len() + TRAILER_LEN;
+    let mut message = Vec::with_capacity(total_len);
+    message.extend_from_slice(&(total_len as u32).to_be_bytes());
+    message.extend_from_slice(&(encoded_headers.len() as u32).to_be_bytes());
+    message.extend_from_slice(&crc32fast::hash(&message).to_be_bytes());
+    message.extend_from_slice(&encoded_headers);
+    message.extend_from_slice(payload);
+    message.extend_from_slice(&crc32fast::hash(&message).to_be_bytes());
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, payload: &str) -> Vec<u8> {
+        encode(&[(":message-type", "event"), (":event-type", event_type)], payload.as_bytes())
+    }
+
+    async fn decode_chunks(chunks: Vec<Vec<u8>>) -> Vec<Result<EventMessage>> {
+        let chunks = chunks.into_iter().map(Ok::<_, std::io::Error>);
+        decode_bytes(stream::iter(chunks)).collect().await
+    }
+
+    #[tokio::test]
+    async fn decodes_messages_in_one_chunk() {
+        let mut body = event("contentBlockDelta", r#"{"delta":{"text":"Hi"}}"#);
+        body.extend(event("messageStop", r#"{"stopReason":"end_turn"}"#));
+
+        let messages: Vec<EventMessage> = decode_chunks(vec![body]).await.into_iter().map(|m| m.unwrap()).collect();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].header(":event-type"), Some("contentBlockDelta"));
+        assert_eq!(messages[0].payload, br#"{"delta":{"text":"Hi"}}"#);
+        assert_eq!(messages[1].header(":event-type"), Some("messageStop"));
+    }
+
+    #[tokio::test]
+    async fn reassembles_a_frame_split_across_chunks() {
+        let frame = event("contentBlockDelta", r#"{"delta":{"text":"split"}}"#);
+        // Splits inside the prelude, the headers and the payload
+        let chunks = vec![frame[..5].to_vec(), frame[5..20].to_vec(), frame[20..40].to_vec(), frame[40..].to_vec()];
+
+        let messages = decode_chunks(chunks).await;
+        assert_eq!(messages.len(), 1);
+        let message = messages.into_iter().next().unwrap().unwrap();
+        assert_eq!(message.header(":message-type"), Some("event"));
+        assert_eq!(message.payload, br#"{"delta":{"text":"split"}}"#);
+    }
+
+    #[tokio::test]
+    async fn rejects_a_prelude_checksum_mismatch() {
+        let mut frame = event("messageStop", "{}");
+        frame[8] ^= 0xff;
+
+        let messages = decode_chunks(vec![frame]).await;
+        assert_eq!(messages.len(), 1);
+        let error = messages.into_iter().next().unwrap().unwrap_err();
+        assert!(error.to_string().contains("prelude checksum mismatch"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn rejects_a_message_checksum_mismatch() {
+        let mut frame = event("messageStop", "{}");
+        let payload_at = frame.len() - TRAILER_LEN - 1;
+        frame[payload_at] ^= 0xff;
+
+        let messages = decode_chunks(vec![frame]).await;
+        assert_eq!(messages.len(), 1);
+        let error = messages.into_iter().next().unwrap().unwrap_err();
+        assert!(error.to_string().contains("message checksum mismatch"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn reports_a_body_that_ends_mid_message() {
+        let frame = event("messageStop", "{}");
+        let messages = decode_chunks(vec![frame[..frame.len() - 3].to_vec()]).await;
+        assert_eq!(messages.len(), 1);
+        assert!(messages.into_iter().next().unwrap().is_err());
+    }
+}
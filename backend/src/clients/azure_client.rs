@@ -0,0 +1,70 @@
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+
+use crate::{
+    clients::llm_client::{stream_chat_completions, ChatMessagePayload, ChatRequestPayload, ChatStreamEvent, LlmClient, ToolDefinition},
+    error::Result,
+    models::{provider_model::GenerationParams, user_provider::Model as ProviderModel},
+};
+
+pub const DEFAULT_API_VERSION: &str = "2024-10-21";
+
+fn api_version(provider: &ProviderModel) -> String {
+    provider
+        .options
+        .as_ref()
+        .and_then(|o| o.api_version.clone())
+        .unwrap_or_else(|| DEFAULT_API_VERSION.to_string())
+}
+
+/// Builds a URL under the resource's `/openai` root with the configured `api-version`.
+pub fn endpoint(provider: &ProviderModel, path: &str) -> String {
+    let base = provider.url.trim_end_matches('/');
+    let base = base.strip_suffix("/openai").unwrap_or(base);
+    format!("{}/openai/{}?api-version={}", base, path, api_version(provider))
+}
+
+/// Azure addresses models by deployment, so `provider_model.model_id` holds the deployment name.
+pub fn deployment_endpoint(provider: &ProviderModel, deployment: &str, path: &str) -> String {
+    endpoint(provider, &format!("deployments/{}/{}", deployment, path))
+}
+
+pub fn authorize(req: reqwest::RequestBuilder, provider: &ProviderModel) -> reqwest::RequestBuilder {
+    match provider.key.clone() {
+        Some(k) => req.header("api-key", k),
+        None => req,
+    }
+}
+
+#[derive(Clone)]
+pub struct AzureLlmClient {
+    http: reqwest::Client,
+}
+
+impl AzureLlmClient {
+    pub fn new(http: reqwest::Client) -> Self {
+        Self { http }
+    }
+}
+
+#[async_trait]
+impl LlmClient for AzureLlmClient {
+    async fn chat(
+        &self,
+        provider: &ProviderModel,
+        model_id: &str,
+        messages: Vec<ChatMessagePayload>,
+        tools: &[ToolDefinition],
+        params: &GenerationParams,
+    ) -> Result<BoxStream<'static, Result<ChatStreamEvent>>> {
+        let url = deployment_endpoint(provider, model_id, "chat/completions");
+        let payload = ChatRequestPayload::streaming(provider, model_id, messages, tools, params);
+
+        let req = authorize(self.http.post(url).json(&payload), provider);
+        stream_chat_completions(req).await
+    }
+
+    fn supports_tools(&self, _provider: &ProviderModel) -> bool {
+        true
+    }
+}
@@ -0,0 +1,448 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::stream::{BoxStream, StreamExt};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clients::{
+        aws_event_stream::{self, EventMessage},
+        llm_client::{ensure_success, into_event_stream, send_error, ChatMessagePayload, ChatStreamEvent, FinishReason, LlmClient, StreamChunk, TokenUsage, ToolDefinition},
+        sigv4::{uri_encode, AwsCredentials, Signer},
+    },
+    error::{AppError, Result, UpstreamError},
+    models::{provider_model::GenerationParams, user_provider::Model as ProviderModel},
+};
+
+const SIGNING_SERVICE: &str = "bedrock";
+
+#[derive(Debug, Clone, Serialize)]
+struct TextBlock {
+    text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ImageSource {
+    bytes: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ImageBlock {
+    format: String,
+    source: ImageSource,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum ContentBlock {
+    Text(String),
+    Image(ImageBlock),
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ConverseMessage {
+    role: String,
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InferenceConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConverseStreamRequest {
+    messages: Vec<ConverseMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    system: Vec<TextBlock>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inference_config: Option<InferenceConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReasoningDelta {
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeltaText {
+    text: Option<String>,
+    reasoning_content: Option<ReasoningDelta>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlockDelta {
+    delta: DeltaText,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConverseUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConverseMetadata {
+    usage: Option<ConverseUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageStop {
+    stop_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExceptionPayload {
+    message: Option<String>,
+}
+
+/// Resolves the signing region from the provider options, falling back to the
+/// `bedrock-runtime.{region}.amazonaws.com` host name.
+pub fn region(provider: &ProviderModel) -> Result<String> {
+    if let Some(region) = provider.options.as_ref().and_then(|o| o.region.clone()) {
+        return Ok(region);
+    }
+
+    let url = reqwest::Url::parse(&provider.url)
+        .map_err(|e| AppError::BadRequest(format!("Invalid provider URL: {}", e)))?;
+    let host = url.host_str().unwrap_or_default();
+    let mut labels = host.split('.');
+    match (labels.next(), labels.next()) {
+        (Some(service), Some(region)) if service.starts_with("bedrock") => Ok(region.to_string()),
+        _ => Err(AppError::BadRequest("AWS region is not configured for provider".to_string())),
+    }
+}
+
+/// Signs and sends a request to a Bedrock endpoint.
+pub async fn send_signed(
+    http: &reqwest::Client,
+    provider: &ProviderModel,
+    method: reqwest::Method,
+    url: &str,
+    body: Vec<u8>,
+    headers: HeaderMap,
+) -> Result<reqwest::Response> {
+    let url = reqwest::Url::parse(url).map_err(|e| AppError::BadRequest(format!("Invalid provider URL: {}", e)))?;
+    let credentials = AwsCredentials::from_key(provider.key.as_deref())?;
+    let region = region(provider)?;
+    let signer = Signer { credentials: &credentials, region: &region, service: SIGNING_SERVICE };
+    let headers = signer.sign(method.as_str(), &url, &headers, &body, Utc::now())?;
+
+    http.request(method, url)
+        .headers(headers)
+        .body(body)
+        .send()
+        .await
+        .map_err(send_error)
+}
+
+/// The control-plane endpoint lives on `bedrock.` rather than `bedrock-runtime.`.
+pub fn control_plane_endpoint(provider: &ProviderModel, path: &str) -> String {
+    let base = provider.url.trim_end_matches('/').replacen("bedrock-runtime", "bedrock", 1);
+    format!("{}/{}", base, path)
+}
+
+/// Converse expects alternating user/assistant turns and takes the system
+/// prompt separately, so system messages are hoisted and same-role turns merged.
+fn build_request(messages: Vec<ChatMessagePayload>, params: &GenerationParams) -> ConverseStreamRequest {
+    let mut system = Vec::new();
+    let mut merged: Vec<ConverseMessage> = Vec::new();
+
+    for message in messages {
+        if message.role == "system" {
+            system.push(TextBlock { text: message.content });
+            continue;
+        }
+        // Images go first, as the Converse docs recommend; the format is the MIME subtype
+        let mut content: Vec<ContentBlock> = message
+            .images
+            .into_iter()
+            .map(|image| ContentBlock::Image(ImageBlock {
+                format: image.mime_type.trim_start_matches("image/").to_string(),
+                source: ImageSource { bytes: image.data },
+            }))
+            .collect();
+        if content.is_empty() || !message.content.is_empty() {
+            content.push(ContentBlock::Text(message.content));
+        }
+        match merged.last_mut() {
+            Some(last) if last.role == message.role => last.content.extend(content),
+            _ => merged.push(ConverseMessage { role: message.role, content }),
+        }
+    }
+
+    let inference_config = (!params.is_empty()).then(|| InferenceConfig {
+        max_tokens: params.max_tokens,
+        temperature: params.temperature,
+        top_p: params.top_p,
+        stop_sequences: params.stop.clone(),
+    });
+
+    ConverseStreamRequest { messages: merged, system, inference_config }
+}
+
+/// Exceptions raised mid-stream carry no HTTP status; these are the ones the
+/// synchronous API documents for each type.
+fn exception_status(kind: &str) -> u16 {
+    match kind {
+        "validationException" => 400,
+        "throttlingException" => 429,
+        "serviceUnavailableException" => 503,
+        "modelNotReadyException" => 503,
+        "modelTimeoutException" => 408,
+        _ => 500,
+    }
+}
+
+fn parse_message(message: &EventMessage) -> Result<StreamChunk> {
+    let payload = String::from_utf8_lossy(&message.payload);
+
+    if message.header(":message-type") == Some("exception") {
+        let detail = serde_json::from_str::<ExceptionPayload>(&payload)
+            .ok()
+            .and_then(|p| p.message)
+            .unwrap_or_else(|| payload.to_string());
+        let kind = message.header(":exception-type").unwrap_or("exception");
+        return Err(AppError::Upstream(UpstreamError {
+            status: Some(exception_status(kind)),
+            retry_after: None,
+            message: format!("Stream error: {} {}", kind, detail),
+        }));
+    }
+
+    match message.header(":event-type") {
+        Some("contentBlockDelta") => {
+            let parsed = serde_json::from_str::<ContentBlockDelta>(&payload).map_err(|e| {
+                AppError::Internal(format!("Failed to parse event data: {} | Data: {}", e, payload))
+            })?;
+            if let Some(reasoning) = parsed.delta.reasoning_content.and_then(|r| r.text).filter(|t| !t.is_empty()) {
+                return Ok(StreamChunk::Events(vec![ChatStreamEvent::Reasoning(reasoning)]));
+            }
+            Ok(StreamChunk::text(parsed.delta.text.unwrap_or_default()))
+        }
+        Some("messageStop") => {
+            let parsed = serde_json::from_str::<MessageStop>(&payload).map_err(|e| {
+                AppError::Internal(format!("Failed to parse event data: {} | Data: {}", e, payload))
+            })?;
+            Ok(StreamChunk::Events(vec![ChatStreamEvent::Finish(FinishReason::parse(
+                parsed.stop_reason.as_deref().unwrap_or("end_turn"),
+            ))]))
+        }
+        // Usage arrives in the metadata event after messageStop, so the stream runs to its end
+        Some("metadata") => {
+            let parsed = serde_json::from_str::<ConverseMetadata>(&payload).map_err(|e| {
+                AppError::Internal(format!("Failed to parse event data: {} | Data: {}", e, payload))
+            })?;
+            Ok(StreamChunk::Events(
+                parsed
+                    .usage
+                    .map(|u| ChatStreamEvent::Usage(TokenUsage {
+                        prompt_tokens: u.input_tokens,
+                        completion_tokens: u.output_tokens,
+                        ..Default::default()
+                    }))
+                    .into_iter()
+                    .collect(),
+            ))
+        }
+        _ => Ok(StreamChunk::skip()),
+    }
+}
+
+#[derive(Clone)]
+pub struct BedrockLlmClient {
+    http: reqwest::Client,
+}
+
+impl BedrockLlmClient {
+    pub fn new(http: reqwest::Client) -> Self {
+        Self { http }
+    }
+}
+
+#[async_trait]
+impl LlmClient for BedrockLlmClient {
+    async fn chat(
+        &self,
+        provider: &ProviderModel,
+        model_id: &str,
+        messages: Vec<ChatMessagePayload>,
+        _tools: &[ToolDefinition],
+        params: &GenerationParams,
+    ) -> Result<BoxStream<'static, Result<ChatStreamEvent>>> {
+        let url = format!(
+            "{}/model/{}/converse-stream",
+            provider.url.trim_end_matches('/'),
+            uri_encode(model_id)
+        );
+        let body = serde_json::to_vec(&build_request(messages, params))?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(ACCEPT, HeaderValue::from_static("application/vnd.amazon.eventstream"));
+
+        let resp = send_signed(&self.http, provider, reqwest::Method::POST, &url, body, headers).await?;
+        let resp = ensure_success(resp).await?;
+
+        let chunks = aws_event_stream::decode(resp).map(|message| message.and_then(|m| parse_message(&m)));
+
+        Ok(into_event_stream(chunks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Bytes,
+        http::{HeaderMap as AxumHeaderMap, Method, StatusCode, Uri},
+        response::IntoResponse,
+        routing::any,
+        Router,
+    };
+    use chrono::NaiveDateTime;
+    use reqwest::header::{HeaderName, AUTHORIZATION};
+    use uuid::Uuid;
+
+    use crate::models::user_provider::{ProviderOptions, ProviderType};
+
+    const ACCESS_KEY_ID: &str = "AKIDEXAMPLE";
+    const SECRET_ACCESS_KEY: &str = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+    const MODEL_ID: &str = "anthropic.claude-3-haiku-20240307-v1:0";
+
+    /// Recomputes the signature the way AWS does, from the headers the client
+    /// says it signed, and answers with a short reply when it matches.
+    async fn mock_bedrock(method: Method, uri: Uri, headers: AxumHeaderMap, body: Bytes) -> impl IntoResponse {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+        let authorization = header("authorization");
+        let signed_headers = authorization
+            .split("SignedHeaders=")
+            .nth(1)
+            .and_then(|rest| rest.split(',').next())
+            .unwrap_or_default();
+
+        let mut extra = HeaderMap::new();
+        for name in signed_headers.split(';') {
+            if matches!(name, "host" | "x-amz-date" | "x-amz-security-token") {
+                continue;
+            }
+            if let Some(value) = headers.get(name) {
+                extra.insert(HeaderName::from_bytes(name.as_bytes()).unwrap(), value.clone());
+            }
+        }
+
+        let signed_at = NaiveDateTime::parse_from_str(&header("x-amz-date"), "%Y%m%dT%H%M%SZ").unwrap().and_utc();
+        let url = format!("http://{}{}", header("host"), uri).parse().unwrap();
+        let credentials = AwsCredentials {
+            access_key_id: ACCESS_KEY_ID.to_string(),
+            secret_access_key: SECRET_ACCESS_KEY.to_string(),
+            session_token: None,
+        };
+        let signer = Signer { credentials: &credentials, region: "us-east-1", service: SIGNING_SERVICE };
+        let expected = signer.sign(method.as_str(), &url, &extra, &body, signed_at).unwrap();
+
+        if expected.get(AUTHORIZATION).map(|v| v.to_str().unwrap()) != Some(authorization.as_str()) {
+            let message = r#"{"message":"The request signature we calculated does not match the signature you provided."}"#;
+            return (StatusCode::FORBIDDEN, message.as_bytes().to_vec()).into_response();
+        }
+        if uri.path() != format!("/model/{}/converse-stream", uri_encode(MODEL_ID)) {
+            return StatusCode::NOT_FOUND.into_response();
+        }
+
+        let event = |event_type: &str, payload: &str| {
+            aws_event_stream::encode(&[(":message-type", "event"), (":event-type", event_type)], payload.as_bytes())
+        };
+        let mut reply = event("messageStart", r#"{"role":"assistant"}"#);
+        reply.extend(event("contentBlockDelta", r#"{"contentBlockIndex":0,"delta":{"text":"Hello"}}"#));
+        reply.extend(event("contentBlockDelta", r#"{"contentBlockIndex":0,"delta":{"text":" world"}}"#));
+        reply.extend(event("messageStop", r#"{"stopReason":"end_turn"}"#));
+        reply.extend(event("metadata", r#"{"usage":{"inputTokens":11,"outputTokens":2,"totalTokens":13}}"#));
+        ([(CONTENT_TYPE, "application/vnd.amazon.eventstream")], reply).into_response()
+    }
+
+    async fn start_mock() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().fallback(any(mock_bedrock))).await.unwrap();
+        });
+        format!("http://{}", addr)
+    }
+
+    fn provider(url: String, key: &str) -> ProviderModel {
+        let now = Utc::now().into();
+        ProviderModel {
+            id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            name: "Bedrock".to_string(),
+            provider_type: ProviderType::Bedrock,
+            url,
+            key: Some(key.to_string()),
+            options: Some(ProviderOptions { region: Some("us-east-1".to_string()), ..Default::default() }),
+            budget: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    async fn chat(provider: &ProviderModel) -> Result<Vec<ChatStreamEvent>> {
+        let client = BedrockLlmClient::new(reqwest::Client::new());
+        let messages = vec![
+            ChatMessagePayload::text("system", "Be brief.".to_string()),
+            ChatMessagePayload::text("user", "Say hello".to_string()),
+        ];
+        let stream = client.chat(provider, MODEL_ID, messages, &[], &GenerationParams::default()).await?;
+        stream.collect::<Vec<_>>().await.into_iter().collect()
+    }
+
+    #[tokio::test]
+    async fn streams_a_reply_from_a_signature_checking_endpoint() {
+        let url = start_mock().await;
+        let events = chat(&provider(url, &format!("{}:{}", ACCESS_KEY_ID, SECRET_ACCESS_KEY))).await.unwrap();
+
+        let text: String = events
+            .iter()
+            .filter_map(|e| match e {
+                ChatStreamEvent::Delta(d) => Some(d.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, "Hello world");
+        assert!(events.iter().any(|e| matches!(e, ChatStreamEvent::Finish(FinishReason::Stop))));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            ChatStreamEvent::Usage(TokenUsage { prompt_tokens: 11, completion_tokens: 2, .. })
+        )));
+    }
+
+    #[tokio::test]
+    async fn wrong_secret_is_rejected() {
+        let url = start_mock().await;
+        let error = chat(&provider(url, &format!("{}:not-the-secret", ACCESS_KEY_ID))).await.unwrap_err();
+        match error {
+            AppError::Upstream(e) => assert_eq!(e.status, Some(403)),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reads_region_from_host() {
+        let mut provider = provider("https://bedrock-runtime.eu-west-1.amazonaws.com".to_string(), "a:b");
+        provider.options = None;
+        assert_eq!(region(&provider).unwrap(), "eu-west-1");
+    }
+}
@@ -0,0 +1,258 @@
+use async_trait::async_trait;
+use eventsource_stream::Eventsource;
+use futures::stream::{BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clients::llm_client::{ensure_success, into_event_stream, send_error, stream_error, ChatMessagePayload, ChatStreamEvent, FinishReason, LlmClient, StreamChunk, TokenUsage, ToolDefinition},
+    error::{AppError, Result},
+    models::{provider_model::GenerationParams, user_provider::Model as ProviderModel},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InlineData {
+    mime_type: String,
+    data: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Part {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inline_data: Option<InlineData>,
+    /// Marks a summary of the model's thinking rather than answer text.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    thought: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Content {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ThinkingConfig {
+    include_thoughts: bool,
+    thinking_budget: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking_config: Option<ThinkingConfig>,
+}
+
+impl From<&GenerationParams> for GenerationConfig {
+    fn from(params: &GenerationParams) -> Self {
+        Self {
+            temperature: params.temperature,
+            top_p: params.top_p,
+            max_output_tokens: params.max_tokens,
+            stop_sequences: params.stop.clone(),
+            presence_penalty: params.presence_penalty,
+            frequency_penalty: params.frequency_penalty,
+            seed: params.seed,
+            thinking_config: params
+                .reasoning_effort
+                .map(|effort| ThinkingConfig { include_thoughts: true, thinking_budget: effort.budget_tokens() }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateContentRequest {
+    contents: Vec<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generation_config: Option<GenerationConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CandidateContent {
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Candidate {
+    content: Option<CandidateContent>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u32,
+    #[serde(default)]
+    candidates_token_count: u32,
+    #[serde(default)]
+    thoughts_token_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateContentResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    usage_metadata: Option<UsageMetadata>,
+}
+
+/// Builds a URL under the Gemini API root, accepting bases with or without a version segment.
+pub fn endpoint(provider: &ProviderModel, path: &str) -> String {
+    let base = provider.url.trim_end_matches('/');
+    if base.ends_with("/v1beta") || base.ends_with("/v1") {
+        format!("{}/{}", base, path)
+    } else {
+        format!("{}/v1beta/{}", base, path)
+    }
+}
+
+pub fn authorize(req: reqwest::RequestBuilder, provider: &ProviderModel) -> reqwest::RequestBuilder {
+    match provider.key.clone() {
+        Some(k) => req.header("x-goog-api-key", k),
+        None => req,
+    }
+}
+
+/// Gemini names the assistant role `model` and takes the system prompt
+/// as a separate `systemInstruction` rather than as part of `contents`.
+fn build_request(messages: Vec<ChatMessagePayload>, params: &GenerationParams) -> GenerateContentRequest {
+    let mut system_parts: Vec<Part> = Vec::new();
+    let mut contents: Vec<Content> = Vec::new();
+
+    for message in messages {
+        let role = match message.role.as_str() {
+            "system" => {
+                system_parts.push(Part { text: Some(message.content), ..Default::default() });
+                continue;
+            }
+            "assistant" => "model",
+            _ => "user",
+        };
+        let mut parts: Vec<Part> = message
+            .images
+            .into_iter()
+            .map(|image| Part {
+                inline_data: Some(InlineData { mime_type: image.mime_type, data: image.data }),
+                ..Default::default()
+            })
+            .collect();
+        if parts.is_empty() || !message.content.is_empty() {
+            parts.push(Part { text: Some(message.content), ..Default::default() });
+        }
+        contents.push(Content { role: Some(role.to_string()), parts });
+    }
+
+    let system_instruction = if system_parts.is_empty() {
+        None
+    } else {
+        Some(Content { role: None, parts: system_parts })
+    };
+
+    let generation_config = (!params.is_empty()).then(|| GenerationConfig::from(params));
+
+    GenerateContentRequest { contents, system_instruction, generation_config }
+}
+
+fn parse_event(data: &str) -> Result<StreamChunk> {
+    let parsed = serde_json::from_str::<GenerateContentResponse>(data).map_err(|e| {
+        AppError::Internal(format!("Failed to parse SSE data: {} | Data: {}", e, data))
+    })?;
+
+    let candidate = parsed.candidates.into_iter().next();
+    let finish_reason = candidate.as_ref().and_then(|c| c.finish_reason.clone());
+    let (thoughts, parts): (Vec<Part>, Vec<Part>) = candidate
+        .and_then(|c| c.content)
+        .map(|c| c.parts)
+        .unwrap_or_default()
+        .into_iter()
+        .partition(|p| p.thought);
+    let reasoning: String = thoughts.into_iter().filter_map(|p| p.text).collect();
+    let text: String = parts.into_iter().filter_map(|p| p.text).collect();
+
+    let mut events = Vec::new();
+    if !reasoning.is_empty() {
+        events.push(ChatStreamEvent::Reasoning(reasoning));
+    }
+    if !text.is_empty() {
+        events.push(ChatStreamEvent::Delta(text));
+    }
+    if let Some(reason) = finish_reason {
+        events.push(ChatStreamEvent::Finish(FinishReason::parse(&reason)));
+    }
+    if let Some(usage) = parsed.usage_metadata {
+        // Thinking tokens are billed as output but reported separately
+        events.push(ChatStreamEvent::Usage(TokenUsage {
+            prompt_tokens: usage.prompt_token_count,
+            completion_tokens: usage.candidates_token_count + usage.thoughts_token_count,
+            reasoning_tokens: usage.thoughts_token_count,
+            ..Default::default()
+        }));
+    }
+    Ok(StreamChunk::Events(events))
+}
+
+#[derive(Clone)]
+pub struct GeminiLlmClient {
+    http: reqwest::Client,
+}
+
+impl GeminiLlmClient {
+    pub fn new(http: reqwest::Client) -> Self {
+        Self { http }
+    }
+}
+
+#[async_trait]
+impl LlmClient for GeminiLlmClient {
+    async fn chat(
+        &self,
+        provider: &ProviderModel,
+        model_id: &str,
+        messages: Vec<ChatMessagePayload>,
+        _tools: &[ToolDefinition],
+        params: &GenerationParams,
+    ) -> Result<BoxStream<'static, Result<ChatStreamEvent>>> {
+        let model = model_id.trim_start_matches("models/");
+        let url = endpoint(provider, &format!("models/{}:streamGenerateContent?alt=sse", model));
+        let payload = build_request(messages, params);
+
+        let resp = authorize(self.http.post(url).json(&payload), provider)
+            .send()
+            .await
+            .map_err(send_error)?;
+        let resp = ensure_success(resp).await?;
+
+        let chunks = resp.bytes_stream().eventsource().map(|event| match event {
+            Ok(event) => parse_event(&event.data),
+            Err(e) => Err(stream_error(e)),
+        });
+
+        Ok(into_event_stream(chunks))
+    }
+}
@@ -1,150 +1,698 @@
 use async_trait::async_trait;
 use eventsource_stream::Eventsource;
-use futures::stream::{BoxStream, StreamExt};
-use serde::{Deserialize, Serialize};
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
 use std::time::Duration;
+use uuid::Uuid;
 
 use crate::{
-    error::{AppError, Result},
-    models::user_provider::Model as ProviderModel,
+    clients::{
+        anthropic_client::AnthropicLlmClient, azure_client::AzureLlmClient, bedrock_client::BedrockLlmClient,
+        gemini_client::GeminiLlmClient, ollama_client::OllamaLlmClient, openai_responses_client::OpenAiResponsesClient,
+    },
+    error::{AppError, Result, UpstreamError},
+    models::{
+        conversation_message::ToolCall,
+        provider_model::GenerationParams,
+        user_provider::{Model as ProviderModel, OpenAiApiMode, ProviderType},
+    },
 };
 
-#[derive(Debug, Clone, Serialize)]
+/// An image sent with a message, base64-encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageData {
+    pub mime_type: String,
+    pub data: String,
+}
+
+impl ImageData {
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.mime_type, self.data)
+    }
+}
+
+/// Serializes in the Chat Completions message format, with `content` as an
+/// array of text and image parts when images are attached. Other providers map
+/// it onto their own shapes.
+#[derive(Debug, Clone, Default)]
 pub struct ChatMessagePayload {
     pub role: String,
     pub content: String,
+    /// Images attached to a user turn.
+    pub images: Vec<ImageData>,
+    /// Calls requested by an assistant turn.
+    pub tool_calls: Vec<ToolCall>,
+    /// The call a `tool` message answers.
+    pub tool_call_id: Option<String>,
+    /// The stored message this was built from. Never sent to providers.
+    pub message_id: Option<Uuid>,
+}
+
+impl ChatMessagePayload {
+    pub fn text(role: &str, content: String) -> Self {
+        Self { role: role.to_string(), content, ..Default::default() }
+    }
+}
+
+impl Serialize for ChatMessagePayload {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct ImageUrl {
+            url: String,
+        }
+
+        #[derive(Serialize)]
+        #[serde(tag = "type", rename_all = "snake_case")]
+        enum ContentPart<'a> {
+            Text { text: &'a str },
+            ImageUrl { image_url: ImageUrl },
+        }
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("role", &self.role)?;
+        if self.images.is_empty() {
+            map.serialize_entry("content", &self.content)?;
+        } else {
+            let text = (!self.content.is_empty()).then(|| ContentPart::Text { text: &self.content });
+            let images = self.images.iter().map(|image| ContentPart::ImageUrl { image_url: ImageUrl { url: image.data_url() } });
+            map.serialize_entry("content", &text.into_iter().chain(images).collect::<Vec<_>>())?;
+        }
+        if !self.tool_calls.is_empty() {
+            map.serialize_entry("tool_calls", &ToolCallsPayload(&self.tool_calls))?;
+        }
+        if let Some(tool_call_id) = &self.tool_call_id {
+            map.serialize_entry("tool_call_id", tool_call_id)?;
+        }
+        map.end()
+    }
+}
+
+struct ToolCallsPayload<'a>(&'a [ToolCall]);
+
+impl Serialize for ToolCallsPayload<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Function<'a> {
+            name: &'a str,
+            arguments: &'a str,
+        }
+
+        #[derive(Serialize)]
+        struct Call<'a> {
+            id: &'a str,
+            #[serde(rename = "type")]
+            kind: &'static str,
+            function: Function<'a>,
+        }
+
+        serializer.collect_seq(self.0.iter().map(|c| Call {
+            id: &c.id,
+            kind: "function",
+            function: Function { name: &c.name, arguments: &c.arguments },
+        }))
+    }
+}
+
+/// Drops tool calls and their results for providers that can't replay them.
+/// The assistant's later answers already reflect what the tools returned.
+pub fn without_tools(messages: Vec<ChatMessagePayload>) -> Vec<ChatMessagePayload> {
+    messages
+        .into_iter()
+        .filter(|m| m.role != "tool" && (!m.content.is_empty() || m.tool_calls.is_empty()))
+        .map(|m| ChatMessagePayload { tool_calls: Vec::new(), ..m })
+        .collect()
+}
+
+/// A tool the model may call. `parameters` is a JSON Schema object.
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionTool<'a> {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub function: &'a ToolDefinition,
 }
 
 #[derive(Debug, Clone, Serialize)]
-pub struct ChatRequestPayload {
+pub struct StreamOptions {
+    pub include_usage: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChatRequestPayload<'a> {
     pub model: String,
     pub messages: Vec<ChatMessagePayload>,
     pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<FunctionTool<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Replaces `max_tokens` for OpenAI reasoning models, which reject the older field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<&'a [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<&'static str>,
+}
+
+/// Whether to ask the provider for usage in the stream; without it, replies
+/// on OpenAI-compatible servers go unpriced.
+fn streams_usage(provider: &ProviderModel) -> bool {
+    provider.options.as_ref().and_then(|o| o.stream_usage).unwrap_or(true)
+}
+
+impl<'a> ChatRequestPayload<'a> {
+    pub fn streaming(
+        provider: &ProviderModel,
+        model: &str,
+        messages: Vec<ChatMessagePayload>,
+        tools: &'a [ToolDefinition],
+        params: &'a GenerationParams,
+    ) -> Self {
+        let reasoning = params.reasoning_effort.is_some();
+        Self {
+            model: model.to_string(),
+            messages,
+            stream: true,
+            stream_options: streams_usage(provider).then_some(StreamOptions { include_usage: true }),
+            tools: tools.iter().map(|function| FunctionTool { kind: "function", function }).collect(),
+            temperature: params.temperature,
+            top_p: params.top_p,
+            max_tokens: params.max_tokens.filter(|_| !reasoning),
+            max_completion_tokens: params.max_tokens.filter(|_| reasoning),
+            stop: params.stop.as_deref(),
+            presence_penalty: params.presence_penalty,
+            frequency_penalty: params.frequency_penalty,
+            seed: params.seed,
+            reasoning_effort: params.reasoning_effort.map(|e| e.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FunctionDelta {
+    name: Option<String>,
+    arguments: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct ToolCallDelta {
+    index: usize,
+    id: Option<String>,
+    function: Option<FunctionDelta>,
+}
+
+/// `reasoning_content` is sent by DeepSeek, Qwen and vLLM; OpenRouter calls it `reasoning`.
 #[derive(Debug, Deserialize)]
 struct ChoiceDelta {
     content: Option<String>,
+    #[serde(alias = "reasoning")]
+    reasoning_content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCallDelta>,
 }
 
 #[derive(Debug, Deserialize)]
 struct Choice {
-    index: usize,
     delta: ChoiceDelta,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionTokensDetails {
+    #[serde(default)]
+    reasoning_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsagePayload {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    completion_tokens_details: Option<CompletionTokensDetails>,
 }
 
 #[derive(Debug, Deserialize)]
 struct ChatResponsePayload {
+    #[serde(default)]
     choices: Vec<Choice>,
+    usage: Option<UsagePayload>,
+}
+
+/// Token counts reported by a provider. Providers report cumulative counts,
+/// sometimes split across several events, so consumers combine them with [`TokenUsage::merge`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    /// Every billed output token, reasoning included.
+    pub completion_tokens: u32,
+    /// The part of `completion_tokens` spent on reasoning; zero when not reported.
+    pub reasoning_tokens: u32,
+    /// The part of `prompt_tokens` read from the provider's prompt cache, billed below the input price.
+    pub cache_read_tokens: u32,
+    /// The part of `prompt_tokens` written to the prompt cache, billed above the input price.
+    pub cache_write_tokens: u32,
+}
+
+impl TokenUsage {
+    pub fn merge(&mut self, other: TokenUsage) {
+        self.prompt_tokens = self.prompt_tokens.max(other.prompt_tokens);
+        self.completion_tokens = self.completion_tokens.max(other.completion_tokens);
+        self.reasoning_tokens = self.reasoning_tokens.max(other.reasoning_tokens);
+        self.cache_read_tokens = self.cache_read_tokens.max(other.cache_read_tokens);
+        self.cache_write_tokens = self.cache_write_tokens.max(other.cache_write_tokens);
+    }
+}
+
+/// Why a reply ended. Provider-specific reasons are normalized by [`FinishReason::parse`];
+/// `Cancelled` and `Error` are only ever set by the conversation service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    Stop,
+    Length,
+    ContentFilter,
+    ToolCalls,
+    Cancelled,
+    Error,
+}
+
+impl FinishReason {
+    pub fn parse(reason: &str) -> Self {
+        match reason.to_ascii_lowercase().as_str() {
+            "length" | "max_tokens" | "max_output_tokens" => FinishReason::Length,
+            "content_filter" | "content_filtered" | "guardrail_intervened" | "refusal" | "safety"
+            | "recitation" | "blocklist" | "prohibited_content" | "spii" => FinishReason::ContentFilter,
+            "tool_calls" | "tool_use" | "function_call" => FinishReason::ToolCalls,
+            _ => FinishReason::Stop,
+        }
+    }
+}
+
+/// A fragment of a streamed tool call. The first fragment for an `index`
+/// carries the id and name; the arguments arrive as pieces of JSON text.
+#[derive(Debug, Clone)]
+pub struct ToolCallChunk {
+    pub index: usize,
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub arguments: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum ChatStreamEvent {
+    Delta(String),
+    Reasoning(String),
+    ToolCall(ToolCallChunk),
+    Usage(TokenUsage),
+    Finish(FinishReason),
+}
+
+/// What a provider parser makes of one upstream frame.
+pub enum StreamChunk {
+    Events(Vec<ChatStreamEvent>),
+    Stop,
+}
+
+impl StreamChunk {
+    pub fn skip() -> Self {
+        StreamChunk::Events(Vec::new())
+    }
+
+    pub fn text(text: String) -> Self {
+        if text.is_empty() {
+            Self::skip()
+        } else {
+            StreamChunk::Events(vec![ChatStreamEvent::Delta(text)])
+        }
+    }
+}
+
+/// Flattens parsed frames into the event stream, ending at the first `Stop`.
+pub fn into_event_stream<S>(chunks: S) -> BoxStream<'static, Result<ChatStreamEvent>>
+where
+    S: Stream<Item = Result<StreamChunk>> + Send + 'static,
+{
+    chunks
+        .take_while(|x| futures::future::ready(!matches!(x, Ok(StreamChunk::Stop))))
+        .flat_map(|x| {
+            let items: Vec<Result<ChatStreamEvent>> = match x {
+                Ok(StreamChunk::Events(events)) => events.into_iter().map(Ok).collect(),
+                Ok(StreamChunk::Stop) => Vec::new(),
+                Err(e) => vec![Err(e)],
+            };
+            stream::iter(items)
+        })
+        .boxed()
+}
+
+/// A request that never reached the provider, e.g. a refused connection or a timeout.
+pub fn send_error(e: reqwest::Error) -> AppError {
+    AppError::Upstream(UpstreamError {
+        status: None,
+        retry_after: None,
+        message: format!("Failed to call model API: {}", e),
+    })
+}
+
+/// A response body that broke off mid-stream.
+pub fn stream_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Upstream(UpstreamError { status: None, retry_after: None, message: format!("Stream error: {}", e) })
+}
+
+/// Passes successful responses through and turns error statuses into an
+/// [`UpstreamError`], keeping any `Retry-After` hint the provider sent.
+pub async fn ensure_success(resp: reqwest::Response) -> Result<reqwest::Response> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let retry_after = retry_after(resp.headers());
+    let body = resp.text().await.unwrap_or_default();
+    Err(AppError::Upstream(UpstreamError {
+        status: Some(status.as_u16()),
+        retry_after,
+        message: format!("API error: {} {}", status, body),
+    }))
+}
+
+/// Reads `retry-after-ms` (OpenAI), then `Retry-After` as seconds or an HTTP date.
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+
+    if let Some(ms) = header("retry-after-ms").and_then(|v| v.parse::<f64>().ok()) {
+        return Some(Duration::from_secs_f64(ms.max(0.0) / 1000.0));
+    }
+    let value = header("retry-after")?;
+    if let Ok(secs) = value.parse::<f64>() {
+        return Some(Duration::from_secs_f64(secs.max(0.0)));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().ok()
+}
+
+/// What a provider type accepts of [`GenerationParams`], beyond the ranges every provider shares.
+struct ParamSupport {
+    max_temperature: f32,
+    /// Most stop sequences accepted; zero when they aren't supported.
+    max_stop: usize,
+    penalties: bool,
+    seed: bool,
+    reasoning_effort: bool,
+}
+
+/// Rejects parameters the provider would refuse or silently ignore, so a
+/// setting never appears to apply when it doesn't.
+pub fn check_params(provider: &ProviderModel, params: &GenerationParams) -> Result<()> {
+    let support = match provider.provider_type {
+        ProviderType::OpenAI if api_mode(provider) == OpenAiApiMode::Responses => ParamSupport {
+            max_temperature: 2.0,
+            max_stop: 0,
+            penalties: false,
+            seed: false,
+            reasoning_effort: true,
+        },
+        ProviderType::OpenAI | ProviderType::Azure => ParamSupport {
+            max_temperature: 2.0,
+            max_stop: 4,
+            penalties: true,
+            seed: true,
+            reasoning_effort: true,
+        },
+        ProviderType::Anthropic => ParamSupport {
+            max_temperature: 1.0,
+            max_stop: 16,
+            penalties: false,
+            seed: false,
+            reasoning_effort: true,
+        },
+        ProviderType::Gemini => ParamSupport {
+            max_temperature: 2.0,
+            max_stop: 5,
+            penalties: true,
+            seed: true,
+            reasoning_effort: true,
+        },
+        ProviderType::Ollama => ParamSupport {
+            max_temperature: 2.0,
+            max_stop: 16,
+            penalties: true,
+            seed: true,
+            reasoning_effort: true,
+        },
+        ProviderType::Bedrock => ParamSupport {
+            max_temperature: 1.0,
+            max_stop: 4,
+            penalties: false,
+            seed: false,
+            reasoning_effort: false,
+        },
+    };
+    let provider_type = format!("{:?}", provider.provider_type);
+    let unsupported = |name: &str| Err(AppError::BadRequest(format!("{} is not supported by {} providers", name, provider_type)));
+
+    if params.temperature.is_some_and(|t| t > support.max_temperature) {
+        return Err(AppError::BadRequest(format!(
+            "temperature must be at most {} for {} providers",
+            support.max_temperature, provider_type
+        )));
+    }
+    if let Some(stop) = &params.stop {
+        if support.max_stop == 0 {
+            return unsupported("stop");
+        }
+        if stop.len() > support.max_stop {
+            return Err(AppError::BadRequest(format!(
+                "At most {} stop sequences are supported by {} providers",
+                support.max_stop, provider_type
+            )));
+        }
+        if stop.iter().any(|s| s.is_empty()) {
+            return Err(AppError::BadRequest("Stop sequences can't be empty".to_string()));
+        }
+    }
+    if !support.penalties && params.presence_penalty.is_some() {
+        return unsupported("presence_penalty");
+    }
+    if !support.penalties && params.frequency_penalty.is_some() {
+        return unsupported("frequency_penalty");
+    }
+    if !support.seed && params.seed.is_some() {
+        return unsupported("seed");
+    }
+    let Some(effort) = params.reasoning_effort else { return Ok(()) };
+    if !support.reasoning_effort {
+        return unsupported("reasoning_effort");
+    }
+    // Thinking budgets count towards max_tokens on both, and Anthropic fixes the sampling while thinking
+    if matches!(provider.provider_type, ProviderType::Anthropic | ProviderType::Gemini)
+        && params.max_tokens.is_some_and(|max| max <= effort.budget_tokens())
+    {
+        return Err(AppError::BadRequest(format!(
+            "max_tokens must exceed the {} thinking budget of {} tokens",
+            effort.as_str(),
+            effort.budget_tokens()
+        )));
+    }
+    if provider.provider_type == ProviderType::Anthropic {
+        if params.temperature.is_some() {
+            return Err(AppError::BadRequest("temperature can't be set together with reasoning_effort for Anthropic".to_string()));
+        }
+        if params.top_p.is_some_and(|p| p < 0.95) {
+            return Err(AppError::BadRequest("top_p must be at least 0.95 with reasoning_effort for Anthropic".to_string()));
+        }
+    }
+    Ok(())
 }
 
 #[async_trait]
 pub trait LlmClient: Send + Sync {
+    /// Streams a reply. `tools` is only honoured when [`LlmClient::supports_tools`] is true;
+    /// `params` should already have passed [`check_params`] for the provider.
     async fn chat(
         &self,
         provider: &ProviderModel,
         model_id: &str,
         messages: Vec<ChatMessagePayload>,
-    ) -> Result<BoxStream<'static, Result<String>>>;
+        tools: &[ToolDefinition],
+        params: &GenerationParams,
+    ) -> Result<BoxStream<'static, Result<ChatStreamEvent>>>;
+
+    fn supports_tools(&self, _provider: &ProviderModel) -> bool {
+        false
+    }
 }
 
+/// How long to wait for a provider to accept the connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest silence allowed on a provider's connection. Replies have no overall
+/// limit, since long reasoning streams run for minutes; models that think
+/// without streaming it can go quiet for a while before the first token.
+const READ_TIMEOUT: Duration = Duration::from_secs(300);
+
 #[derive(Clone)]
 pub struct DefaultLlmClient {
     http: reqwest::Client,
+    anthropic: AnthropicLlmClient,
+    gemini: GeminiLlmClient,
+    ollama: OllamaLlmClient,
+    azure: AzureLlmClient,
+    bedrock: BedrockLlmClient,
+    responses: OpenAiResponsesClient,
 }
 
 impl Default for DefaultLlmClient {
     fn default() -> Self {
         let http = reqwest::Client::builder()
-            .timeout(Duration::from_secs(60))
+            .connect_timeout(CONNECT_TIMEOUT)
+            .read_timeout(READ_TIMEOUT)
             .build()
             .unwrap_or_else(|_| reqwest::Client::new());
-        Self { http }
+        let anthropic = AnthropicLlmClient::new(http.clone());
+        let gemini = GeminiLlmClient::new(http.clone());
+        let ollama = OllamaLlmClient::new(http.clone());
+        let azure = AzureLlmClient::new(http.clone());
+        let bedrock = BedrockLlmClient::new(http.clone());
+        let responses = OpenAiResponsesClient::new(http.clone());
+        Self { http, anthropic, gemini, ollama, azure, bedrock, responses }
     }
 }
 
-#[async_trait]
-impl LlmClient for DefaultLlmClient {
-    async fn chat(
+impl DefaultLlmClient {
+    async fn openai_chat(
         &self,
         provider: &ProviderModel,
         model_id: &str,
         messages: Vec<ChatMessagePayload>,
-    ) -> Result<BoxStream<'static, Result<String>>> {
+        tools: &[ToolDefinition],
+        params: &GenerationParams,
+    ) -> Result<BoxStream<'static, Result<ChatStreamEvent>>> {
+        if api_mode(provider) == OpenAiApiMode::Responses {
+            return self.responses.chat(provider, model_id, messages, tools, params).await;
+        }
+
         let base = provider.url.trim_end_matches('/');
-        
+
         let url = if base.ends_with("/v1") {
             format!("{}/chat/completions", base)
         } else {
             format!("{}/v1/chat/completions", base)
         };
 
-        let payload = ChatRequestPayload {
-            model: model_id.to_string(),
-            messages,
-            stream: true,
-        };
+        let payload = ChatRequestPayload::streaming(provider, model_id, messages, tools, params);
 
         let mut req = self.http.post(url).json(&payload);
         if let Some(k) = provider.key.clone() {
             req = req.bearer_auth(k);
         }
 
-        let resp = req
-            .send()
-            .await
-            .map_err(|e| AppError::Internal(format!("Failed to call model API: {}", e)))?;
+        stream_chat_completions(req).await
+    }
+}
 
-        let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            return Err(AppError::BadRequest(format!(
-                "API error: {} {}",
-                status, body
-            )));
+/// Sends a Chat Completions request and parses the SSE response. Shared by
+/// every provider that speaks the OpenAI wire format.
+pub async fn stream_chat_completions(req: reqwest::RequestBuilder) -> Result<BoxStream<'static, Result<ChatStreamEvent>>> {
+    let resp = req.send().await.map_err(send_error)?;
+    let resp = ensure_success(resp).await?;
+
+    let chunks = resp.bytes_stream().eventsource().map(|event| match event {
+        Ok(event) if event.data == "[DONE]" => Ok(StreamChunk::Stop),
+        Ok(event) => parse_chat_completion_event(&event.data),
+        Err(e) => Err(stream_error(e)),
+    });
+
+    Ok(into_event_stream(chunks))
+}
+
+fn parse_chat_completion_event(data: &str) -> Result<StreamChunk> {
+    let parsed = serde_json::from_str::<ChatResponsePayload>(data).map_err(|e| {
+        AppError::Internal(format!("Failed to parse SSE data: {} | Data: {}", e, data))
+    })?;
+
+    let mut events = Vec::new();
+    if let Some(choice) = parsed.choices.into_iter().next() {
+        if let Some(reasoning) = choice.delta.reasoning_content.filter(|c| !c.is_empty()) {
+            events.push(ChatStreamEvent::Reasoning(reasoning));
+        }
+        if let Some(content) = choice.delta.content.filter(|c| !c.is_empty()) {
+            events.push(ChatStreamEvent::Delta(content));
         }
+        for call in choice.delta.tool_calls {
+            let function = call.function.unwrap_or(FunctionDelta { name: None, arguments: None });
+            events.push(ChatStreamEvent::ToolCall(ToolCallChunk {
+                index: call.index,
+                id: call.id,
+                name: function.name,
+                arguments: function.arguments.unwrap_or_default(),
+            }));
+        }
+        if let Some(reason) = choice.finish_reason {
+            events.push(ChatStreamEvent::Finish(FinishReason::parse(&reason)));
+        }
+    }
+    if let Some(usage) = parsed.usage {
+        events.push(ChatStreamEvent::Usage(TokenUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            reasoning_tokens: usage.completion_tokens_details.map(|d| d.reasoning_tokens).unwrap_or_default(),
+            ..Default::default()
+        }));
+    }
+    Ok(StreamChunk::Events(events))
+}
+
+#[async_trait]
+impl LlmClient for DefaultLlmClient {
+    async fn chat(
+        &self,
+        provider: &ProviderModel,
+        model_id: &str,
+        messages: Vec<ChatMessagePayload>,
+        tools: &[ToolDefinition],
+        params: &GenerationParams,
+    ) -> Result<BoxStream<'static, Result<ChatStreamEvent>>> {
+        let (messages, tools) = if self.supports_tools(provider) {
+            (messages, tools)
+        } else {
+            (without_tools(messages), &[][..])
+        };
+        match provider.provider_type {
+            ProviderType::OpenAI => self.openai_chat(provider, model_id, messages, tools, params).await,
+            ProviderType::Anthropic => self.anthropic.chat(provider, model_id, messages, tools, params).await,
+            ProviderType::Gemini => self.gemini.chat(provider, model_id, messages, tools, params).await,
+            ProviderType::Ollama => self.ollama.chat(provider, model_id, messages, tools, params).await,
+            ProviderType::Azure => self.azure.chat(provider, model_id, messages, tools, params).await,
+            ProviderType::Bedrock => self.bedrock.chat(provider, model_id, messages, tools, params).await,
+        }
+    }
 
-        let stream = resp
-            .bytes_stream()
-            .eventsource()
-            .map(|event| {
-                match event {
-                    Ok(event) => {
-                        if event.data == "[DONE]" {
-                            None
-                        } else {
-                            match serde_json::from_str::<ChatResponsePayload>(&event.data) {
-                                Ok(parsed) => {
-                                    if let Some(choice) = parsed.choices.first() {
-                                        if let Some(content) = choice.delta.content.clone() {
-                                            if !content.is_empty() {
-                                                return Some(Ok(content));
-                                            }
-                                        }
-                                    }
-                                    // Empty content or no choices, skip
-                                    Some(Ok("".to_string()))
-                                }
-                                Err(e) => Some(Err(AppError::Internal(format!(
-                                    "Failed to parse SSE data: {} | Data: {}",
-                                    e, event.data
-                                )))),
-                            }
-                        }
-                    }
-                    Err(e) => Some(Err(AppError::Internal(format!("Stream error: {}", e)))),
-                }
-            })
-            // Filter out None (DONE) and empty strings to clean up the stream
-            .take_while(|x| futures::future::ready(x.is_some()))
-            .map(|x| x.unwrap())
-            .filter(|x| {
-                futures::future::ready(match x {
-                    Ok(s) => !s.is_empty(),
-                    Err(_) => true,
-                })
-            });
-
-        Ok(Box::pin(stream))
+    fn supports_tools(&self, provider: &ProviderModel) -> bool {
+        match provider.provider_type {
+            ProviderType::OpenAI => api_mode(provider) == OpenAiApiMode::ChatCompletions,
+            ProviderType::Anthropic | ProviderType::Azure => true,
+            ProviderType::Gemini | ProviderType::Ollama | ProviderType::Bedrock => false,
+        }
     }
 }
+
+fn api_mode(provider: &ProviderModel) -> OpenAiApiMode {
+    provider.options.as_ref().and_then(|o| o.api_mode.clone()).unwrap_or_default()
+}
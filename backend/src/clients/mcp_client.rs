@@ -0,0 +1,431 @@
+use eventsource_stream::Eventsource;
+use futures::StreamExt;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{Child, ChildStdin, Command},
+    sync::oneshot,
+};
+
+use crate::{
+    error::{AppError, Result, UpstreamError},
+    models::mcp_server::{self, McpTransport},
+};
+
+pub const PROTOCOL_VERSION: &str = "2025-06-18";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const SESSION_HEADER: &str = "mcp-session-id";
+const PROTOCOL_HEADER: &str = "mcp-protocol-version";
+/// Guards against servers that keep returning a cursor.
+const MAX_TOOL_PAGES: usize = 20;
+
+fn mcp_error(message: String) -> AppError {
+    AppError::Upstream(UpstreamError { status: None, retry_after: None, message })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpToolInfo {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "empty_schema")]
+    pub input_schema: Value,
+}
+
+fn empty_schema() -> Value {
+    json!({ "type": "object" })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListToolsResult {
+    #[serde(default)]
+    tools: Vec<McpToolInfo>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CallToolResult {
+    #[serde(default)]
+    content: Vec<Value>,
+    structured_content: Option<Value>,
+    #[serde(default)]
+    is_error: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+/// A tool result flattened to the text the model is shown.
+#[derive(Debug, Clone)]
+pub struct McpCallResult {
+    pub content: String,
+    pub is_error: bool,
+}
+
+impl From<CallToolResult> for McpCallResult {
+    fn from(result: CallToolResult) -> Self {
+        let mut parts: Vec<String> = result
+            .content
+            .iter()
+            .filter_map(|block| match block.get("type").and_then(Value::as_str) {
+                Some("text") => block.get("text").and_then(Value::as_str).map(str::to_string),
+                Some("resource") => block
+                    .pointer("/resource/text")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                Some(kind) => Some(format!(
+                    "[{} content: {}]",
+                    kind,
+                    block.get("mimeType").and_then(Value::as_str).unwrap_or("unknown type")
+                )),
+                None => None,
+            })
+            .collect();
+        if parts.is_empty() {
+            if let Some(structured) = result.structured_content {
+                parts.push(structured.to_string());
+            }
+        }
+        Self { content: parts.join("\n"), is_error: result.is_error }
+    }
+}
+
+/// An initialized connection to an MCP server, speaking JSON-RPC over either transport.
+pub struct McpSession {
+    transport: Transport,
+    next_id: AtomicU64,
+}
+
+enum Transport {
+    Stdio(StdioTransport),
+    Http(HttpTransport),
+}
+
+impl McpSession {
+    /// Starts or connects to the server and performs the initialize handshake.
+    pub async fn connect(server: &mcp_server::Model, http: &reqwest::Client) -> Result<Self> {
+        let transport = match server.transport {
+            McpTransport::Stdio => Transport::Stdio(StdioTransport::spawn(server)?),
+            McpTransport::Http => Transport::Http(HttpTransport::new(server, http.clone())?),
+        };
+        let session = Self { transport, next_id: AtomicU64::new(1) };
+
+        let result = session
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "palette", "version": env!("CARGO_PKG_VERSION") },
+                }),
+            )
+            .await?;
+        if let (Transport::Http(http), Some(version)) =
+            (&session.transport, result.get("protocolVersion").and_then(Value::as_str))
+        {
+            http.set_protocol_version(version);
+        }
+        session.notify("notifications/initialized").await?;
+        Ok(session)
+    }
+
+    /// Whether the server has gone away and the session must be re-established.
+    pub fn is_closed(&self) -> bool {
+        match &self.transport {
+            Transport::Stdio(t) => t.closed.load(Ordering::Relaxed),
+            Transport::Http(t) => t.closed.load(Ordering::Relaxed),
+        }
+    }
+
+    pub async fn list_tools(&self) -> Result<Vec<McpToolInfo>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_TOOL_PAGES {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let page: ListToolsResult = serde_json::from_value(self.request("tools/list", params).await?)
+                .map_err(|e| mcp_error(format!("Invalid tools/list response: {}", e)))?;
+            tools.extend(page.tools);
+            match page.next_cursor {
+                Some(next) if !next.is_empty() => cursor = Some(next),
+                _ => break,
+            }
+        }
+        Ok(tools)
+    }
+
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<McpCallResult> {
+        let result: CallToolResult = serde_json::from_value(
+            self.request("tools/call", json!({ "name": name, "arguments": arguments })).await?,
+        )
+        .map_err(|e| mcp_error(format!("Invalid tools/call response: {}", e)))?;
+        Ok(result.into())
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, async {
+            match &self.transport {
+                Transport::Stdio(t) => t.request(id, &message).await,
+                Transport::Http(t) => t.request(id, &message).await,
+            }
+        })
+        .await
+        .map_err(|_| mcp_error(format!("MCP request {} timed out", method)))??;
+
+        if let Some(error) = response.get("error") {
+            let error = serde_json::from_value::<RpcError>(error.clone())
+                .unwrap_or(RpcError { code: 0, message: error.to_string() });
+            return Err(mcp_error(format!("MCP error {}: {}", error.code, error.message)));
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    async fn notify(&self, method: &str) -> Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method });
+        match &self.transport {
+            Transport::Stdio(t) => write_message(&t.stdin, &message).await,
+            Transport::Http(t) => t.post(&message).await.map(|_| ()),
+        }
+    }
+}
+
+type PendingRequests = Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>;
+
+/// A child process exchanging newline-delimited JSON-RPC messages. The
+/// process is killed when the transport is dropped.
+struct StdioTransport {
+    _child: Child,
+    stdin: Arc<tokio::sync::Mutex<ChildStdin>>,
+    pending: PendingRequests,
+    closed: Arc<AtomicBool>,
+}
+
+impl StdioTransport {
+    fn spawn(server: &mcp_server::Model) -> Result<Self> {
+        let command = server
+            .command
+            .as_deref()
+            .filter(|c| !c.trim().is_empty())
+            .ok_or_else(|| AppError::BadRequest("A command is required for stdio MCP servers".to_string()))?;
+
+        let mut child = Command::new(command)
+            .args(server.args.as_ref().map(|a| a.0.as_slice()).unwrap_or_default())
+            .envs(server.env.as_ref().map(|e| e.0.clone()).unwrap_or_default())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| mcp_error(format!("Failed to start MCP server {}: {}", server.name, e)))?;
+
+        let (Some(stdin), Some(stdout), Some(stderr)) = (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            return Err(mcp_error(format!("Failed to open pipes to MCP server {}", server.name)));
+        };
+        let stdin = Arc::new(tokio::sync::Mutex::new(stdin));
+        let pending: PendingRequests = Arc::default();
+        let closed = Arc::new(AtomicBool::new(false));
+
+        let name = server.name.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                tracing::debug!("MCP server {}: {}", name, line);
+            }
+        });
+
+        let (reader_stdin, reader_pending, reader_closed) = (stdin.clone(), pending.clone(), closed.clone());
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                // Some servers log to stdout; anything that isn't JSON-RPC is skipped
+                let Ok(message) = serde_json::from_str::<Value>(&line) else { continue };
+                let id = message.get("id").cloned();
+                match (message.get("method").and_then(Value::as_str), id) {
+                    (Some(method), Some(id)) => {
+                        let reply = server_request_reply(method, id);
+                        if write_message(&reader_stdin, &reply).await.is_err() {
+                            break;
+                        }
+                    }
+                    (None, Some(id)) => {
+                        let sender = id
+                            .as_u64()
+                            .and_then(|id| reader_pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&id));
+                        if let Some(sender) = sender {
+                            let _ = sender.send(message);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            reader_closed.store(true, Ordering::Relaxed);
+            // Dropping the senders fails every request still waiting for a reply
+            reader_pending.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        });
+
+        Ok(Self { _child: child, stdin, pending, closed })
+    }
+
+    async fn request(&self, id: u64, message: &Value) -> Result<Value> {
+        if self.closed.load(Ordering::Relaxed) {
+            return Err(mcp_error("MCP server has exited".to_string()));
+        }
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).insert(id, tx);
+        if let Err(e) = write_message(&self.stdin, message).await {
+            self.pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+            return Err(e);
+        }
+        rx.await.map_err(|_| mcp_error("MCP server closed the connection".to_string()))
+    }
+}
+
+async fn write_message(stdin: &tokio::sync::Mutex<ChildStdin>, message: &Value) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    let mut stdin = stdin.lock().await;
+    stdin
+        .write_all(&line)
+        .await
+        .and(stdin.flush().await)
+        .map_err(|e| mcp_error(format!("Failed to write to MCP server: {}", e)))
+}
+
+/// Palette offers no client capabilities, so server requests other than `ping` are refused.
+fn server_request_reply(method: &str, id: Value) -> Value {
+    if method == "ping" {
+        json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+    } else {
+        json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32601, "message": "Method not found" } })
+    }
+}
+
+/// The streamable HTTP transport: every message is a POST, answered either
+/// with a JSON body or with an SSE stream that carries the response.
+struct HttpTransport {
+    http: reqwest::Client,
+    url: String,
+    headers: HeaderMap,
+    session_id: Mutex<Option<String>>,
+    protocol_version: Mutex<String>,
+    closed: AtomicBool,
+}
+
+impl HttpTransport {
+    fn new(server: &mcp_server::Model, http: reqwest::Client) -> Result<Self> {
+        let url = server
+            .url
+            .clone()
+            .filter(|u| !u.trim().is_empty())
+            .ok_or_else(|| AppError::BadRequest("A URL is required for HTTP MCP servers".to_string()))?;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in server.headers.as_ref().map(|h| &h.0).into_iter().flatten() {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| AppError::BadRequest(format!("Invalid header name: {}", name)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| AppError::BadRequest(format!("Invalid value for header {}", name)))?;
+            headers.insert(name, value);
+        }
+
+        Ok(Self {
+            http,
+            url,
+            headers,
+            session_id: Mutex::new(None),
+            protocol_version: Mutex::new(PROTOCOL_VERSION.to_string()),
+            closed: AtomicBool::new(false),
+        })
+    }
+
+    fn set_protocol_version(&self, version: &str) {
+        *self.protocol_version.lock().unwrap_or_else(|e| e.into_inner()) = version.to_string();
+    }
+
+    async fn post(&self, message: &Value) -> Result<reqwest::Response> {
+        let session_id = self.session_id.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let protocol_version = self.protocol_version.lock().unwrap_or_else(|e| e.into_inner()).clone();
+
+        let mut req = self
+            .http
+            .post(&self.url)
+            .headers(self.headers.clone())
+            .header(ACCEPT, "application/json, text/event-stream")
+            .header(PROTOCOL_HEADER, protocol_version)
+            .json(message);
+        if let Some(session_id) = &session_id {
+            req = req.header(SESSION_HEADER, session_id);
+        }
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| mcp_error(format!("Failed to reach MCP server: {}", e)))?;
+        let status = resp.status();
+        if !status.is_success() {
+            // The server forgets sessions it has expired and answers 404
+            if status == reqwest::StatusCode::NOT_FOUND && session_id.is_some() {
+                self.closed.store(true, Ordering::Relaxed);
+            }
+            let body = resp.text().await.unwrap_or_default();
+            return Err(AppError::Upstream(UpstreamError {
+                status: Some(status.as_u16()),
+                retry_after: None,
+                message: format!("MCP server error: {} {}", status, body),
+            }));
+        }
+        if let Some(id) = resp.headers().get(SESSION_HEADER).and_then(|v| v.to_str().ok()) {
+            *self.session_id.lock().unwrap_or_else(|e| e.into_inner()) = Some(id.to_string());
+        }
+        Ok(resp)
+    }
+
+    async fn request(&self, id: u64, message: &Value) -> Result<Value> {
+        let resp = self.post(message).await?;
+        let is_stream = resp
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        if !is_stream {
+            return resp
+                .json::<Value>()
+                .await
+                .map_err(|e| mcp_error(format!("Invalid MCP response: {}", e)));
+        }
+
+        // The stream may carry notifications before the response we're waiting for
+        let mut events = resp.bytes_stream().eventsource();
+        while let Some(event) = events.next().await {
+            let event = event.map_err(|e| mcp_error(format!("MCP stream error: {}", e)))?;
+            let Ok(message) = serde_json::from_str::<Value>(&event.data) else { continue };
+            if message.get("method").is_none() && message.get("id").and_then(Value::as_u64) == Some(id) {
+                return Ok(message);
+            }
+        }
+        Err(mcp_error("MCP server ended the stream without a response".to_string()))
+    }
+}
@@ -1,2 +1,12 @@
 pub mod model_info_client;
-pub mod llm_client;
\ No newline at end of file
+pub mod llm_client;
+pub mod anthropic_client;
+pub mod gemini_client;
+pub mod ollama_client;
+pub mod azure_client;
+pub mod bedrock_client;
+pub mod sigv4;
+pub mod aws_event_stream;
+pub mod openai_responses_client;
+pub mod retry_client;
+pub mod mcp_client;
\ No newline at end of file
@@ -5,14 +5,16 @@ use serde::Deserialize;
 use tracing::warn;
 
 use crate::{
+    clients::{anthropic_client, azure_client, bedrock_client, gemini_client, ollama_client},
     error::{AppError, Result},
-    models::user_provider::Model as ProviderModel,
+    models::user_provider::{Model as ProviderModel, ProviderType},
 };
 
 #[async_trait]
 pub trait ModelInfoClient: Send + Sync {
     async fn fetch_prices(&self, provider: &ProviderModel, model_id: &str) -> Result<(Decimal, Decimal)>;
     async fn check_connectivity(&self, provider: &ProviderModel) -> Result<()>;
+    async fn list_models(&self, provider: &ProviderModel) -> Result<Vec<String>>;
 }
 
 #[derive(Clone)]
@@ -30,6 +32,23 @@ impl Default for DefaultModelInfoClient {
     }
 }
 
+impl DefaultModelInfoClient {
+    fn authorize(&self, req: reqwest::RequestBuilder, provider: &ProviderModel) -> reqwest::RequestBuilder {
+        match provider.provider_type {
+            ProviderType::OpenAI => match provider.key.clone() {
+                Some(k) => req.bearer_auth(k),
+                None => req,
+            },
+            ProviderType::Anthropic => anthropic_client::authorize(req, provider),
+            ProviderType::Gemini => gemini_client::authorize(req, provider),
+            ProviderType::Ollama => ollama_client::authorize(req, provider),
+            ProviderType::Azure => azure_client::authorize(req, provider),
+            // Bedrock requests are signed per request in `bedrock_client::send_signed`
+            ProviderType::Bedrock => req,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct PricingResponse {
     input_price_per_million: Decimal,
@@ -39,19 +58,28 @@ struct PricingResponse {
 #[async_trait]
 impl ModelInfoClient for DefaultModelInfoClient {
     async fn fetch_prices(&self, provider: &ProviderModel, model_id: &str) -> Result<(Decimal, Decimal)> {
+        // Local models have no usage cost
+        if provider.provider_type == ProviderType::Ollama {
+            return Ok((Decimal::ZERO, Decimal::ZERO));
+        }
+
+        // Bedrock has no pricing endpoint and its credentials must never be sent unsigned
+        if provider.provider_type == ProviderType::Bedrock {
+            warn!("Could not fetch prices for model {}, defaulting to 0: Bedrock has no pricing endpoint", model_id);
+            return Ok((Decimal::ZERO, Decimal::ZERO));
+        }
+
         let base = provider.url.trim_end_matches('/');
-        let candidates = vec![
-            format!("{}/pricing/models/{}", base, model_id),
-            format!("{}/v1/pricing/models/{}", base, model_id),
-        ];
+        let candidates = match provider.provider_type {
+            ProviderType::Azure => vec![azure_client::deployment_endpoint(provider, model_id, "pricing")],
+            _ => vec![
+                format!("{}/pricing/models/{}", base, model_id),
+                format!("{}/v1/pricing/models/{}", base, model_id),
+            ],
+        };
 
-        let key = provider.key.clone();
         for url in candidates {
-            let mut req = self.http.get(&url);
-            if let Some(k) = key.clone() {
-                req = req.bearer_auth(k);
-            }
-            let resp = req.send().await;
+            let resp = self.authorize(self.http.get(&url), provider).send().await;
             if let Ok(r) = resp {
                 if r.status().is_success() {
                     let parsed: PricingResponse = r
@@ -68,19 +96,32 @@ impl ModelInfoClient for DefaultModelInfoClient {
     }
 
     async fn check_connectivity(&self, provider: &ProviderModel) -> Result<()> {
-        let base = provider.url.trim_end_matches('/');
-        let url = if base.ends_with("/v1") {
-            format!("{}/models", base)
-        } else {
-            format!("{}/v1/models", base)
+        let url = match provider.provider_type {
+            ProviderType::OpenAI => {
+                let base = provider.url.trim_end_matches('/');
+                if base.ends_with("/v1") {
+                    format!("{}/models", base)
+                } else {
+                    format!("{}/v1/models", base)
+                }
+            }
+            ProviderType::Anthropic => anthropic_client::endpoint(provider, "models"),
+            ProviderType::Gemini => gemini_client::endpoint(provider, "models"),
+            ProviderType::Ollama => ollama_client::endpoint(provider, "tags"),
+            ProviderType::Azure => azure_client::endpoint(provider, "models"),
+            ProviderType::Bedrock => {
+                let url = bedrock_client::control_plane_endpoint(provider, "foundation-models");
+                let resp = bedrock_client::send_signed(&self.http, provider, reqwest::Method::GET, &url, Vec::new(), Default::default())
+                    .await?;
+                return if resp.status().is_success() {
+                    Ok(())
+                } else {
+                    Err(AppError::BadRequest(format!("Provider check failed with status: {}", resp.status())))
+                };
+            }
         };
-        
-        let mut req = self.http.get(&url);
-        if let Some(k) = provider.key.clone() {
-            req = req.bearer_auth(k);
-        }
 
-        let resp = req.send().await
+        let resp = self.authorize(self.http.get(&url), provider).send().await
             .map_err(|e| AppError::Internal(format!("Network error: {}", e)))?;
 
         if resp.status().is_success() {
@@ -89,4 +130,24 @@ impl ModelInfoClient for DefaultModelInfoClient {
             Err(AppError::BadRequest(format!("Provider check failed with status: {}", resp.status())))
         }
     }
-}
\ No newline at end of file
+
+    async fn list_models(&self, provider: &ProviderModel) -> Result<Vec<String>> {
+        if provider.provider_type != ProviderType::Ollama {
+            return Err(AppError::BadRequest("Model discovery is not supported for this provider type".to_string()));
+        }
+
+        let url = ollama_client::endpoint(provider, "tags");
+        let resp = self.authorize(self.http.get(&url), provider).send().await
+            .map_err(|e| AppError::Internal(format!("Network error: {}", e)))?;
+
+        if !resp.status().is_success() {
+            return Err(AppError::BadRequest(format!("Model discovery failed with status: {}", resp.status())));
+        }
+
+        let parsed: ollama_client::OllamaTagsResponse = resp
+            .json()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to parse model list: {}", e)))?;
+        Ok(parsed.models.into_iter().map(|m| m.name).collect())
+    }
+}
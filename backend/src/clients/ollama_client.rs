@@ -0,0 +1,226 @@
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clients::llm_client::{ensure_success, into_event_stream, send_error, stream_error, ChatMessagePayload, ChatStreamEvent, FinishReason, LlmClient, StreamChunk, TokenUsage, ToolDefinition},
+    error::{AppError, Result},
+    models::{provider_model::GenerationParams, user_provider::Model as ProviderModel},
+};
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_ctx: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+}
+
+/// Ollama takes images as bare base64 strings next to the text.
+#[derive(Debug, Clone, Serialize)]
+struct OllamaRequestMessage {
+    role: String,
+    content: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    images: Vec<String>,
+}
+
+impl From<ChatMessagePayload> for OllamaRequestMessage {
+    fn from(message: ChatMessagePayload) -> Self {
+        Self {
+            role: message.role,
+            content: message.content,
+            images: message.images.into_iter().map(|image| image.data).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct OllamaChatRequest {
+    model: String,
+    messages: Vec<OllamaRequestMessage>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<OllamaOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<String>,
+    /// Ollama only switches thinking on or off, so any reasoning effort enables it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    think: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaMessage {
+    #[serde(default)]
+    content: String,
+    /// Set by thinking models such as DeepSeek-R1 and Qwen3.
+    #[serde(default)]
+    thinking: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaChatChunk {
+    message: Option<OllamaMessage>,
+    #[serde(default)]
+    done: bool,
+    error: Option<String>,
+    done_reason: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: u32,
+    #[serde(default)]
+    eval_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OllamaTag {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OllamaTagsResponse {
+    #[serde(default)]
+    pub models: Vec<OllamaTag>,
+}
+
+pub fn endpoint(provider: &ProviderModel, path: &str) -> String {
+    format!("{}/api/{}", provider.url.trim_end_matches('/'), path)
+}
+
+/// Ollama has no auth of its own, but a key is forwarded as a bearer token
+/// for instances that sit behind an authenticating proxy.
+pub fn authorize(req: reqwest::RequestBuilder, provider: &ProviderModel) -> reqwest::RequestBuilder {
+    match provider.key.clone() {
+        Some(k) => req.bearer_auth(k),
+        None => req,
+    }
+}
+
+/// Splits a response body into newline-delimited records.
+fn ndjson_lines(resp: reqwest::Response) -> impl Stream<Item = Result<String>> {
+    let bytes = Box::pin(resp.bytes_stream());
+    stream::unfold((bytes, Vec::new(), false), |(mut bytes, mut buf, mut eof)| async move {
+        loop {
+            if let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buf.drain(..=pos).collect();
+                let line = String::from_utf8_lossy(&line).trim().to_string();
+                if line.is_empty() {
+                    continue;
+                }
+                return Some((Ok(line), (bytes, buf, eof)));
+            }
+            if eof {
+                let line = String::from_utf8_lossy(&buf).trim().to_string();
+                buf.clear();
+                return if line.is_empty() { None } else { Some((Ok(line), (bytes, buf, eof))) };
+            }
+            match bytes.next().await {
+                Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+                Some(Err(e)) => {
+                    return Some((
+                        Err(stream_error(e)),
+                        (bytes, Vec::new(), true),
+                    ))
+                }
+                None => eof = true,
+            }
+        }
+    })
+}
+
+fn parse_line(line: &str) -> Result<StreamChunk> {
+    let chunk = serde_json::from_str::<OllamaChatChunk>(line).map_err(|e| {
+        AppError::Internal(format!("Failed to parse NDJSON data: {} | Data: {}", e, line))
+    })?;
+
+    // Ollama reports failures after the 200 without a status, like a dropped stream
+    if let Some(error) = chunk.error {
+        return Err(stream_error(error));
+    }
+    if chunk.done {
+        return Ok(StreamChunk::Events(vec![
+            ChatStreamEvent::Usage(TokenUsage {
+                prompt_tokens: chunk.prompt_eval_count,
+                // Ollama counts thinking in eval_count without breaking it out
+                completion_tokens: chunk.eval_count,
+                ..Default::default()
+            }),
+            ChatStreamEvent::Finish(FinishReason::parse(chunk.done_reason.as_deref().unwrap_or("stop"))),
+        ]));
+    }
+    let Some(message) = chunk.message else {
+        return Ok(StreamChunk::skip());
+    };
+    let mut events = Vec::new();
+    if let Some(thinking) = message.thinking.filter(|t| !t.is_empty()) {
+        events.push(ChatStreamEvent::Reasoning(thinking));
+    }
+    if !message.content.is_empty() {
+        events.push(ChatStreamEvent::Delta(message.content));
+    }
+    Ok(StreamChunk::Events(events))
+}
+
+#[derive(Clone)]
+pub struct OllamaLlmClient {
+    http: reqwest::Client,
+}
+
+impl OllamaLlmClient {
+    pub fn new(http: reqwest::Client) -> Self {
+        Self { http }
+    }
+}
+
+#[async_trait]
+impl LlmClient for OllamaLlmClient {
+    async fn chat(
+        &self,
+        provider: &ProviderModel,
+        model_id: &str,
+        messages: Vec<ChatMessagePayload>,
+        _tools: &[ToolDefinition],
+        params: &GenerationParams,
+    ) -> Result<BoxStream<'static, Result<ChatStreamEvent>>> {
+        let options = provider.options.clone().unwrap_or_default();
+        let model_options = OllamaOptions {
+            num_ctx: options.num_ctx,
+            temperature: params.temperature,
+            top_p: params.top_p,
+            num_predict: params.max_tokens,
+            stop: params.stop.clone(),
+            presence_penalty: params.presence_penalty,
+            frequency_penalty: params.frequency_penalty,
+            seed: params.seed,
+        };
+        let payload = OllamaChatRequest {
+            model: model_id.to_string(),
+            messages: messages.into_iter().map(OllamaRequestMessage::from).collect(),
+            stream: true,
+            options: (options.num_ctx.is_some() || !params.is_empty()).then_some(model_options),
+            keep_alive: options.keep_alive,
+            think: params.reasoning_effort.map(|_| true),
+        };
+
+        let resp = authorize(self.http.post(endpoint(provider, "chat")).json(&payload), provider)
+            .send()
+            .await
+            .map_err(send_error)?;
+        let resp = ensure_success(resp).await?;
+
+        let chunks = ndjson_lines(resp).map(|line| line.and_then(|line| parse_line(&line)));
+
+        Ok(into_event_stream(chunks))
+    }
+}
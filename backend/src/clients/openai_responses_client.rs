@@ -0,0 +1,260 @@
+use async_trait::async_trait;
+use eventsource_stream::Eventsource;
+use futures::stream::{BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clients::llm_client::{ensure_success, into_event_stream, send_error, stream_error, ChatMessagePayload, ChatStreamEvent, FinishReason, LlmClient, StreamChunk, TokenUsage, ToolDefinition},
+    error::{AppError, Result, UpstreamError},
+    models::{provider_model::GenerationParams, user_provider::Model as ProviderModel},
+};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum InputPart {
+    InputText { text: String },
+    InputImage { image_url: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+enum InputContent {
+    Text(String),
+    Parts(Vec<InputPart>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct InputMessage {
+    role: String,
+    content: InputContent,
+}
+
+/// Images use `input_image` parts here rather than the Chat Completions `image_url` shape.
+impl From<ChatMessagePayload> for InputMessage {
+    fn from(message: ChatMessagePayload) -> Self {
+        let content = if message.images.is_empty() {
+            InputContent::Text(message.content)
+        } else {
+            let text = (!message.content.is_empty()).then_some(InputPart::InputText { text: message.content });
+            let images = message.images.iter().map(|image| InputPart::InputImage { image_url: image.data_url() });
+            InputContent::Parts(text.into_iter().chain(images).collect())
+        };
+        Self { role: message.role, content }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Reasoning {
+    effort: &'static str,
+    /// Reasoning is only streamed as a summary, and only when one is asked for.
+    summary: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ResponsesRequestPayload {
+    model: String,
+    input: Vec<InputMessage>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning: Option<Reasoning>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseError {
+    code: Option<String>,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OutputTokensDetails {
+    #[serde(default)]
+    reasoning_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseUsage {
+    input_tokens: u32,
+    output_tokens: u32,
+    output_tokens_details: Option<OutputTokensDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IncompleteDetails {
+    reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseObject {
+    error: Option<ResponseError>,
+    usage: Option<ResponseUsage>,
+    incomplete_details: Option<IncompleteDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OutputItem {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// Streaming events of the Responses API. Only the events that affect the
+/// text stream, and the start of built-in tool calls, are modelled; everything
+/// else is skipped.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum ResponsesStreamEvent {
+    #[serde(rename = "response.output_text.delta")]
+    OutputTextDelta { delta: String },
+    #[serde(rename = "response.reasoning_summary_text.delta")]
+    ReasoningSummaryDelta { delta: String },
+    #[serde(rename = "response.output_item.added")]
+    OutputItemAdded { item: OutputItem },
+    #[serde(rename = "response.completed")]
+    Completed { response: ResponseObject },
+    #[serde(rename = "response.incomplete")]
+    Incomplete { response: ResponseObject },
+    #[serde(rename = "response.failed")]
+    Failed { response: ResponseObject },
+    #[serde(rename = "error")]
+    Error { code: Option<String>, message: String },
+    #[serde(other)]
+    Other,
+}
+
+fn parse_event(data: &str) -> Result<StreamChunk> {
+    let event = serde_json::from_str::<ResponsesStreamEvent>(data).map_err(|e| {
+        AppError::Internal(format!("Failed to parse SSE data: {} | Data: {}", e, data))
+    })?;
+
+    match event {
+        ResponsesStreamEvent::OutputTextDelta { delta } => Ok(StreamChunk::text(delta)),
+        ResponsesStreamEvent::OutputItemAdded { item } => match builtin_tool_status(&item.kind) {
+            Some(status) => Ok(StreamChunk::Events(vec![ChatStreamEvent::Reasoning(status.to_string())])),
+            None => Ok(StreamChunk::skip()),
+        },
+        ResponsesStreamEvent::ReasoningSummaryDelta { delta } if !delta.is_empty() => {
+            Ok(StreamChunk::Events(vec![ChatStreamEvent::Reasoning(delta)]))
+        }
+        ResponsesStreamEvent::Completed { response } | ResponsesStreamEvent::Incomplete { response } => {
+            let reason = response
+                .incomplete_details
+                .and_then(|d| d.reason)
+                .map(|r| FinishReason::parse(&r))
+                .unwrap_or(FinishReason::Stop);
+            let mut events: Vec<ChatStreamEvent> = response
+                .usage
+                .map(|u| ChatStreamEvent::Usage(TokenUsage {
+                    prompt_tokens: u.input_tokens,
+                    completion_tokens: u.output_tokens,
+                    reasoning_tokens: u.output_tokens_details.map(|d| d.reasoning_tokens).unwrap_or_default(),
+                    ..Default::default()
+                }))
+                .into_iter()
+                .collect();
+            events.push(ChatStreamEvent::Finish(reason));
+            Ok(StreamChunk::Events(events))
+        }
+        ResponsesStreamEvent::Failed { response } => {
+            let (code, message) = match response.error {
+                Some(error) => (error.code, error.message),
+                None => (None, "response failed".to_string()),
+            };
+            Err(upstream_error(code.as_deref(), &message))
+        }
+        ResponsesStreamEvent::Error { code, message } => Err(upstream_error(code.as_deref(), &message)),
+        ResponsesStreamEvent::ReasoningSummaryDelta { .. } | ResponsesStreamEvent::Other => Ok(StreamChunk::skip()),
+    }
+}
+
+/// A note for the reasoning stream when the model starts one of OpenAI's
+/// hosted tools. Palette doesn't request these tools, and their results stay
+/// on OpenAI's side, but a model or stored prompt can still use them.
+fn builtin_tool_status(kind: &str) -> Option<&'static str> {
+    match kind {
+        "web_search_call" => Some("Searching the web…\n"),
+        "file_search_call" => Some("Searching files…\n"),
+        "code_interpreter_call" => Some("Running code…\n"),
+        "computer_call" => Some("Using the computer…\n"),
+        "image_generation_call" => Some("Generating an image…\n"),
+        _ => None,
+    }
+}
+
+/// Failures reported inside the stream carry an error code rather than a
+/// status; this is the status the same failure gets as an HTTP error.
+fn upstream_error(code: Option<&str>, message: &str) -> AppError {
+    let status = match code {
+        Some("rate_limit_exceeded") | Some("insufficient_quota") => 429,
+        Some("invalid_prompt") | Some("invalid_request_error") | Some("invalid_image") | Some("context_length_exceeded") => 400,
+        // `server_error` and anything unrecognised
+        _ => 500,
+    };
+    AppError::Upstream(UpstreamError {
+        status: Some(status),
+        retry_after: None,
+        message: format!("Stream error: {} {}", code.unwrap_or("error"), message),
+    })
+}
+
+#[derive(Clone)]
+pub struct OpenAiResponsesClient {
+    http: reqwest::Client,
+}
+
+impl OpenAiResponsesClient {
+    pub fn new(http: reqwest::Client) -> Self {
+        Self { http }
+    }
+}
+
+#[async_trait]
+impl LlmClient for OpenAiResponsesClient {
+    async fn chat(
+        &self,
+        provider: &ProviderModel,
+        model_id: &str,
+        messages: Vec<ChatMessagePayload>,
+        _tools: &[ToolDefinition],
+        params: &GenerationParams,
+    ) -> Result<BoxStream<'static, Result<ChatStreamEvent>>> {
+        let base = provider.url.trim_end_matches('/');
+        let url = if base.ends_with("/v1") {
+            format!("{}/responses", base)
+        } else {
+            format!("{}/v1/responses", base)
+        };
+
+        let payload = ResponsesRequestPayload {
+            model: model_id.to_string(),
+            input: messages.into_iter().map(InputMessage::from).collect(),
+            stream: true,
+            temperature: params.temperature,
+            top_p: params.top_p,
+            max_output_tokens: params.max_tokens,
+            reasoning: params.reasoning_effort.map(|effort| Reasoning { effort: effort.as_str(), summary: "auto" }),
+        };
+
+        let mut req = self.http.post(url).json(&payload);
+        if let Some(k) = provider.key.clone() {
+            req = req.bearer_auth(k);
+        }
+
+        let resp = req
+            .send()
+            .await
+            .map_err(send_error)?;
+        let resp = ensure_success(resp).await?;
+
+        let chunks = resp.bytes_stream().eventsource().map(|event| match event {
+            Ok(event) => parse_event(&event.data),
+            Err(e) => Err(stream_error(e)),
+        });
+
+        Ok(into_event_stream(chunks))
+    }
+}
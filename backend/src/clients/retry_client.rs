@@ -0,0 +1,97 @@
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    clients::llm_client::{ChatMessagePayload, ChatStreamEvent, LlmClient, ToolDefinition},
+    config::RetryConfig,
+    error::{AppError, Result, UpstreamError},
+    models::{provider_model::GenerationParams, user_provider::Model as ProviderModel},
+};
+
+/// Retries transient provider failures with exponential backoff.
+///
+/// A call only counts as started once the first token arrives: errors before
+/// that (including ones reported inside the stream) are retried, errors after
+/// it are passed through since the caller has already shown partial output.
+pub struct RetryingLlmClient {
+    inner: Arc<dyn LlmClient>,
+    config: RetryConfig,
+}
+
+impl RetryingLlmClient {
+    pub fn new(inner: Arc<dyn LlmClient>, config: RetryConfig) -> Self {
+        Self { inner, config }
+    }
+
+    /// Calls the provider and waits for the first token, buffering anything before it.
+    async fn first_token(
+        &self,
+        provider: &ProviderModel,
+        model_id: &str,
+        messages: Vec<ChatMessagePayload>,
+        tools: &[ToolDefinition],
+        params: &GenerationParams,
+    ) -> Result<BoxStream<'static, Result<ChatStreamEvent>>> {
+        let mut upstream = self.inner.chat(provider, model_id, messages, tools, params).await?;
+
+        let mut buffered = Vec::new();
+        while let Some(event) = upstream.next().await {
+            let event = event?;
+            let started = matches!(
+                event,
+                ChatStreamEvent::Delta(_) | ChatStreamEvent::Reasoning(_) | ChatStreamEvent::ToolCall(_)
+            );
+            buffered.push(Ok(event));
+            if started {
+                break;
+            }
+        }
+        Ok(stream::iter(buffered).chain(upstream).boxed())
+    }
+
+    /// The wait before the next attempt, or `None` when the provider asked
+    /// for longer than we are willing to wait.
+    fn delay(&self, attempt: u32, err: &UpstreamError) -> Option<Duration> {
+        if let Some(retry_after) = err.retry_after {
+            return (retry_after <= self.config.max_delay).then_some(retry_after);
+        }
+        let backoff = self.config.base_delay.saturating_mul(2u32.saturating_pow(attempt)).min(self.config.max_delay);
+        // Jitter keeps concurrent retries from lining up
+        Some(backoff.mul_f64(rand::random_range(0.5..=1.0)))
+    }
+}
+
+#[async_trait]
+impl LlmClient for RetryingLlmClient {
+    async fn chat(
+        &self,
+        provider: &ProviderModel,
+        model_id: &str,
+        messages: Vec<ChatMessagePayload>,
+        tools: &[ToolDefinition],
+        params: &GenerationParams,
+    ) -> Result<BoxStream<'static, Result<ChatStreamEvent>>> {
+        let mut attempt = 0;
+        loop {
+            let err = match self.first_token(provider, model_id, messages.clone(), tools, params).await {
+                Err(AppError::Upstream(err)) if err.is_retryable() && attempt < self.config.max_retries => err,
+                result => return result,
+            };
+            let Some(delay) = self.delay(attempt, &err) else {
+                return Err(AppError::Upstream(err));
+            };
+
+            attempt += 1;
+            tracing::warn!(
+                "Model call to {} failed ({}), retrying in {:?} (attempt {}/{})",
+                model_id, err, delay, attempt, self.config.max_retries
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    fn supports_tools(&self, provider: &ProviderModel) -> bool {
+        self.inner.supports_tools(provider)
+    }
+}
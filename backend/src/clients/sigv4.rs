@@ -0,0 +1,273 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, HOST};
+use sha2::{Digest, Sha256};
+
+use crate::error::{AppError, Result};
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    /// Parses the provider key, stored as `ACCESS_KEY_ID:SECRET_ACCESS_KEY[:SESSION_TOKEN]`.
+    pub fn from_key(key: Option<&str>) -> Result<Self> {
+        let key = key.ok_or_else(|| AppError::BadRequest("AWS credentials are not configured".to_string()))?;
+        let mut parts = key.splitn(3, ':');
+        let access_key_id = parts.next().unwrap_or_default().trim().to_string();
+        let secret_access_key = parts.next().unwrap_or_default().trim().to_string();
+        let session_token = parts.next().map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+
+        if access_key_id.is_empty() || secret_access_key.is_empty() {
+            return Err(AppError::BadRequest(
+                "AWS credentials must be in the form ACCESS_KEY_ID:SECRET_ACCESS_KEY[:SESSION_TOKEN]".to_string(),
+            ));
+        }
+
+        Ok(Self { access_key_id, secret_access_key, session_token })
+    }
+}
+
+/// Percent-encodes everything except RFC 3986 unreserved characters, as SigV4 requires.
+pub fn uri_encode(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for b in input.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn header(value: &str) -> Result<HeaderValue> {
+    HeaderValue::from_str(value).map_err(|e| AppError::Internal(format!("Invalid header value: {}", e)))
+}
+
+pub struct Signer<'a> {
+    pub credentials: &'a AwsCredentials,
+    pub region: &'a str,
+    pub service: &'a str,
+}
+
+impl Signer<'_> {
+    /// Signs a request with AWS Signature Version 4 and returns the headers to attach,
+    /// including `host`, `x-amz-date`, the optional session token and `authorization`.
+    pub fn sign(
+        &self,
+        method: &str,
+        url: &reqwest::Url,
+        extra_headers: &HeaderMap,
+        body: &[u8],
+        now: DateTime<Utc>,
+    ) -> Result<HeaderMap> {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(AppError::BadRequest("Provider URL has no host".to_string())),
+        };
+
+        let mut headers = extra_headers.clone();
+        headers.insert(HOST, header(&host)?);
+        headers.insert(HeaderName::from_static("x-amz-date"), header(&amz_date)?);
+        if let Some(token) = &self.credentials.session_token {
+            headers.insert(HeaderName::from_static("x-amz-security-token"), header(token)?);
+        }
+
+        let (canonical_request, signed_headers) = canonical_request(method, url, &headers, body);
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+        let string_to_sign = string_to_sign(&amz_date, &scope, &canonical_request);
+        let signature = self.signature(&date, &string_to_sign);
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.credentials.access_key_id, scope, signed_headers, signature
+        );
+        headers.insert(AUTHORIZATION, header(&authorization)?);
+
+        Ok(headers)
+    }
+
+    fn signature(&self, date: &str, string_to_sign: &str) -> String {
+        let k_date = hmac(format!("AWS4{}", self.credentials.secret_access_key).as_bytes(), date);
+        let k_region = hmac(&k_date, self.region);
+        let k_service = hmac(&k_region, self.service);
+        let k_signing = hmac(&k_service, "aws4_request");
+        hex::encode(hmac(&k_signing, string_to_sign))
+    }
+}
+
+/// Builds the canonical request over every header in `headers` and returns
+/// it with the list of signed header names.
+fn canonical_request(method: &str, url: &reqwest::Url, headers: &HeaderMap, body: &[u8]) -> (String, String) {
+    // The path is already encoded once in the URL; non-S3 services sign it encoded again.
+    let canonical_uri = url
+        .path()
+        .split('/')
+        .map(uri_encode)
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (uri_encode(&k), uri_encode(&v)))
+        .collect();
+    query.sort();
+    let canonical_query = query
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let mut canonical_headers: Vec<(String, String)> = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = value.to_str().unwrap_or_default().split_whitespace().collect::<Vec<_>>().join(" ");
+            (name.as_str().to_lowercase(), value)
+        })
+        .collect();
+    canonical_headers.sort();
+    let signed_headers = canonical_headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_headers = canonical_headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect::<String>();
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method, canonical_uri, canonical_query, canonical_headers, signed_headers, sha256_hex(body)
+    );
+    (canonical_request, signed_headers)
+}
+
+fn string_to_sign(amz_date: &str, scope: &str, canonical_request: &str) -> String {
+    format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use reqwest::header::CONTENT_TYPE;
+
+    // From the AWS Signature Version 4 test suite
+    const ACCESS_KEY_ID: &str = "AKIDEXAMPLE";
+    const SECRET_ACCESS_KEY: &str = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+    const EMPTY_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    fn credentials() -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: ACCESS_KEY_ID.to_string(),
+            secret_access_key: SECRET_ACCESS_KEY.to_string(),
+            session_token: None,
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap()
+    }
+
+    fn sign(method: &str, url: &str, extra_headers: HeaderMap, body: &[u8]) -> HeaderMap {
+        let credentials = credentials();
+        let signer = Signer { credentials: &credentials, region: "us-east-1", service: "service" };
+        signer.sign(method, &url.parse().unwrap(), &extra_headers, body, now()).unwrap()
+    }
+
+    fn authorization(headers: &HeaderMap) -> &str {
+        headers.get(AUTHORIZATION).unwrap().to_str().unwrap()
+    }
+
+    #[test]
+    fn get_vanilla() {
+        let url: reqwest::Url = "https://example.amazonaws.com/".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, HeaderValue::from_static("example.amazonaws.com"));
+        headers.insert("x-amz-date", HeaderValue::from_static("20150830T123600Z"));
+
+        let (canonical, signed_headers) = canonical_request("GET", &url, &headers, b"");
+        assert_eq!(
+            canonical,
+            format!("GET\n/\n\nhost:example.amazonaws.com\nx-amz-date:20150830T123600Z\n\nhost;x-amz-date\n{}", EMPTY_HASH)
+        );
+        assert_eq!(signed_headers, "host;x-amz-date");
+
+        let string_to_sign = string_to_sign("20150830T123600Z", "20150830/us-east-1/service/aws4_request", &canonical);
+        assert_eq!(
+            string_to_sign,
+            "AWS4-HMAC-SHA256\n20150830T123600Z\n20150830/us-east-1/service/aws4_request\nbb579772317eb040ac9ed261061d46c1f17a8133879d6129b6e1c25292927e63"
+        );
+
+        let signed = sign("GET", "https://example.amazonaws.com/", HeaderMap::new(), b"");
+        assert_eq!(
+            authorization(&signed),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn get_vanilla_query_order_key_case() {
+        let signed = sign("GET", "https://example.amazonaws.com/?Param2=value2&Param1=value1", HeaderMap::new(), b"");
+        assert_eq!(
+            authorization(&signed),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        );
+    }
+
+    #[test]
+    fn post_x_www_form_urlencoded() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/x-www-form-urlencoded"));
+        let signed = sign("POST", "https://example.amazonaws.com/", headers, b"Param1=value1");
+        assert_eq!(
+            authorization(&signed),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=content-type;host;x-amz-date, Signature=ff11897932ad3f4e8b18135d722051e5ac45fc38421b1da7b9d196a0fe09473a"
+        );
+    }
+
+    #[test]
+    fn session_token_is_signed() {
+        let credentials = AwsCredentials { session_token: Some("token".to_string()), ..credentials() };
+        let signer = Signer { credentials: &credentials, region: "us-east-1", service: "service" };
+        let url = "https://example.amazonaws.com/".parse().unwrap();
+        let signed = signer.sign("GET", &url, &HeaderMap::new(), b"", now()).unwrap();
+        assert_eq!(signed.get("x-amz-security-token").unwrap(), "token");
+        assert!(authorization(&signed).contains("SignedHeaders=host;x-amz-date;x-amz-security-token,"));
+    }
+
+    #[test]
+    fn parses_credentials_from_key() {
+        let parsed = AwsCredentials::from_key(Some("AKID:secret:token")).unwrap();
+        assert_eq!(parsed.access_key_id, "AKID");
+        assert_eq!(parsed.secret_access_key, "secret");
+        assert_eq!(parsed.session_token.as_deref(), Some("token"));
+        assert!(AwsCredentials::from_key(Some("AKID")).is_err());
+        assert!(AwsCredentials::from_key(None).is_err());
+    }
+}
@@ -1,12 +1,16 @@
-use dotenvy;
 use jsonwebtoken::{DecodingKey, EncodingKey};
-use std::{env, fs, io};
+use std::{env, fs, io, time::Duration};
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub server: ServerConfig,
     pub database_url: String,
+    /// Buffers the events of in-flight generations so streams can be resumed
+    /// and followed. Without it, a stream ends with the connection carrying it.
+    pub redis_url: Option<String>,
     pub jwt: JwtConfig,
+    pub llm_retry: RetryConfig,
+    pub mcp: McpConfig,
 }
 
 #[derive(Debug, Clone)]
@@ -22,6 +26,21 @@ pub struct JwtConfig {
     pub expires_in: i64,
 }
 
+/// How provider calls are retried before the first token arrives.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+/// Limits on the MCP servers users may configure.
+#[derive(Debug, Clone)]
+pub struct McpConfig {
+    /// Whether users may register `stdio` servers, which run commands on this host.
+    pub allow_stdio: bool,
+}
+
 impl Config {
     pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
         match dotenvy::from_path("../.env") {
@@ -54,13 +73,30 @@ impl Config {
             },
             database_url: env::var("DATABASE_URL")
                 .map_err(|_| io::Error::new(io::ErrorKind::NotFound, "Environment variable DATABASE_URL not set"))?,
+            redis_url: env::var("REDIS_URL").ok().filter(|url| !url.is_empty()),
             jwt: JwtConfig {
                 encoding_key: jwt_private_key,
                 decoding_key: jwt_public_key,
                 expires_in: env::var("JWT_EXPIRES_IN")
                     .ok()
                     .and_then(|p| p.parse::<i64>().ok()).unwrap_or(86400),
-            }
+            },
+            llm_retry: RetryConfig {
+                max_retries: env::var("LLM_MAX_RETRIES")
+                    .ok()
+                    .and_then(|p| p.parse::<u32>().ok()).unwrap_or(2),
+                base_delay: Duration::from_millis(env::var("LLM_RETRY_BASE_DELAY_MS")
+                    .ok()
+                    .and_then(|p| p.parse::<u64>().ok()).unwrap_or(500)),
+                max_delay: Duration::from_millis(env::var("LLM_RETRY_MAX_DELAY_MS")
+                    .ok()
+                    .and_then(|p| p.parse::<u64>().ok()).unwrap_or(10000)),
+            },
+            mcp: McpConfig {
+                allow_stdio: env::var("MCP_ALLOW_STDIO")
+                    .ok()
+                    .and_then(|p| p.parse::<bool>().ok()).unwrap_or(false),
+            },
         };
         Ok(config)
     }
@@ -13,6 +13,11 @@ pub async fn get_postgres_connection(url: &str) -> Result<DatabaseConnection, Db
     Database::connect(opt).await
 }
 
+/// The shared Redis connection, which reconnects on its own if the server restarts.
+pub async fn get_redis_connection(client: &redis::Client) -> redis::RedisResult<redis::aio::ConnectionManager> {
+    redis::aio::ConnectionManager::new(client.clone()).await
+}
+
 pub async fn run_migrations(db: &DatabaseConnection) -> Result<(), DbErr> {
     use migration::Migrator;
     use sea_orm_migration::MigratorTrait;
@@ -1,7 +1,33 @@
 use axum::{Json, http::StatusCode, response::{IntoResponse, Response}};
+use std::{fmt, time::Duration};
 use thiserror::Error;
 
-use crate::http::dto::common_schema::ApiResponse;
+use crate::http::{dto::common_schema::ApiResponse, extractors::jwt::AuthError};
+
+/// A failed call to a model provider or MCP server, kept structured so callers can decide whether to retry.
+#[derive(Debug)]
+pub struct UpstreamError {
+    /// HTTP status, or `None` when the connection itself failed.
+    pub status: Option<u16>,
+    /// How long the provider asked us to wait before trying again.
+    pub retry_after: Option<Duration>,
+    pub message: String,
+}
+
+impl UpstreamError {
+    pub fn is_retryable(&self) -> bool {
+        match self.status {
+            None => true,
+            Some(status) => status == 408 || status == 429 || status >= 500,
+        }
+    }
+}
+
+impl fmt::Display for UpstreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -16,15 +42,24 @@ pub enum AppError {
     
     #[error("Bad request: {0}")]
     BadRequest(String),
+
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
     
     #[error("Internal server error: {0}")]
     Internal(String),
 
+    #[error("Upstream error: {0}")]
+    Upstream(UpstreamError),
+
     #[error("Authorization error: {0}")]
-    Authorization(#[from] crate::http::extractors::jwt::AuthError),
+    Authorization(#[from] AuthError),
 
     #[error("Database error: {0}")]
     Database(#[from] sea_orm::DbErr),
+
+    #[error("Redis error: {0}")]
+    Redis(#[from] redis::RedisError),
     
     #[error("Validation error: {0}")]
     Validation(#[from] validator::ValidationErrors),
@@ -42,12 +77,17 @@ pub enum AppError {
     Uuid(#[from] uuid::Error),
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            AppError::Authorization(ref msg) => {
-                tracing::error!("Authorization error: {}", msg);
-                (StatusCode::UNAUTHORIZED, "Failed to authenticate")
+impl AppError {
+    /// The status and client-facing message the error is reported with. The
+    /// details of internal errors are logged rather than sent.
+    pub fn status_and_message(&self) -> (StatusCode, &str) {
+        match *self {
+            AppError::Authorization(ref err) => {
+                tracing::error!("Authorization error: {}", err);
+                match err {
+                    AuthError::InvalidToken => (StatusCode::UNAUTHORIZED, "Failed to authenticate"),
+                    _ => err.status_and_message(),
+                }
             }
             AppError::Forbidden(ref msg) => {
                 tracing::error!("Forbidden error: {}", msg);
@@ -65,14 +105,26 @@ impl IntoResponse for AppError {
                 tracing::error!("Bad Request error: {}", msg);
                 (StatusCode::BAD_REQUEST, msg.as_str())
             }
+            AppError::LimitExceeded(ref msg) => {
+                tracing::warn!("Limit exceeded: {}", msg);
+                (StatusCode::TOO_MANY_REQUESTS, msg.as_str())
+            }
             AppError::Internal(ref msg) => {
                 tracing::error!("Internal Server error: {}", msg);
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server error")
             }
+            AppError::Upstream(ref err) => {
+                tracing::error!("Upstream error: {}", err);
+                (StatusCode::BAD_GATEWAY, err.message.as_str())
+            }
             AppError::Database(ref err) => {
                 tracing::error!("Database error: {:?}", err);
                 (StatusCode::INTERNAL_SERVER_ERROR, "Database error")
             }
+            AppError::Redis(ref err) => {
+                tracing::error!("Redis error: {:?}", err);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Stream store error")
+            }
             AppError::Validation(ref err) => {
                 tracing::error!("Validation error: {:?}", err);
                 (StatusCode::BAD_REQUEST, "Failed to validate request data")
@@ -93,8 +145,13 @@ impl IntoResponse for AppError {
                 tracing::error!("UUID error: {:?}", err);
                 (StatusCode::BAD_REQUEST, "Invalid id")
             }
-        };
+        }
+    }
+}
 
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, error_message) = self.status_and_message();
         let body = Json(ApiResponse::failed(Some(error_message.to_string())));
 
         (status, body).into_response()
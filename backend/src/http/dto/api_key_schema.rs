@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::{models::api_key, repositories::api_key_repo::ApiKeyUsageTotals};
+
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct CreateApiKeyRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeyResponse {
+    #[serde(flatten)]
+    pub key: api_key::Model,
+    pub usage: ApiKeyUsageTotals,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeysResponse {
+    pub items: Vec<ApiKeyResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub key: api_key::Model,
+    /// The secret to send as the bearer token. It is only ever shown here.
+    pub secret: String,
+}
@@ -1,8 +1,147 @@
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::models::{conversation_message, conversation_session};
+use crate::{
+    clients::llm_client::FinishReason,
+    models::{
+        conversation_message::BranchMessage, conversation_session::{self, ContextStrategy}, message_attachment::AttachmentInfo,
+        provider_model::{self, GenerationParams},
+        user::MAX_INSTRUCTIONS_LEN,
+    },
+    services::budget_service::BudgetWarning,
+};
+
+/// Version of the streamed event protocol, sent in every `start` event.
+/// Bumped whenever an event changes in a way old clients can't ignore.
+pub const EVENT_PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventModel {
+    pub id: Uuid,
+    pub model_id: String,
+    pub name: String,
+}
+
+impl From<&provider_model::Model> for EventModel {
+    fn from(model: &provider_model::Model) -> Self {
+        Self { id: model.id, model_id: model.model_id.clone(), name: model.name.clone() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamErrorCode {
+    /// The provider rejected the request or the stream broke.
+    ProviderError,
+    /// The reply could not be saved.
+    InternalError,
+}
+
+/// Events streamed while a reply is generated. Each is sent as an SSE event
+/// named after its `type`, with the JSON-encoded event as data. A turn always
+/// ends with `done`; `title` may follow it for a session's first reply.
+///
+/// When the model calls tools, each call is reported with `tool_call` and
+/// `tool_result`, and `step` starts the assistant message the following
+/// deltas belong to.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConversationEvent {
+    Start {
+        version: u32,
+        session_id: Uuid,
+        user_message_id: Option<Uuid>,
+        assistant_message_id: Uuid,
+        model: EventModel,
+    },
+    Delta {
+        text: String,
+    },
+    Reasoning {
+        text: String,
+    },
+    ToolCall {
+        /// The assistant message that requested the call.
+        message_id: Uuid,
+        id: String,
+        name: String,
+        arguments: String,
+    },
+    ToolResult {
+        /// The stored `tool` message.
+        message_id: Uuid,
+        tool_call_id: String,
+        content: String,
+        is_error: bool,
+    },
+    Step {
+        assistant_message_id: Uuid,
+    },
+    Usage {
+        prompt_tokens: u32,
+        /// Includes any reasoning tokens.
+        completion_tokens: u32,
+        /// The part of the completion spent reasoning, when the provider reports it.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reasoning_tokens: Option<u32>,
+        cost: Decimal,
+    },
+    Warning(BudgetWarning),
+    /// The model failed before producing any output and the next fallback is being tried.
+    Fallback {
+        from: EventModel,
+        to: EventModel,
+        reason: String,
+    },
+    Title {
+        title: String,
+    },
+    Done {
+        finish_reason: FinishReason,
+    },
+    Error {
+        code: StreamErrorCode,
+        message: String,
+    },
+}
+
+impl ConversationEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConversationEvent::Start { .. } => "start",
+            ConversationEvent::Delta { .. } => "delta",
+            ConversationEvent::Reasoning { .. } => "reasoning",
+            ConversationEvent::ToolCall { .. } => "tool_call",
+            ConversationEvent::ToolResult { .. } => "tool_result",
+            ConversationEvent::Step { .. } => "step",
+            ConversationEvent::Usage { .. } => "usage",
+            ConversationEvent::Warning(_) => "warning",
+            ConversationEvent::Fallback { .. } => "fallback",
+            ConversationEvent::Title { .. } => "title",
+            ConversationEvent::Done { .. } => "done",
+            ConversationEvent::Error { .. } => "error",
+        }
+    }
+}
+
+/// An event as streamed to a client. `id` is its entry in the generation's
+/// buffer, sent as the SSE event ID so a dropped connection can resume after it.
+#[derive(Debug, Clone)]
+pub struct StreamedEvent {
+    pub id: Option<String>,
+    pub event: ConversationEvent,
+}
+
+/// An event of one of the replies to a comparison, tagged with the model
+/// producing it. Sent like any other event, with `provider_model_id` added.
+#[derive(Debug, Clone, Serialize)]
+pub struct ComparisonEvent {
+    pub provider_model_id: Uuid,
+    #[serde(flatten)]
+    pub event: ConversationEvent,
+}
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ConversationSessionsResponse {
@@ -14,13 +153,88 @@ pub struct CreateConversationRequest {}
 
 #[derive(Debug, Clone, Validate, Deserialize)]
 pub struct SendMessageRequest {
-    #[validate(length(min = 1))]
+    /// May be empty when images are attached.
     pub content: String,
+    /// Uploads to send with the message, from `POST /api/attachments`.
+    #[serde(default)]
+    pub attachment_ids: Vec<Uuid>,
     pub provider_model_id: Uuid,
+    /// Overrides the session's and the model's defaults for this turn.
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub params: GenerationParams,
+}
+
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct CompareMessageRequest {
+    /// May be empty when images are attached.
+    pub content: String,
+    #[serde(default)]
+    pub attachment_ids: Vec<Uuid>,
+    /// The models to answer side by side.
+    pub provider_model_ids: Vec<Uuid>,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub params: GenerationParams,
+}
+
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct RetryMessageRequest {
+    /// Model to retry with; defaults to the one that produced the reply.
+    pub provider_model_id: Option<Uuid>,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub params: GenerationParams,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FollowGenerationQuery {
+    /// The assistant message a generation started with; defaults to the session's latest.
+    pub message_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SwitchBranchRequest {
+    /// Which version of the message to show, from its `sibling_index` range.
+    pub sibling_index: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateFallbackModelsRequest {
+    /// Models to try in order when the requested one fails; `null` falls back to the user's default list.
+    pub fallback_model_ids: Option<Vec<Uuid>>,
+}
+
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct UpdateSystemPromptRequest {
+    /// `null` or blank removes it.
+    #[validate(length(max = MAX_INSTRUCTIONS_LEN))]
+    pub system_prompt: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SystemPromptResponse {
+    pub system_prompt: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateContextStrategyRequest {
+    /// `null` falls back to the user's default strategy.
+    pub context_strategy: Option<ContextStrategy>,
+}
+
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct UpdateGenerationParamsRequest {
+    /// Defaults for the session's messages; `null` leaves them to the model.
+    #[validate(nested)]
+    pub generation_params: Option<GenerationParams>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct ConversationResponse {
     pub id: Uuid,
-    pub items: Vec<conversation_message::Model>,
+    /// The active branch, from the first message down.
+    pub items: Vec<BranchMessage>,
+    /// Attachments of the listed messages, linked by `message_id`.
+    pub attachments: Vec<AttachmentInfo>,
 }
\ No newline at end of file
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use validator::Validate;
+
+use crate::{
+    http::dto::common_schema::nullable,
+    models::mcp_server::{self, McpTransport},
+};
+
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct CreateMcpServerRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    pub transport: McpTransport,
+    #[validate(length(min = 1, max = 1024))]
+    pub command: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub env: Option<BTreeMap<String, String>>,
+    #[validate(url)]
+    pub url: Option<String>,
+    pub headers: Option<BTreeMap<String, String>>,
+    pub enabled: Option<bool>,
+    pub allowed_tools: Option<Vec<String>>,
+}
+
+/// Omitted fields are left unchanged; `null` clears the optional ones.
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct UpdateMcpServerRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub name: Option<String>,
+    pub transport: Option<McpTransport>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub command: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub args: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub env: Option<Option<BTreeMap<String, String>>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub url: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub headers: Option<Option<BTreeMap<String, String>>>,
+    pub enabled: Option<bool>,
+    /// `null` exposes every tool the server offers.
+    #[serde(default, deserialize_with = "nullable")]
+    pub allowed_tools: Option<Option<Vec<String>>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct McpServersResponse {
+    pub items: Vec<mcp_server::Model>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct McpToolResponse {
+    pub name: String,
+    pub description: Option<String>,
+    /// Whether the tool passes the server's allowlist and is offered to models.
+    pub allowed: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct McpToolsResponse {
+    pub items: Vec<McpToolResponse>,
+}
@@ -3,4 +3,9 @@ pub mod auth_schema;
 pub mod conversation_schema;
 pub mod user_schema;
 pub mod provider_schema;
-pub mod provider_models_schema;
\ No newline at end of file
+pub mod provider_models_schema;
+pub mod mcp_schema;
+pub mod ws_schema;
+pub mod openai_schema;
+pub mod api_key_schema;
+pub mod personal_access_token_schema;
\ No newline at end of file
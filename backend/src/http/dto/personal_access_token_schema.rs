@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::models::personal_access_token::{self, TokenScope};
+
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct CreatePersonalAccessTokenRequest {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    #[validate(length(min = 1))]
+    pub scopes: Vec<TokenScope>,
+    /// The token never expires when unset.
+    #[validate(range(min = 1, max = 3650))]
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PersonalAccessTokensResponse {
+    pub items: Vec<personal_access_token::Model>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedPersonalAccessTokenResponse {
+    #[serde(flatten)]
+    pub token: personal_access_token::Model,
+    /// The secret to send as the bearer token. It is only ever shown here.
+    pub secret: String,
+}
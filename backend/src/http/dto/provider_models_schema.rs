@@ -2,7 +2,10 @@ use serde::{Deserialize, Serialize};
 use validator::Validate;
 use uuid::Uuid;
 
-use crate::models::provider_model;
+use crate::{
+    http::dto::common_schema::nullable,
+    models::provider_model::{self, GenerationParams},
+};
 
 #[derive(Debug, Clone, Validate, Deserialize)]
 pub struct CreateProviderModelRequest {
@@ -18,14 +21,37 @@ pub struct UpdateProviderModelRequest {
     pub model_id: Option<String>,
     #[validate(length(min = 1, max = 64))]
     pub name: Option<String>,
+    /// Defaults for calls to this model; `null` clears them.
+    #[serde(default, deserialize_with = "nullable")]
+    pub generation_params: Option<Option<GenerationParams>>,
+    /// Tokens the model accepts, enabling history trimming; `null` clears it.
+    #[serde(default, deserialize_with = "nullable")]
+    pub context_length: Option<Option<u32>>,
 }
 
-// #[derive(Debug, Serialize)]
-// pub struct ProviderModelListResponse {
-//     pub items: Vec<provider_model::Model>,
-// }
-
 #[derive(Debug, Serialize)]
 pub struct ProviderModelIdResponse {
     pub id: Uuid,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct ImportProviderModelsRequest {
+    #[validate(length(min = 1))]
+    pub model_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiscoveredModel {
+    pub model_id: String,
+    pub imported: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiscoveredModelListResponse {
+    pub items: Vec<DiscoveredModel>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProviderModelListResponse {
+    pub items: Vec<provider_model::Model>,
+}
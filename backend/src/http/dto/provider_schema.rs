@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::models::{user_provider::{self, ProviderType}, provider_model};
+use crate::models::{user::SpendingBudget, user_provider::{self, ProviderOptions, ProviderType}, provider_model};
 
 #[derive(Debug, Clone, Validate, Deserialize)]
 pub struct CreateProviderRequest {
@@ -13,6 +13,7 @@ pub struct CreateProviderRequest {
     pub url: String,
     #[validate(length(min = 1, max = 4096))]
     pub key: Option<String>,
+    pub options: Option<ProviderOptions>,
 }
 
 #[derive(Debug, Clone, Validate, Deserialize)]
@@ -23,6 +24,12 @@ pub struct UpdateProviderRequest {
     #[validate(url)]
     pub url: Option<String>,
     pub key: Option<Option<String>>,
+    pub options: Option<Option<ProviderOptions>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateProviderBudgetRequest {
+    pub budget: Option<SpendingBudget>,
 }
 
 #[derive(Debug, Serialize)]
@@ -1,9 +1,24 @@
-use serde::{Serialize, Deserialize};
+use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::models::user::MAX_INSTRUCTIONS_LEN;
 
+
+#[allow(dead_code)]
 #[derive(Debug, Clone, Validate, Deserialize)]
 pub struct UpdateProfileRequest {
     #[validate(length(min = 1, max = 64))]
     pub name: Option<String>,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Validate, Deserialize)]
+pub struct UpdateCustomInstructionsRequest {
+    /// `null` or blank removes them.
+    #[validate(length(max = MAX_INSTRUCTIONS_LEN))]
+    pub custom_instructions: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CustomInstructionsResponse {
+    pub custom_instructions: Option<String>,
+}
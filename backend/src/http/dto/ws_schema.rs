@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::http::dto::conversation_schema::{ConversationEvent, RetryMessageRequest, SendMessageRequest};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SocketQuery {
+    /// Access token, for clients that can't set the `Authorization` header on the upgrade request.
+    pub token: Option<String>,
+}
+
+/// A command sent over the socket as a JSON text frame. `request_id` is
+/// chosen by the client and echoed in the command's `ack` or `error`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SocketCommand {
+    /// Like `POST /api/conversations/{id}/messages`.
+    Send {
+        request_id: Option<String>,
+        conversation_id: Uuid,
+        #[serde(flatten)]
+        request: SendMessageRequest,
+    },
+    /// Like `POST /api/conversations/{id}/messages/{message_id}/regenerate`.
+    Regenerate {
+        request_id: Option<String>,
+        conversation_id: Uuid,
+        message_id: Uuid,
+        #[serde(flatten)]
+        request: RetryMessageRequest,
+    },
+    /// Like `POST /api/conversations/{id}/cancel`.
+    Cancel {
+        request_id: Option<String>,
+        conversation_id: Uuid,
+    },
+}
+
+impl SocketCommand {
+    pub fn request_id(&self) -> Option<String> {
+        match self {
+            SocketCommand::Send { request_id, .. }
+            | SocketCommand::Regenerate { request_id, .. }
+            | SocketCommand::Cancel { request_id, .. } => request_id.clone(),
+        }
+    }
+}
+
+/// A message sent to the client as a JSON text frame.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SocketMessage {
+    /// The command was accepted; a reply's events follow as `event` messages.
+    Ack { request_id: Option<String> },
+    /// The command failed, with the status and message its HTTP endpoint would respond with.
+    Error {
+        request_id: Option<String>,
+        status: u16,
+        message: String,
+    },
+    /// An event of a reply, as it is streamed over SSE. `event_id` resumes the
+    /// reply through `GET /api/conversations/{id}/stream` after a reconnect.
+    Event {
+        conversation_id: Uuid,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        event_id: Option<String>,
+        event: ConversationEvent,
+    },
+}
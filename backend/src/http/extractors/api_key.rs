@@ -0,0 +1,40 @@
+use axum::{
+    RequestPartsExt, extract::{FromRef, FromRequestParts}, http::request::Parts,
+};
+use axum_extra::{TypedHeader, headers::{Authorization, authorization::Bearer}};
+
+use crate::{
+    error::AppError,
+    http::{dto::openai_schema::OpenAiError, extractors::jwt::AuthError},
+    models::api_key,
+    state::AppState,
+};
+
+/// The Palette API key a `/v1` request was made with.
+pub struct ApiKeyAuth(pub api_key::Model);
+
+impl<S> FromRequestParts<S> for ApiKeyAuth
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = OpenAiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let TypedHeader(Authorization(bearer)) = parts
+            .extract::<TypedHeader<Authorization<Bearer>>>()
+            .await
+            .map_err(|_| AppError::from(AuthError::InvalidToken))?;
+
+        let key = AppState::from_ref(state)
+            .api_key_service
+            .authenticate(bearer.token())
+            .await?
+            .ok_or(AppError::from(AuthError::InvalidToken))?;
+
+        Ok(ApiKeyAuth(key))
+    }
+}
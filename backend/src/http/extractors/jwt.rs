@@ -4,23 +4,45 @@ use axum::{
 use axum_extra::{TypedHeader, headers::{Authorization, authorization::Bearer}};
 use jsonwebtoken::{Algorithm, Validation, decode};
 use serde::de::DeserializeOwned;
-use thiserror::Error; 
+use thiserror::Error;
 
-use crate::{http::dto::{auth_schema::Claims, common_schema::ApiResponse}, state::AppState};
+use crate::{
+    http::dto::{auth_schema::Claims, common_schema::ApiResponse},
+    models::personal_access_token::TokenScope,
+    services::personal_access_token_service::PERSONAL_TOKEN_PREFIX,
+    state::AppState,
+};
 
 pub struct AuthUser(pub Claims);
 
+/// The scopes, any one of which lets a personal access token use a route.
+/// Routes without one only accept login tokens.
+#[derive(Debug, Clone, Copy)]
+pub struct RequiredScope(pub &'static [TokenScope]);
+
 #[derive(Error, Debug)]
 pub enum AuthError {
     #[error("Invalid token")]
     InvalidToken,
+    #[error("Token lacks the required scope")]
+    InsufficientScope,
+    #[error("Failed to look up token")]
+    LookupFailed,
+}
+
+impl AuthError {
+    pub fn status_and_message(&self) -> (StatusCode, &'static str) {
+        match self {
+            AuthError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid token"),
+            AuthError::InsufficientScope => (StatusCode::FORBIDDEN, "Token lacks the required scope"),
+            AuthError::LookupFailed => (StatusCode::INTERNAL_SERVER_ERROR, "Failed to authenticate"),
+        }
+    }
 }
 
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            AuthError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid token"),
-        };
+        let (status, error_message) = self.status_and_message();
 
         let body = Json(ApiResponse::failed(Some(error_message)));
 
@@ -45,14 +67,44 @@ where
             .await
             .map_err(|_| AuthError::InvalidToken)?;
 
-        let claims = decode::<Claims>(
-            bearer.token(),
-            &AppState::from_ref(state).auth_service.jwt_config.decoding_key,
-            &Validation::new(Algorithm::RS256),
-        )
-        .map_err(|_| AuthError::InvalidToken)?
-        .claims;
+        let required = parts.extensions.get::<RequiredScope>().copied();
+        let claims = authenticate(&AppState::from_ref(state), bearer.token(), required).await?;
 
         Ok(AuthUser(claims))
     }
 }
+
+/// Verifies a login token or personal access token, for callers that can't
+/// send it in the `Authorization` header. A personal access token must have
+/// one of the `required` scopes.
+pub async fn authenticate(state: &AppState, token: &str, required: Option<RequiredScope>) -> Result<Claims, AuthError> {
+    if !token.starts_with(PERSONAL_TOKEN_PREFIX) {
+        return decode_claims(state, token);
+    }
+
+    let token = state
+        .personal_access_token_service
+        .authenticate(token)
+        .await
+        .map_err(|e| {
+            tracing::error!("Personal access token lookup failed: {}", e);
+            AuthError::LookupFailed
+        })?
+        .ok_or(AuthError::InvalidToken)?;
+    if !required.is_some_and(|RequiredScope(scopes)| token.allows_any(scopes)) {
+        return Err(AuthError::InsufficientScope);
+    }
+
+    let exp = token.expires_at.map_or(i64::MAX, |at| at.timestamp());
+    Ok(Claims { sub: token.user_id, exp })
+}
+
+fn decode_claims(state: &AppState, token: &str) -> Result<Claims, AuthError> {
+    decode::<Claims>(
+        token,
+        &state.auth_service.jwt_config.decoding_key,
+        &Validation::new(Algorithm::RS256),
+    )
+    .map(|data| data.claims)
+    .map_err(|_| AuthError::InvalidToken)
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::Result,
+    http::{
+        dto::{
+            api_key_schema::{ApiKeyResponse, ApiKeysResponse, CreateApiKeyRequest, CreatedApiKeyResponse},
+            common_schema::ApiResponse,
+        },
+        extractors::jwt::AuthUser,
+    },
+    services::api_key_service::ApiKeyService,
+};
+
+pub async fn list_api_keys(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ApiKeyService>>,
+) -> Result<Json<ApiResponse<ApiKeysResponse>>> {
+    let items = service
+        .list(claims.sub)
+        .await?
+        .into_iter()
+        .map(|(key, usage)| ApiKeyResponse { key, usage })
+        .collect();
+    Ok(Json(ApiResponse::success(Some(ApiKeysResponse { items }), None::<String>)))
+}
+
+pub async fn create_api_key(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ApiKeyService>>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<Json<ApiResponse<CreatedApiKeyResponse>>> {
+    request.validate()?;
+    let (key, secret) = service.create(claims.sub, request.name).await?;
+    Ok(Json(ApiResponse::success(Some(CreatedApiKeyResponse { key, secret }), Some("API key created"))))
+}
+
+pub async fn revoke_api_key(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ApiKeyService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>> {
+    service.revoke(claims.sub, id).await?;
+    Ok(Json(ApiResponse::success(None, Some("API key revoked"))))
+}
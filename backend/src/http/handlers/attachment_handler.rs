@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Multipart, Path, State},
+    http::header::{CACHE_CONTROL, CONTENT_TYPE},
+    response::{IntoResponse, Response},
+};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    http::{
+        dto::{common_schema::ApiResponse, provider_schema::ProviderIdResponse},
+        extractors::jwt::AuthUser,
+    },
+    models::message_attachment,
+    services::attachment_service::AttachmentService,
+};
+
+/// Accepts a single image in the `file` field of a multipart form.
+pub async fn upload_attachment(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<AttachmentService>>,
+    mut multipart: Multipart,
+) -> Result<Json<ApiResponse<message_attachment::Model>>> {
+    while let Some(field) = multipart.next_field().await.map_err(|e| AppError::BadRequest(e.body_text()))? {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let file_name = field.file_name().map(str::to_string);
+        let data = field.bytes().await.map_err(|e| AppError::BadRequest(e.body_text()))?;
+        let created = service.upload(claims.sub, file_name, data.to_vec()).await?;
+        return Ok(Json(ApiResponse::success(Some(created), Some("Attachment uploaded"))));
+    }
+    Err(AppError::BadRequest("Missing file field".to_string()))
+}
+
+pub async fn get_attachment(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<AttachmentService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Response> {
+    let attachment = service.get(claims.sub, id).await?;
+    Ok((
+        [(CONTENT_TYPE, attachment.mime_type), (CACHE_CONTROL, "private, max-age=86400".to_string())],
+        attachment.data,
+    )
+        .into_response())
+}
+
+pub async fn delete_attachment(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<AttachmentService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<ProviderIdResponse>>> {
+    service.delete(claims.sub, id).await?;
+    Ok(Json(ApiResponse::success(Some(ProviderIdResponse { id }), Some("Attachment deleted"))))
+}
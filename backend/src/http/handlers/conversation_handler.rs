@@ -2,7 +2,8 @@ use std::convert::Infallible;
 use std::sync::Arc;
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::HeaderMap,
     response::sse::{Event, KeepAlive, Sse},
     Json,
 };
@@ -16,6 +17,7 @@ use crate::{
         dto::{common_schema::ApiResponse, conversation_schema::*},
         extractors::jwt::AuthUser,
     },
+    models::conversation_session,
     services::conversation_service::ConversationService,
 };
 
@@ -41,6 +43,7 @@ pub async fn create_conversation(
         Some(ConversationResponse {
             id: created.id,
             items: vec![],
+            attachments: vec![],
         }),
         Some("Conversation created"),
     )))
@@ -51,11 +54,12 @@ pub async fn list_messages(
     State(service): State<Arc<ConversationService>>,
     Path(session_id): Path<Uuid>,
 ) -> Result<Json<ApiResponse<ConversationResponse>>> {
-    let items = service.list_messages(claims.sub, session_id).await?;
+    let (items, attachments) = service.list_messages(claims.sub, session_id).await?;
     Ok(Json(ApiResponse::success(
         Some(ConversationResponse {
             id: session_id,
             items,
+            attachments,
         }),
         None::<String>,
     )))
@@ -74,18 +78,219 @@ pub async fn send_message(
             claims.sub,
             session_id,
             request.content,
+            request.attachment_ids,
             request.provider_model_id,
+            request.params,
         )
         .await?;
 
-    let sse_stream = stream.map(|res| match res {
-        Ok(text) => Ok(Event::default().data(text)),
-        Err(e) => Ok(Event::default().event("error").data(e.to_string())),
+    Ok(into_sse(stream))
+}
+
+pub async fn compare_message(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ConversationService>>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<CompareMessageRequest>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    request.validate()?;
+
+    let stream = service
+        .compare_message(
+            claims.sub,
+            session_id,
+            request.content,
+            request.attachment_ids,
+            request.provider_model_ids,
+            request.params,
+        )
+        .await?;
+
+    let sse_stream = stream.map(|tagged| {
+        Ok(Event::default()
+            .event(tagged.event.name())
+            .data(serde_json::to_string(&tagged).unwrap_or_default()))
     });
+    Ok(Sse::new(sse_stream).keep_alive(KeepAlive::default()))
+}
+
+pub async fn retry_message(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ConversationService>>,
+    Path((session_id, message_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<RetryMessageRequest>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    request.validate()?;
 
+    let stream = service
+        .retry_message(claims.sub, session_id, message_id, request.provider_model_id, request.params)
+        .await?;
+
+    Ok(into_sse(stream))
+}
+
+pub async fn edit_message(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ConversationService>>,
+    Path((session_id, message_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<SendMessageRequest>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    request.validate()?;
+
+    let stream = service
+        .edit_message(
+            claims.sub,
+            session_id,
+            message_id,
+            request.content,
+            request.attachment_ids,
+            request.provider_model_id,
+            request.params,
+        )
+        .await?;
+
+    Ok(into_sse(stream))
+}
+
+pub async fn regenerate_message(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ConversationService>>,
+    Path((session_id, message_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<RetryMessageRequest>,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    request.validate()?;
+
+    let stream = service
+        .regenerate_message(claims.sub, session_id, message_id, request.provider_model_id, request.params)
+        .await?;
+
+    Ok(into_sse(stream))
+}
+
+pub async fn switch_branch(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ConversationService>>,
+    Path((session_id, message_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<SwitchBranchRequest>,
+) -> Result<Json<ApiResponse<ConversationResponse>>> {
+    let (items, attachments) = service
+        .switch_branch(claims.sub, session_id, message_id, request.sibling_index)
+        .await?;
+    Ok(Json(ApiResponse::success(
+        Some(ConversationResponse {
+            id: session_id,
+            items,
+            attachments,
+        }),
+        Some("Branch switched"),
+    )))
+}
+
+fn into_sse(
+    stream: impl Stream<Item = StreamedEvent> + Send + 'static,
+) -> Sse<impl Stream<Item = std::result::Result<Event, Infallible>>> {
+    let sse_stream = stream.map(|StreamedEvent { id, event }| {
+        let sse_event = Event::default()
+            .event(event.name())
+            .data(serde_json::to_string(&event).unwrap_or_default());
+        Ok(match id {
+            Some(id) => sse_event.id(id),
+            None => sse_event,
+        })
+    });
+
+    Sse::new(sse_stream).keep_alive(KeepAlive::default())
+}
+
+/// Replays and follows a generation, resuming after the `Last-Event-ID` header when given.
+pub async fn follow_generation(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ConversationService>>,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<FollowGenerationQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = std::result::Result<Event, Infallible>>>> {
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string());
+
+    let stream = service
+        .follow_generation(claims.sub, session_id, query.message_id, last_event_id)
+        .await?;
+
+    let sse_stream = stream.map(|entry| Ok(Event::default().id(entry.id).event(entry.name).data(entry.data)));
     Ok(Sse::new(sse_stream).keep_alive(KeepAlive::default()))
 }
 
+pub async fn update_fallback_models(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ConversationService>>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<UpdateFallbackModelsRequest>,
+) -> Result<Json<ApiResponse<conversation_session::Model>>> {
+    let session = service
+        .update_fallback_models(claims.sub, session_id, request.fallback_model_ids)
+        .await?;
+    Ok(Json(ApiResponse::success(Some(session), Some("Fallback models updated"))))
+}
+
+pub async fn get_system_prompt(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ConversationService>>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<SystemPromptResponse>>> {
+    let system_prompt = service.get_system_prompt(claims.sub, session_id).await?;
+    Ok(Json(ApiResponse::success(Some(SystemPromptResponse { system_prompt }), None::<String>)))
+}
+
+pub async fn update_system_prompt(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ConversationService>>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<UpdateSystemPromptRequest>,
+) -> Result<Json<ApiResponse<conversation_session::Model>>> {
+    request.validate()?;
+    let session = service
+        .update_system_prompt(claims.sub, session_id, request.system_prompt)
+        .await?;
+    Ok(Json(ApiResponse::success(Some(session), Some("System prompt updated"))))
+}
+
+pub async fn update_generation_params(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ConversationService>>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<UpdateGenerationParamsRequest>,
+) -> Result<Json<ApiResponse<conversation_session::Model>>> {
+    request.validate()?;
+    let session = service
+        .update_generation_params(claims.sub, session_id, request.generation_params)
+        .await?;
+    Ok(Json(ApiResponse::success(Some(session), Some("Generation parameters updated"))))
+}
+
+pub async fn update_context_strategy(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ConversationService>>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<UpdateContextStrategyRequest>,
+) -> Result<Json<ApiResponse<conversation_session::Model>>> {
+    let session = service
+        .update_context_strategy(claims.sub, session_id, request.context_strategy)
+        .await?;
+    Ok(Json(ApiResponse::success(Some(session), Some("Context strategy updated"))))
+}
+
+pub async fn cancel_generation(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<ConversationService>>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>> {
+    service.cancel_generation(claims.sub, session_id).await?;
+    Ok(Json(ApiResponse::success(None, Some("Generation cancelled"))))
+}
+
 pub async fn delete_conversation(
     AuthUser(claims): AuthUser,
     State(service): State<Arc<ConversationService>>,
@@ -96,6 +301,7 @@ pub async fn delete_conversation(
         Some(ConversationResponse {
             id: session_id,
             items: vec![],
+            attachments: vec![],
         }),
         Some("Conversation deleted"),
     )))
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::Result,
+    http::{
+        dto::{
+            common_schema::ApiResponse,
+            mcp_schema::{CreateMcpServerRequest, UpdateMcpServerRequest, McpServersResponse, McpToolResponse, McpToolsResponse},
+            provider_schema::ProviderIdResponse,
+        },
+        extractors::jwt::AuthUser,
+    },
+    models::mcp_server,
+    services::mcp_server_service::McpServerService,
+};
+
+pub async fn list_servers(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<McpServerService>>,
+) -> Result<Json<ApiResponse<McpServersResponse>>> {
+    let items = state.list(claims.sub).await?;
+    Ok(Json(ApiResponse::success(Some(McpServersResponse { items }), None::<String>)))
+}
+
+pub async fn create_server(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<McpServerService>>,
+    Json(request): Json<CreateMcpServerRequest>,
+) -> Result<Json<ApiResponse<mcp_server::Model>>> {
+    request.validate()?;
+    let created = state.create(claims.sub, request).await?;
+    Ok(Json(ApiResponse::success(Some(created), Some("MCP server created"))))
+}
+
+pub async fn get_server(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<McpServerService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<mcp_server::Model>>> {
+    let server = state.get(claims.sub, id).await?;
+    Ok(Json(ApiResponse::success(Some(server), None::<String>)))
+}
+
+pub async fn update_server(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<McpServerService>>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateMcpServerRequest>,
+) -> Result<Json<ApiResponse<mcp_server::Model>>> {
+    request.validate()?;
+    let updated = state.update(claims.sub, id, request).await?;
+    Ok(Json(ApiResponse::success(Some(updated), Some("MCP server updated"))))
+}
+
+pub async fn delete_server(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<McpServerService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<ProviderIdResponse>>> {
+    state.delete(claims.sub, id).await?;
+    Ok(Json(ApiResponse::success(Some(ProviderIdResponse { id }), Some("MCP server deleted"))))
+}
+
+pub async fn list_tools(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<McpServerService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<McpToolsResponse>>> {
+    let items = state
+        .list_tools(claims.sub, id)
+        .await?
+        .into_iter()
+        .map(|(tool, allowed)| McpToolResponse { name: tool.name, description: tool.description, allowed })
+        .collect();
+    Ok(Json(ApiResponse::success(Some(McpToolsResponse { items }), None::<String>)))
+}
@@ -1,4 +1,11 @@
 pub mod auth_handler;
 pub mod conversation_handler;
 pub mod user_provider_handler;
-pub mod provider_model_handler;
\ No newline at end of file
+pub mod provider_model_handler;
+pub mod user_handler;
+pub mod mcp_server_handler;
+pub mod attachment_handler;
+pub mod ws_handler;
+pub mod api_key_handler;
+pub mod openai_handler;
+pub mod personal_access_token_handler;
\ No newline at end of file
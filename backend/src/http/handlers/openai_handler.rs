@@ -0,0 +1,67 @@
+use std::{convert::Infallible, sync::Arc};
+
+use axum::{
+    extract::{rejection::JsonRejection, State},
+    response::{
+        sse::{Event, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use futures::StreamExt;
+
+use crate::{
+    error::AppError,
+    http::{
+        dto::openai_schema::{ChatCompletionRequest, ErrorResponse, ModelList, ModelObject, OpenAiError},
+        extractors::api_key::ApiKeyAuth,
+    },
+    services::openai_proxy_service::{self, OpenAiProxyService, ProxyChunk},
+};
+
+pub async fn list_models(
+    ApiKeyAuth(key): ApiKeyAuth,
+    State(service): State<Arc<OpenAiProxyService>>,
+) -> Result<Json<ModelList>, OpenAiError> {
+    let data = service
+        .list_models(key.user_id)
+        .await?
+        .into_iter()
+        .map(|m| ModelObject {
+            id: m.name,
+            object: "model",
+            created: m.model.created_at.timestamp(),
+            owned_by: m.provider.name,
+        })
+        .collect();
+    Ok(Json(ModelList { object: "list", data }))
+}
+
+/// Answers with a `chat.completion`, or streams `chat.completion.chunk`s when
+/// the request sets `stream`. Unlike Palette's own streams, chunks carry no
+/// event names or IDs and no keep-alive comments are sent, as OpenAI sends none.
+pub async fn chat_completions(
+    ApiKeyAuth(key): ApiKeyAuth,
+    State(service): State<Arc<OpenAiProxyService>>,
+    request: std::result::Result<Json<ChatCompletionRequest>, JsonRejection>,
+) -> Result<Response, OpenAiError> {
+    let Json(request) = request.map_err(|e| AppError::BadRequest(e.body_text()))?;
+    let stream = request.stream;
+    let include_usage = request.stream_options.as_ref().is_some_and(|o| o.include_usage);
+    let completion = service.chat(&key, request).await?;
+
+    if !stream {
+        let completion = openai_proxy_service::complete(completion).await?;
+        return Ok(Json(completion).into_response());
+    }
+
+    let events = openai_proxy_service::chunks(completion, include_usage).map(|chunk| {
+        let data = match chunk {
+            ProxyChunk::Chunk(chunk) => serde_json::to_string(&chunk).unwrap_or_default(),
+            ProxyChunk::Error(e) => serde_json::to_string(&ErrorResponse::from_error(&e).1).unwrap_or_default(),
+            ProxyChunk::Done => "[DONE]".to_string(),
+        };
+        Ok::<_, Infallible>(Event::default().data(data))
+    });
+    Ok(Sse::new(events).into_response())
+}
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::Result,
+    http::{
+        dto::{
+            common_schema::ApiResponse,
+            personal_access_token_schema::{
+                CreatePersonalAccessTokenRequest, CreatedPersonalAccessTokenResponse, PersonalAccessTokensResponse,
+            },
+        },
+        extractors::jwt::AuthUser,
+    },
+    services::personal_access_token_service::PersonalAccessTokenService,
+};
+
+pub async fn list_tokens(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<PersonalAccessTokenService>>,
+) -> Result<Json<ApiResponse<PersonalAccessTokensResponse>>> {
+    let items = service.list(claims.sub).await?;
+    Ok(Json(ApiResponse::success(Some(PersonalAccessTokensResponse { items }), None::<String>)))
+}
+
+pub async fn create_token(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<PersonalAccessTokenService>>,
+    Json(request): Json<CreatePersonalAccessTokenRequest>,
+) -> Result<Json<ApiResponse<CreatedPersonalAccessTokenResponse>>> {
+    request.validate()?;
+    let (token, secret) = service
+        .create(claims.sub, request.name, request.scopes, request.expires_in_days)
+        .await?;
+    Ok(Json(ApiResponse::success(Some(CreatedPersonalAccessTokenResponse { token, secret }), Some("Token created"))))
+}
+
+pub async fn revoke_token(
+    AuthUser(claims): AuthUser,
+    State(service): State<Arc<PersonalAccessTokenService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>> {
+    service.revoke(claims.sub, id).await?;
+    Ok(Json(ApiResponse::success(None, Some("Token revoked"))))
+}
@@ -8,7 +8,7 @@ use crate::{
     error::{AppError, Result},
     http::dto::{
         common_schema::ApiResponse,
-        provider_models_schema::{CreateProviderModelRequest, UpdateProviderModelRequest, ProviderModelIdResponse},
+        provider_models_schema::{CreateProviderModelRequest, UpdateProviderModelRequest, ProviderModelIdResponse, ImportProviderModelsRequest, DiscoveredModel, DiscoveredModelListResponse, ProviderModelListResponse},
     },
     models::provider_model,
     services::provider_model_service::ProviderModelService,
@@ -46,6 +46,8 @@ pub async fn update_model(
             id,
             request.model_id,
             request.name,
+            request.generation_params,
+            request.context_length,
         )
         .await?;
     if updated.provider_id != provider_id {
@@ -65,4 +67,29 @@ pub async fn delete_model(
     }
     state.delete(claims.sub, id).await?;
     Ok(Json(ApiResponse::success(Some(ProviderModelIdResponse { id }), Some("Model deleted"))))
-}
\ No newline at end of file
+}
+
+pub async fn discover_models(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<ProviderModelService>>,
+    Path(provider_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<DiscoveredModelListResponse>>> {
+    let items = state
+        .discover(claims.sub, provider_id)
+        .await?
+        .into_iter()
+        .map(|(model_id, imported)| DiscoveredModel { model_id, imported })
+        .collect();
+    Ok(Json(ApiResponse::success(Some(DiscoveredModelListResponse { items }), None::<String>)))
+}
+
+pub async fn import_models(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<ProviderModelService>>,
+    Path(provider_id): Path<Uuid>,
+    Json(request): Json<ImportProviderModelsRequest>,
+) -> Result<Json<ApiResponse<ProviderModelListResponse>>> {
+    request.validate()?;
+    let items = state.import(claims.sub, provider_id, request.model_ids).await?;
+    Ok(Json(ApiResponse::success(Some(ProviderModelListResponse { items }), Some("Models imported"))))
+}
@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use validator::Validate;
+
+use crate::{
+    error::Result,
+    http::{
+        dto::{
+            common_schema::ApiResponse,
+            user_schema::{CustomInstructionsResponse, UpdateCustomInstructionsRequest},
+        },
+        extractors::jwt::AuthUser,
+    },
+    models::user::UserPreferences,
+    services::user_service::UserService,
+};
+
+pub async fn get_preferences(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<UserService>>,
+) -> Result<Json<ApiResponse<UserPreferences>>> {
+    let preferences = state.get_preferences(claims.sub).await?;
+    Ok(Json(ApiResponse::success(Some(preferences), None::<String>)))
+}
+
+pub async fn update_preferences(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<UserService>>,
+    Json(request): Json<UserPreferences>,
+) -> Result<Json<ApiResponse<UserPreferences>>> {
+    let preferences = state.update_preferences(claims.sub, request).await?;
+    Ok(Json(ApiResponse::success(Some(preferences), Some("Preferences updated"))))
+}
+
+pub async fn get_custom_instructions(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<UserService>>,
+) -> Result<Json<ApiResponse<CustomInstructionsResponse>>> {
+    let custom_instructions = state.get_custom_instructions(claims.sub).await?;
+    Ok(Json(ApiResponse::success(Some(CustomInstructionsResponse { custom_instructions }), None::<String>)))
+}
+
+pub async fn update_custom_instructions(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<UserService>>,
+    Json(request): Json<UpdateCustomInstructionsRequest>,
+) -> Result<Json<ApiResponse<CustomInstructionsResponse>>> {
+    request.validate()?;
+    let custom_instructions = state.update_custom_instructions(claims.sub, request.custom_instructions).await?;
+    Ok(Json(ApiResponse::success(
+        Some(CustomInstructionsResponse { custom_instructions }),
+        Some("Custom instructions updated"),
+    )))
+}
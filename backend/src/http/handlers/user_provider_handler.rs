@@ -12,7 +12,7 @@ use crate::{
     http::{
         dto::{
             common_schema::ApiResponse,
-            provider_schema::{CreateProviderRequest, UpdateProviderRequest, ProviderWithModelsListResponse, ProviderWithModels, ProviderIdResponse},
+            provider_schema::{CreateProviderRequest, UpdateProviderRequest, UpdateProviderBudgetRequest, ProviderWithModelsListResponse, ProviderWithModels, ProviderIdResponse},
         },
         extractors::jwt::AuthUser,
     },
@@ -36,7 +36,7 @@ pub async fn create_provider(
 ) -> Result<Json<ApiResponse<user_provider::Model>>> {
     request.validate()?;
     let created = state
-        .create(claims.sub, request.name, request.provider_type, request.url, request.key)
+        .create(claims.sub, request.name, request.provider_type, request.url, request.key, request.options)
         .await?;
     Ok(Json(ApiResponse::success(Some(created), Some("Provider created"))))
 }
@@ -58,11 +58,21 @@ pub async fn update_provider(
 ) -> Result<Json<ApiResponse<user_provider::Model>>> {
     request.validate()?;
     let updated = state
-        .update(claims.sub, id, request.name, request.provider_type, request.url, request.key)
+        .update(claims.sub, id, request.name, request.provider_type, request.url, request.key, request.options)
         .await?;
     Ok(Json(ApiResponse::success(Some(updated), Some("Provider updated"))))
 }
 
+pub async fn update_provider_budget(
+    AuthUser(claims): AuthUser,
+    State(state): State<Arc<UserProviderService>>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UpdateProviderBudgetRequest>,
+) -> Result<Json<ApiResponse<user_provider::Model>>> {
+    let updated = state.update_budget(claims.sub, id, request.budget).await?;
+    Ok(Json(ApiResponse::success(Some(updated), Some("Provider budget updated"))))
+}
+
 pub async fn delete_provider(
     AuthUser(claims): AuthUser,
     State(state): State<Arc<UserProviderService>>,
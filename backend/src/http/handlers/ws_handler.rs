@@ -0,0 +1,217 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    Extension,
+    http::{header::AUTHORIZATION, HeaderMap},
+    response::Response,
+};
+use chrono::Utc;
+use futures::{SinkExt, Stream, StreamExt};
+use tokio::{sync::mpsc, task::JoinSet, time::Instant};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    error::{AppError, Result},
+    http::{
+        dto::{
+            auth_schema::Claims,
+            conversation_schema::StreamedEvent,
+            ws_schema::{SocketCommand, SocketMessage, SocketQuery},
+        },
+        extractors::jwt::{authenticate, AuthError, RequiredScope},
+    },
+    services::conversation_service::ConversationService,
+    state::AppState,
+};
+
+/// How often an open socket checks its token again, so revoking a personal
+/// access token also ends the sockets opened with it.
+const REVALIDATE_EVERY: Duration = Duration::from_secs(60);
+/// How long queued messages get to reach the client once the socket is closing.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The credentials a socket was opened with, checked again before each command.
+struct SocketAuth {
+    state: AppState,
+    token: String,
+    required: Option<RequiredScope>,
+}
+
+impl SocketAuth {
+    async fn check(&self) -> std::result::Result<Claims, AuthError> {
+        authenticate(&self.state, &self.token, self.required).await
+    }
+}
+
+/// Upgrades to a socket carrying commands and reply events for any number of
+/// the user's conversations. The token goes in the `Authorization` header or,
+/// for browsers, the `token` query parameter.
+pub async fn connect(
+    State(state): State<AppState>,
+    Query(query): Query<SocketQuery>,
+    required: Option<Extension<RequiredScope>>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .or(query.token)
+        .ok_or(AuthError::InvalidToken)?;
+    let auth = SocketAuth { state, token, required: required.map(|Extension(scope)| scope) };
+    let claims = auth.check().await?;
+
+    Ok(upgrade.on_upgrade(move |socket| serve(socket, auth, claims)))
+}
+
+/// Runs each command in its own task, so a long reply doesn't hold up the
+/// others. Closing the socket stops the streaming, not the generations. The
+/// socket is closed when its token expires or stops being accepted.
+async fn serve(socket: WebSocket, auth: SocketAuth, claims: Claims) {
+    let user_id = claims.sub;
+    let service = auth.state.conversation_service.clone();
+    let (mut sink, mut incoming) = socket.split();
+    let (tx, mut rx) = mpsc::unbounded_channel::<SocketMessage>();
+
+    let mut writer = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            let text = serde_json::to_string(&message).unwrap_or_default();
+            if sink.send(Message::Text(text.into())).await.is_err() {
+                return;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let expires_in = (claims.exp - Utc::now().timestamp()).max(0) as u64;
+    let mut expired = std::pin::pin!(tokio::time::sleep(Duration::from_secs(expires_in)));
+    let mut revalidate = tokio::time::interval_at(Instant::now() + REVALIDATE_EVERY, REVALIDATE_EVERY);
+
+    let mut commands = JoinSet::new();
+    let rejected = loop {
+        let frame = tokio::select! {
+            _ = &mut expired => break Some(AuthError::InvalidToken),
+            _ = revalidate.tick() => match auth.check().await {
+                Err(e) if is_rejection(&e) => break Some(e),
+                _ => continue,
+            },
+            frame = incoming.next() => frame,
+        };
+        let Some(Ok(frame)) = frame else { break None };
+        while commands.try_join_next().is_some() {}
+
+        let text = match frame {
+            Message::Text(text) => text,
+            Message::Close(_) => break None,
+            _ => continue,
+        };
+        match serde_json::from_str::<SocketCommand>(&text) {
+            Ok(command) => {
+                // The token may have been revoked since the socket was opened
+                match auth.check().await {
+                    Ok(_) => {
+                        commands.spawn(run_command(service.clone(), user_id, command, tx.clone()));
+                    }
+                    Err(e) if is_rejection(&e) => break Some(e),
+                    Err(e) => {
+                        let _ = tx.send(error_message(command.request_id(), &AppError::Authorization(e)));
+                    }
+                }
+            }
+            Err(e) => {
+                let _ = tx.send(error_message(None, &AppError::BadRequest(format!("Invalid command: {}", e))));
+            }
+        }
+    };
+
+    commands.shutdown().await;
+    if let Some(e) = rejected {
+        let _ = tx.send(error_message(None, &AppError::Authorization(e)));
+    }
+    drop(tx);
+    if tokio::time::timeout(CLOSE_TIMEOUT, &mut writer).await.is_err() {
+        writer.abort();
+    }
+}
+
+/// Whether a failed check means the token is no longer good, rather than
+/// that it couldn't be looked up.
+fn is_rejection(error: &AuthError) -> bool {
+    !matches!(error, AuthError::LookupFailed)
+}
+
+async fn run_command(
+    service: Arc<ConversationService>,
+    user_id: Uuid,
+    command: SocketCommand,
+    tx: mpsc::UnboundedSender<SocketMessage>,
+) {
+    let request_id = command.request_id();
+    if let Err(e) = execute(&service, user_id, command, &tx).await {
+        let _ = tx.send(error_message(request_id, &e));
+    }
+}
+
+async fn execute(
+    service: &ConversationService,
+    user_id: Uuid,
+    command: SocketCommand,
+    tx: &mpsc::UnboundedSender<SocketMessage>,
+) -> Result<()> {
+    match command {
+        SocketCommand::Send { request_id, conversation_id, request } => {
+            request.validate()?;
+            let stream = service
+                .send_message(
+                    user_id,
+                    conversation_id,
+                    request.content,
+                    request.attachment_ids,
+                    request.provider_model_id,
+                    request.params,
+                )
+                .await?;
+            let _ = tx.send(SocketMessage::Ack { request_id });
+            forward(conversation_id, stream, tx).await;
+        }
+        SocketCommand::Regenerate { request_id, conversation_id, message_id, request } => {
+            request.validate()?;
+            let stream = service
+                .regenerate_message(user_id, conversation_id, message_id, request.provider_model_id, request.params)
+                .await?;
+            let _ = tx.send(SocketMessage::Ack { request_id });
+            forward(conversation_id, stream, tx).await;
+        }
+        SocketCommand::Cancel { request_id, conversation_id } => {
+            service.cancel_generation(user_id, conversation_id).await?;
+            let _ = tx.send(SocketMessage::Ack { request_id });
+        }
+    }
+    Ok(())
+}
+
+async fn forward(
+    conversation_id: Uuid,
+    stream: impl Stream<Item = StreamedEvent>,
+    tx: &mpsc::UnboundedSender<SocketMessage>,
+) {
+    let mut stream = std::pin::pin!(stream);
+    while let Some(StreamedEvent { id, event }) = stream.next().await {
+        let message = SocketMessage::Event { conversation_id, event_id: id, event };
+        if tx.send(message).is_err() {
+            break;
+        }
+    }
+}
+
+fn error_message(request_id: Option<String>, error: &AppError) -> SocketMessage {
+    let (status, message) = error.status_and_message();
+    SocketMessage::Error { request_id, status: status.as_u16(), message: message.to_string() }
+}
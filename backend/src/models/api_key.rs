@@ -0,0 +1,46 @@
+use sea_orm::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::set_timestamp_before_save;
+
+/// A key for the OpenAI-compatible API. Only a hash of the secret is stored;
+/// the secret itself is shown once, when the key is created.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "api_keys")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// The start of the secret, to tell keys apart.
+    pub prefix: String,
+    #[serde(skip)]
+    pub key_hash: String,
+    pub last_used_at: Option<DateTimeWithTimeZone>,
+    /// Revoked keys are kept so their usage stays on record.
+    pub revoked_at: Option<DateTimeWithTimeZone>,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::models::user::Entity",
+        from = "Column::UserId",
+        to = "crate::models::user::Column::Id"
+    )]
+    User,
+    #[sea_orm(has_many = "crate::models::api_key_usage::Entity")]
+    Usage,
+}
+
+impl Related<crate::models::user::Entity> for Entity {
+    fn to() -> RelationDef { Relation::User.def() }
+}
+
+impl Related<crate::models::api_key_usage::Entity> for Entity {
+    fn to() -> RelationDef { Relation::Usage.def() }
+}
+
+set_timestamp_before_save!(ActiveModel);
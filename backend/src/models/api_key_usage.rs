@@ -0,0 +1,51 @@
+use rust_decimal::Decimal;
+use sea_orm::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::set_timestamp_before_save;
+
+/// One request made through the OpenAI-compatible API.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "api_key_usage")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub api_key_id: Uuid,
+    /// Unset once the model is deleted.
+    pub provider_model_id: Option<Uuid>,
+    /// The model as the request named it.
+    pub model: String,
+    /// Estimated when the provider doesn't report it.
+    pub prompt_tokens: Option<i32>,
+    pub completion_tokens: Option<i32>,
+    pub reasoning_tokens: Option<i32>,
+    pub cost: Option<Decimal>,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::models::api_key::Entity",
+        from = "Column::ApiKeyId",
+        to = "crate::models::api_key::Column::Id"
+    )]
+    ApiKey,
+    #[sea_orm(
+        belongs_to = "crate::models::provider_model::Entity",
+        from = "Column::ProviderModelId",
+        to = "crate::models::provider_model::Column::Id"
+    )]
+    ProviderModel,
+}
+
+impl Related<crate::models::api_key::Entity> for Entity {
+    fn to() -> RelationDef { Relation::ApiKey.def() }
+}
+
+impl Related<crate::models::provider_model::Entity> for Entity {
+    fn to() -> RelationDef { Relation::ProviderModel.def() }
+}
+
+set_timestamp_before_save!(ActiveModel);
@@ -1,7 +1,8 @@
-use sea_orm::prelude::*;
+use rust_decimal::Decimal;
+use sea_orm::{prelude::*, FromJsonQueryResult};
 use serde::{Deserialize, Serialize};
 
-use crate::set_timestamp_before_save;
+use crate::{models::provider_model::GenerationParams, set_timestamp_before_save};
 
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(
@@ -16,6 +17,8 @@ pub enum ChatRole {
     User,
     #[sea_orm(string_value = "assistant")]
     Assistant,
+    #[sea_orm(string_value = "tool")]
+    Tool,
 }
 
 impl ChatRole {
@@ -24,22 +27,87 @@ impl ChatRole {
             ChatRole::System => "system",
             ChatRole::User => "user",
             ChatRole::Assistant => "assistant",
+            ChatRole::Tool => "tool",
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Text",
+    enum_name = "message_status"
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageStatus {
+    #[sea_orm(string_value = "streaming")]
+    Streaming,
+    #[sea_orm(string_value = "complete")]
+    Complete,
+    #[sea_orm(string_value = "error")]
+    Error,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+}
+
+/// A tool invocation requested by the model. `arguments` is kept as the raw
+/// JSON text the model produced, since it isn't guaranteed to be valid.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct ToolCalls(pub Vec<ToolCall>);
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "conversation_messages")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: Uuid,
     pub session_id: Uuid,
+    /// The message this one follows; `None` for the first message. Edits and
+    /// regenerated replies are siblings sharing a parent.
+    pub parent_id: Option<Uuid>,
     pub role: ChatRole,
     pub content: String,
+    pub status: MessageStatus,
+    /// Why generation failed, for messages in the `error` state.
+    pub error: Option<String>,
+    pub provider_model_id: Option<Uuid>,
+    pub prompt_tokens: Option<i32>,
+    pub completion_tokens: Option<i32>,
+    /// The share of `completion_tokens` spent on reasoning, when the provider reports it.
+    pub reasoning_tokens: Option<i32>,
+    pub cost: Option<Decimal>,
+    /// Thinking the model streamed before its answer. Shown to the user but
+    /// never replayed to providers.
+    pub reasoning: Option<String>,
+    /// Tools the assistant asked to call in this turn.
+    #[sea_orm(column_type = "Json", nullable)]
+    pub tool_calls: Option<ToolCalls>,
+    /// The call a `tool` message answers.
+    pub tool_call_id: Option<String>,
+    /// The parameters an assistant reply was generated with.
+    #[sea_orm(column_type = "Json", nullable)]
+    pub generation_params: Option<GenerationParams>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
 
+/// A message on a session's active branch, with its place among the
+/// alternatives that share its parent.
+#[derive(Debug, Clone, Serialize)]
+pub struct BranchMessage {
+    #[serde(flatten)]
+    pub message: Model,
+    pub sibling_index: usize,
+    pub sibling_count: usize,
+}
+
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(
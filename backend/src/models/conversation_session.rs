@@ -1,15 +1,61 @@
-use sea_orm::prelude::*;
+use sea_orm::{prelude::*, FromJsonQueryResult};
 use serde::{Deserialize, Serialize};
 
-use crate::set_timestamp_before_save;
+use crate::{models::provider_model::GenerationParams, set_timestamp_before_save};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+/// Provider models to try, in order, when the requested one keeps failing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct ModelIds(pub Vec<Uuid>);
+
+/// How history that no longer fits the model's context window is shortened.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContextStrategy {
+    /// Drops the oldest turns until the rest fits.
+    #[default]
+    DropOldest,
+    /// Sends only the last `messages` messages, dropping more if those still don't fit.
+    KeepLast { messages: u32 },
+    /// Replaces the turns that don't fit with a summary, cached on the session.
+    Summarize,
+}
+
+impl ContextStrategy {
+    pub fn is_valid(&self) -> bool {
+        !matches!(self, ContextStrategy::KeepLast { messages: 0 })
+    }
+}
+
+/// A summary of the start of a conversation, up to and including `through_message_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+pub struct ContextSummary {
+    pub text: String,
+    pub through_message_id: Uuid,
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "conversation_sessions")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: Uuid,
     pub user_id: Uuid,
     pub title: Option<String>,
+    /// The last message of the branch being shown and continued.
+    pub active_message_id: Option<Uuid>,
+    /// Sent as a system message after the user's custom instructions.
+    pub system_prompt: Option<String>,
+    #[sea_orm(column_type = "Json", nullable)]
+    pub fallback_model_ids: Option<ModelIds>,
+    /// Defaults for every message of the session, over those of the model.
+    #[sea_orm(column_type = "Json", nullable)]
+    pub generation_params: Option<GenerationParams>,
+    /// Overrides the user's default strategy.
+    #[sea_orm(column_type = "Json", nullable)]
+    pub context_strategy: Option<ContextStrategy>,
+    #[serde(skip_serializing)]
+    #[sea_orm(column_type = "Json", nullable)]
+    pub context_summary: Option<ContextSummary>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
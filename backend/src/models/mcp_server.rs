@@ -0,0 +1,80 @@
+use sea_orm::{prelude::*, FromJsonQueryResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::set_timestamp_before_save;
+
+/// How Palette talks to an MCP server.
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Text",
+    enum_name = "mcp_transport"
+)]
+#[serde(rename_all = "snake_case")]
+pub enum McpTransport {
+    /// A local process speaking JSON-RPC over stdin/stdout.
+    #[sea_orm(string_value = "stdio")]
+    Stdio,
+    /// A remote endpoint using the streamable HTTP transport.
+    #[sea_orm(string_value = "http")]
+    Http,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct StringList(pub Vec<String>);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct StringMap(pub BTreeMap<String, String>);
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "mcp_servers")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub transport: McpTransport,
+    /// Executable for `stdio` servers.
+    pub command: Option<String>,
+    #[sea_orm(column_type = "Json", nullable)]
+    pub args: Option<StringList>,
+    /// Extra environment variables for `stdio` servers.
+    #[sea_orm(column_type = "Json", nullable)]
+    pub env: Option<StringMap>,
+    /// Endpoint for `http` servers.
+    pub url: Option<String>,
+    /// Extra request headers for `http` servers, e.g. `Authorization`.
+    #[sea_orm(column_type = "Json", nullable)]
+    pub headers: Option<StringMap>,
+    pub enabled: bool,
+    /// Tools exposed to the model; all of the server's tools when unset.
+    #[sea_orm(column_type = "Json", nullable)]
+    pub allowed_tools: Option<StringList>,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::models::user::Entity",
+        from = "Column::UserId",
+        to = "crate::models::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<crate::models::user::Entity> for Entity {
+    fn to() -> RelationDef { Relation::User.def() }
+}
+
+impl Model {
+    pub fn allows(&self, tool: &str) -> bool {
+        self.allowed_tools.as_ref().is_none_or(|allowed| allowed.0.iter().any(|t| t == tool))
+    }
+}
+
+set_timestamp_before_save!(ActiveModel);
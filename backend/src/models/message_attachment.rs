@@ -0,0 +1,62 @@
+use sea_orm::{prelude::*, DerivePartialModel, FromQueryResult};
+use serde::{Deserialize, Serialize};
+
+use crate::set_timestamp_before_save;
+
+/// An uploaded image. It belongs to the uploader until it is sent with a
+/// message, after which it is replayed with that message on every turn.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "message_attachments")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Unset until the attachment is sent.
+    pub message_id: Option<Uuid>,
+    pub file_name: String,
+    pub mime_type: String,
+    /// Size of `data` in bytes.
+    pub size: i32,
+    #[serde(skip)]
+    pub data: Vec<u8>,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+/// Attachment metadata, for listings that shouldn't load the file contents.
+#[derive(Clone, Debug, PartialEq, Eq, DerivePartialModel, FromQueryResult, Serialize)]
+#[sea_orm(entity = "Entity")]
+pub struct AttachmentInfo {
+    pub id: Uuid,
+    pub message_id: Option<Uuid>,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size: i32,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::models::user::Entity",
+        from = "Column::UserId",
+        to = "crate::models::user::Column::Id"
+    )]
+    User,
+    #[sea_orm(
+        belongs_to = "crate::models::conversation_message::Entity",
+        from = "Column::MessageId",
+        to = "crate::models::conversation_message::Column::Id"
+    )]
+    Message,
+}
+
+impl Related<crate::models::user::Entity> for Entity {
+    fn to() -> RelationDef { Relation::User.def() }
+}
+
+impl Related<crate::models::conversation_message::Entity> for Entity {
+    fn to() -> RelationDef { Relation::Message.def() }
+}
+
+set_timestamp_before_save!(ActiveModel);
@@ -3,6 +3,12 @@ pub mod user_provider;
 pub mod provider_model;
 pub mod conversation_session;
 pub mod conversation_message;
+pub mod mcp_server;
+pub mod message_attachment;
+pub mod api_key;
+pub mod api_key_usage;
+pub mod personal_access_token;
+pub mod spending_record;
 
 
 #[macro_export]
@@ -0,0 +1,65 @@
+use sea_orm::{prelude::*, FromJsonQueryResult};
+use serde::{Deserialize, Serialize};
+
+use crate::set_timestamp_before_save;
+
+/// What a personal access token may be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    /// Creating conversations, sending messages and managing attachments.
+    Chat,
+    /// Listing conversations and reading their messages.
+    ReadConversations,
+    /// Managing providers, their models and MCP servers.
+    ManageProviders,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct TokenScopes(pub Vec<TokenScope>);
+
+/// A long-lived token for scripts and other automation, sent in place of a
+/// login token. Only a hash of the secret is stored.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "personal_access_tokens")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// The start of the secret, to tell tokens apart.
+    pub prefix: String,
+    #[serde(skip)]
+    pub token_hash: String,
+    #[sea_orm(column_type = "Json")]
+    pub scopes: TokenScopes,
+    /// The token never expires when unset.
+    pub expires_at: Option<DateTimeWithTimeZone>,
+    pub last_used_at: Option<DateTimeWithTimeZone>,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+impl Model {
+    /// Whether the token was granted any of `scopes`.
+    pub fn allows_any(&self, scopes: &[TokenScope]) -> bool {
+        self.scopes.0.iter().any(|scope| scopes.contains(scope))
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::models::user::Entity",
+        from = "Column::UserId",
+        to = "crate::models::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<crate::models::user::Entity> for Entity {
+    fn to() -> RelationDef { Relation::User.def() }
+}
+
+set_timestamp_before_save!(ActiveModel);
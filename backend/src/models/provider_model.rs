@@ -1,10 +1,95 @@
-use sea_orm::prelude::*;
+use sea_orm::{prelude::*, FromJsonQueryResult};
 use serde::{Deserialize, Serialize};
 use rust_decimal::Decimal;
+use validator::Validate;
 
-use crate::set_timestamp_before_save;
+use crate::{clients::llm_client::TokenUsage, set_timestamp_before_save};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+/// Share of the input price charged for prompt tokens read from the cache,
+/// as Anthropic bills them.
+const CACHE_READ_PRICE: Decimal = Decimal::from_parts(1, 0, 0, false, 1);
+/// Share of the input price charged for prompt tokens written to the cache.
+const CACHE_WRITE_PRICE: Decimal = Decimal::from_parts(125, 0, 0, false, 2);
+
+/// How much a model may think before it answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+        }
+    }
+
+    /// Thinking budget for providers that take one in tokens rather than a level.
+    pub fn budget_tokens(&self) -> u32 {
+        match self {
+            ReasoningEffort::Low => 2048,
+            ReasoningEffort::Medium => 8192,
+            ReasoningEffort::High => 24576,
+        }
+    }
+}
+
+/// Sampling settings for a model call. Unset fields are left to the provider.
+/// The ranges here hold for every provider; what each provider type accepts on
+/// top of that is checked by [`crate::clients::llm_client::check_params`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, FromJsonQueryResult, Validate)]
+pub struct GenerationParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 0.0, max = 2.0))]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 0.0, max = 1.0))]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 1))]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(length(min = 1, max = 16))]
+    pub stop: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = -2.0, max = 2.0))]
+    pub presence_penalty: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = -2.0, max = 2.0))]
+    pub frequency_penalty: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+}
+
+impl GenerationParams {
+    /// Fills the fields left unset here from `defaults`.
+    pub fn or(self, defaults: Option<&GenerationParams>) -> GenerationParams {
+        let Some(defaults) = defaults else { return self };
+        GenerationParams {
+            temperature: self.temperature.or(defaults.temperature),
+            top_p: self.top_p.or(defaults.top_p),
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            stop: self.stop.or_else(|| defaults.stop.clone()),
+            presence_penalty: self.presence_penalty.or(defaults.presence_penalty),
+            frequency_penalty: self.frequency_penalty.or(defaults.frequency_penalty),
+            seed: self.seed.or(defaults.seed),
+            reasoning_effort: self.reasoning_effort.or(defaults.reasoning_effort),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == GenerationParams::default()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "provider_models")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
@@ -14,6 +99,12 @@ pub struct Model {
     pub name: String,
     pub input_price_per_million: Decimal,
     pub output_price_per_million: Decimal,
+    /// Used for any parameter neither the request nor the session sets.
+    #[sea_orm(column_type = "Json", nullable)]
+    pub generation_params: Option<GenerationParams>,
+    /// Tokens the model accepts, prompt and reply together. History is only
+    /// shortened for models that set it.
+    pub context_length: Option<i32>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
@@ -32,4 +123,19 @@ impl Related<crate::models::user_provider::Entity> for Entity {
     fn to() -> RelationDef { Relation::Provider.def() }
 }
 
+impl Model {
+    /// Cost of a completion at this model's per-million-token prices. Cached
+    /// prompt tokens are charged a share of the input price.
+    pub fn cost_of(&self, usage: &TokenUsage) -> Decimal {
+        let million = Decimal::from(1_000_000);
+        let cached = usage.cache_read_tokens + usage.cache_write_tokens;
+        let input = Decimal::from(usage.prompt_tokens.saturating_sub(cached))
+            + Decimal::from(usage.cache_read_tokens) * CACHE_READ_PRICE
+            + Decimal::from(usage.cache_write_tokens) * CACHE_WRITE_PRICE;
+        (input * self.input_price_per_million
+            + Decimal::from(usage.completion_tokens) * self.output_price_per_million)
+            / million
+    }
+}
+
 set_timestamp_before_save!(ActiveModel);
\ No newline at end of file
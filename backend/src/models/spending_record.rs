@@ -0,0 +1,63 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::set_timestamp_before_save;
+
+/// The kind of model call that was billed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Text",
+    enum_name = "spend_source"
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SpendSource {
+    /// A reply in a conversation, including each step of a tool loop.
+    #[sea_orm(string_value = "reply")]
+    Reply,
+    /// Naming a new conversation.
+    #[sea_orm(string_value = "title")]
+    Title,
+    /// Summarizing history that no longer fits the context window.
+    #[sea_orm(string_value = "summary")]
+    Summary,
+    /// A request to the OpenAI-compatible API.
+    #[sea_orm(string_value = "api")]
+    Api,
+}
+
+/// An entry in the append-only spending ledger that budgets are checked
+/// against. Nothing but the user's own deletion removes entries.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "spending_records")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Kept after the provider is deleted, so not a foreign key.
+    pub provider_id: Option<Uuid>,
+    pub provider_model_id: Option<Uuid>,
+    pub model: String,
+    pub source: SpendSource,
+    pub prompt_tokens: Option<i32>,
+    pub completion_tokens: Option<i32>,
+    pub cost: Decimal,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "crate::models::user::Entity",
+        from = "Column::UserId",
+        to = "crate::models::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<crate::models::user::Entity> for Entity {
+    fn to() -> RelationDef { Relation::User.def() }
+}
+
+set_timestamp_before_save!(ActiveModel);
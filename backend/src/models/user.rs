@@ -1,7 +1,54 @@
-use sea_orm::prelude::*;
+use rust_decimal::Decimal;
+use sea_orm::{prelude::*, FromJsonQueryResult};
 use serde::{Deserialize, Serialize};
 
-use crate::set_timestamp_before_save;
+use crate::{models::conversation_session::ContextStrategy, set_timestamp_before_save};
+
+/// Spending limits in the same currency as the model prices.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+pub struct SpendingBudget {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_limit: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_limit: Option<Decimal>,
+    /// Percentage of a limit at which a warning is streamed; 80 when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warn_at_percent: Option<u8>,
+}
+
+impl SpendingBudget {
+    pub const DEFAULT_WARN_AT_PERCENT: u8 = 80;
+
+    pub fn is_valid(&self) -> bool {
+        let limits_ok = [self.daily_limit, self.monthly_limit]
+            .iter()
+            .flatten()
+            .all(|limit| limit.is_sign_positive());
+        let percent_ok = self.warn_at_percent.is_none_or(|p| (1..=100).contains(&p));
+        limits_ok && percent_ok
+    }
+}
+
+/// Longest custom instructions or system prompt accepted, in characters.
+pub const MAX_INSTRUCTIONS_LEN: u64 = 16_000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+pub struct UserPreferences {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<SpendingBudget>,
+    /// Default fallback models for conversations that don't set their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_model_ids: Option<Vec<Uuid>>,
+    /// Sent as a system message at the start of every conversation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_instructions: Option<String>,
+    /// Default for conversations that don't set their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_strategy: Option<ContextStrategy>,
+    /// Client-side settings the backend doesn't interpret.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "users")]
@@ -15,7 +62,8 @@ pub struct Model {
     #[serde(skip_serializing)]
     pub password_hash: String,
     pub avatar: Option<String>,
-    pub preferences: Option<Json>,
+    #[sea_orm(column_type = "Json", nullable)]
+    pub preferences: Option<UserPreferences>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
     #[serde(skip_serializing)]
@@ -1,7 +1,7 @@
-use sea_orm::prelude::*;
+use sea_orm::{prelude::*, FromJsonQueryResult};
 use serde::{Deserialize, Serialize};
 
-use crate::set_timestamp_before_save;
+use crate::{models::user::SpendingBudget, set_timestamp_before_save};
 
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(
@@ -12,6 +12,49 @@ use crate::set_timestamp_before_save;
 pub enum ProviderType {
     #[sea_orm(string_value = "OpenAI")]
     OpenAI,
+    #[sea_orm(string_value = "Anthropic")]
+    Anthropic,
+    #[sea_orm(string_value = "Gemini")]
+    Gemini,
+    #[sea_orm(string_value = "Ollama")]
+    Ollama,
+    #[sea_orm(string_value = "Azure")]
+    Azure,
+    #[sea_orm(string_value = "Bedrock")]
+    Bedrock,
+}
+
+/// Which OpenAI endpoint an OpenAI provider streams from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenAiApiMode {
+    #[default]
+    ChatCompletions,
+    Responses,
+}
+
+/// Provider-specific settings that don't fit the common url/key columns.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+pub struct ProviderOptions {
+    /// Ollama context window size, forwarded as `options.num_ctx`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    /// Ollama model unload delay such as `5m` or `-1`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<String>,
+    /// Azure OpenAI `api-version` query parameter.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+    /// AWS signing region for Bedrock, when it can't be read from the URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Use the OpenAI Responses API instead of Chat Completions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_mode: Option<OpenAiApiMode>,
+    /// Ask for token usage with `stream_options.include_usage`. On unless set
+    /// to false, for OpenAI-compatible servers that reject unknown fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_usage: Option<bool>,
 }
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
@@ -24,6 +67,10 @@ pub struct Model {
     pub provider_type: ProviderType,
     pub url: String,
     pub key: Option<String>,
+    #[sea_orm(column_type = "Json", nullable)]
+    pub options: Option<ProviderOptions>,
+    #[sea_orm(column_type = "Json", nullable)]
+    pub budget: Option<SpendingBudget>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
@@ -0,0 +1,139 @@
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use std::collections::HashMap;
+use uuid::Uuid;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::{
+    error::{AppError, Result},
+    models::{api_key, api_key_usage},
+    repositories::conversation_message_repo::MessageUsage,
+    utils::ToUuidV7,
+};
+
+/// What a key has been used for so far.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApiKeyUsageTotals {
+    pub requests: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub cost: Decimal,
+}
+
+pub struct ApiKeyRepo {
+    pub pool: DatabaseConnection,
+}
+
+impl ApiKeyRepo {
+    pub fn new(pool: DatabaseConnection) -> Self { Self { pool } }
+
+    pub async fn list_by_user(&self, user_id: Uuid) -> Result<Vec<api_key::Model>> {
+        api_key::Entity::find()
+            .filter(api_key::Column::UserId.eq(user_id))
+            .order_by_desc(api_key::Column::CreatedAt)
+            .all(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    pub async fn create(&self, user_id: Uuid, name: String, prefix: String, key_hash: String) -> Result<api_key::Model> {
+        let active = api_key::ActiveModel {
+            id: Set(Utc::now().to_uuid_v7()),
+            user_id: Set(user_id),
+            name: Set(name),
+            prefix: Set(prefix),
+            key_hash: Set(key_hash),
+            ..Default::default()
+        };
+        active.insert(&self.pool).await.map_err(AppError::from)
+    }
+
+    /// The key with this hash, unless it has been revoked.
+    pub async fn get_active_by_hash(&self, key_hash: &str) -> Result<Option<api_key::Model>> {
+        api_key::Entity::find()
+            .filter(api_key::Column::KeyHash.eq(key_hash))
+            .filter(api_key::Column::RevokedAt.is_null())
+            .one(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// Returns whether a key that wasn't already revoked was found.
+    pub async fn revoke(&self, user_id: Uuid, id: Uuid) -> Result<bool> {
+        let res = api_key::Entity::update_many()
+            .col_expr(api_key::Column::RevokedAt, Expr::value(Utc::now()))
+            .col_expr(api_key::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(api_key::Column::UserId.eq(user_id))
+            .filter(api_key::Column::Id.eq(id))
+            .filter(api_key::Column::RevokedAt.is_null())
+            .exec(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+        Ok(res.rows_affected > 0)
+    }
+
+    pub async fn touch(&self, id: Uuid) -> Result<()> {
+        api_key::Entity::update_many()
+            .col_expr(api_key::Column::LastUsedAt, Expr::value(Utc::now()))
+            .filter(api_key::Column::Id.eq(id))
+            .exec(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    pub async fn record_usage(
+        &self,
+        api_key_id: Uuid,
+        provider_model_id: Uuid,
+        model: String,
+        usage: Option<MessageUsage>,
+    ) -> Result<api_key_usage::Model> {
+        let active = api_key_usage::ActiveModel {
+            id: Set(Utc::now().to_uuid_v7()),
+            api_key_id: Set(api_key_id),
+            provider_model_id: Set(Some(provider_model_id)),
+            model: Set(model),
+            prompt_tokens: Set(usage.as_ref().map(|u| u.prompt_tokens)),
+            completion_tokens: Set(usage.as_ref().map(|u| u.completion_tokens)),
+            reasoning_tokens: Set(usage.as_ref().and_then(|u| u.reasoning_tokens)),
+            cost: Set(usage.map(|u| u.cost)),
+            ..Default::default()
+        };
+        active.insert(&self.pool).await.map_err(AppError::from)
+    }
+
+    /// Usage of each of the user's keys that has been used, revoked ones included.
+    pub async fn usage_by_user(&self, user_id: Uuid) -> Result<HashMap<Uuid, ApiKeyUsageTotals>> {
+        let rows = api_key_usage::Entity::find()
+            .select_only()
+            .column(api_key_usage::Column::ApiKeyId)
+            .column_as(api_key_usage::Column::Id.count(), "requests")
+            .column_as(api_key_usage::Column::PromptTokens.sum(), "prompt_tokens")
+            .column_as(api_key_usage::Column::CompletionTokens.sum(), "completion_tokens")
+            .column_as(api_key_usage::Column::Cost.sum(), "cost")
+            .inner_join(api_key::Entity)
+            .filter(api_key::Column::UserId.eq(user_id))
+            .group_by(api_key_usage::Column::ApiKeyId)
+            .into_tuple::<(Uuid, i64, Option<i64>, Option<i64>, Option<Decimal>)>()
+            .all(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, requests, prompt_tokens, completion_tokens, cost)| {
+                let totals = ApiKeyUsageTotals {
+                    requests,
+                    prompt_tokens: prompt_tokens.unwrap_or_default(),
+                    completion_tokens: completion_tokens.unwrap_or_default(),
+                    cost: cost.unwrap_or_default(),
+                };
+                (id, totals)
+            })
+            .collect())
+    }
+}
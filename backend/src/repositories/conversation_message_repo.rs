@@ -1,9 +1,48 @@
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, DeleteResult, QueryOrder, TransactionTrait};
+use sea_orm::{ActiveEnum, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, DeleteResult, QueryOrder, TransactionTrait};
+use sea_orm::sea_query::Expr;
 use sea_orm::ActiveValue::Set;
+use std::collections::HashMap;
 use uuid::Uuid;
 use chrono::Utc;
+use rust_decimal::Decimal;
 
-use crate::{error::{AppError, Result}, models::conversation_message::{self, ChatRole}, utils::ToUuidV7};
+use crate::{clients::llm_client::TokenUsage, error::{AppError, Result}, models::{conversation_message::{self, BranchMessage, ChatRole, MessageStatus, ToolCall, ToolCalls}, conversation_session, provider_model::{self, GenerationParams}}, utils::ToUuidV7};
+
+/// Token accounting recorded on an assistant message.
+#[derive(Debug, Clone)]
+pub struct MessageUsage {
+    pub prompt_tokens: i32,
+    /// Includes `reasoning_tokens`.
+    pub completion_tokens: i32,
+    pub reasoning_tokens: Option<i32>,
+    pub cost: Decimal,
+}
+
+impl MessageUsage {
+    /// Usage as a provider reported it, priced at the model's rates.
+    pub fn priced(model: &provider_model::Model, usage: TokenUsage) -> Self {
+        Self {
+            prompt_tokens: usage.prompt_tokens as i32,
+            completion_tokens: usage.completion_tokens as i32,
+            reasoning_tokens: (usage.reasoning_tokens > 0).then_some(usage.reasoning_tokens as i32),
+            cost: model.cost_of(&usage),
+        }
+    }
+}
+
+/// The final state of an assistant message once its stream has ended.
+#[derive(Debug, Clone)]
+pub struct AssistantReply {
+    pub provider_model_id: Uuid,
+    pub content: String,
+    pub reasoning: Option<String>,
+    pub status: MessageStatus,
+    pub error: Option<String>,
+    pub usage: Option<MessageUsage>,
+    pub tool_calls: Vec<ToolCall>,
+    /// What the model that produced the reply was called with.
+    pub generation_params: GenerationParams,
+}
 
 pub struct ConversationMessageRepo {
     pub pool: DatabaseConnection,
@@ -12,37 +51,213 @@ pub struct ConversationMessageRepo {
 impl ConversationMessageRepo {
     pub fn new(pool: DatabaseConnection) -> Self { Self { pool } }
 
-    pub async fn create_pair(&self, session_id: Uuid, user_content: String, assistant_content: String) -> Result<conversation_message::Model> {
-        let txn = self.pool.begin().await.map_err(AppError::from)?;
-        
-        let user_msg_id = Utc::now().to_uuid_v7();
-        let user_msg = conversation_message::ActiveModel {
-            id: Set(user_msg_id),
+    pub async fn create_user_message(&self, session_id: Uuid, parent_id: Option<Uuid>, content: String) -> Result<conversation_message::Model> {
+        let active = conversation_message::ActiveModel {
+            id: Set(Utc::now().to_uuid_v7()),
             session_id: Set(session_id),
+            parent_id: Set(parent_id),
             role: Set(ChatRole::User),
-            content: Set(user_content),
+            content: Set(content),
+            status: Set(MessageStatus::Complete),
             ..Default::default()
         };
-        let _ = user_msg.insert(&txn).await.map_err(AppError::from)?;
+        self.insert_leaf(active).await
+    }
 
-        let asst_id = Utc::now().to_uuid_v7();
-        let asst_msg = conversation_message::ActiveModel {
-            id: Set(asst_id),
+    /// Inserts an empty assistant message in the `streaming` state.
+    pub async fn create_assistant_placeholder(
+        &self,
+        session_id: Uuid,
+        parent_id: Uuid,
+        provider_model_id: Uuid,
+    ) -> Result<conversation_message::Model> {
+        let active = conversation_message::ActiveModel {
+            id: Set(Utc::now().to_uuid_v7()),
             session_id: Set(session_id),
+            parent_id: Set(Some(parent_id)),
             role: Set(ChatRole::Assistant),
-            content: Set(assistant_content),
+            content: Set(String::new()),
+            status: Set(MessageStatus::Streaming),
+            provider_model_id: Set(Some(provider_model_id)),
+            ..Default::default()
+        };
+        self.insert_leaf(active).await
+    }
+
+    /// Puts a finished assistant message back into the `streaming` state for another attempt.
+    pub async fn restart_assistant(&self, id: Uuid, provider_model_id: Uuid) -> Result<conversation_message::Model> {
+        let active = conversation_message::ActiveModel {
+            id: Set(id),
+            content: Set(String::new()),
+            reasoning: Set(None),
+            status: Set(MessageStatus::Streaming),
+            error: Set(None),
+            provider_model_id: Set(Some(provider_model_id)),
+            prompt_tokens: Set(None),
+            completion_tokens: Set(None),
+            reasoning_tokens: Set(None),
+            cost: Set(None),
+            tool_calls: Set(None),
+            generation_params: Set(None),
             ..Default::default()
         };
-        let saved = asst_msg.insert(&txn).await.map_err(AppError::from)?;
-        
+        active.update(&self.pool).await.map_err(AppError::from)
+    }
+
+    pub async fn finish_assistant(&self, id: Uuid, reply: AssistantReply) -> Result<conversation_message::Model> {
+        let active = conversation_message::ActiveModel {
+            id: Set(id),
+            provider_model_id: Set(Some(reply.provider_model_id)),
+            content: Set(reply.content),
+            reasoning: Set(reply.reasoning),
+            status: Set(reply.status),
+            error: Set(reply.error),
+            prompt_tokens: Set(reply.usage.as_ref().map(|u| u.prompt_tokens)),
+            completion_tokens: Set(reply.usage.as_ref().map(|u| u.completion_tokens)),
+            reasoning_tokens: Set(reply.usage.as_ref().and_then(|u| u.reasoning_tokens)),
+            cost: Set(reply.usage.map(|u| u.cost)),
+            tool_calls: Set((!reply.tool_calls.is_empty()).then_some(ToolCalls(reply.tool_calls))),
+            generation_params: Set((!reply.generation_params.is_empty()).then_some(reply.generation_params)),
+            ..Default::default()
+        };
+        active.update(&self.pool).await.map_err(AppError::from)
+    }
+
+    /// Stores the result of a tool call. Failed calls keep the message the
+    /// model was shown in `content` and flag it in `error`.
+    pub async fn create_tool_message(
+        &self,
+        session_id: Uuid,
+        parent_id: Uuid,
+        tool_call_id: String,
+        content: String,
+        error: Option<String>,
+    ) -> Result<conversation_message::Model> {
+        let active = conversation_message::ActiveModel {
+            id: Set(Utc::now().to_uuid_v7()),
+            session_id: Set(session_id),
+            parent_id: Set(Some(parent_id)),
+            role: Set(ChatRole::Tool),
+            content: Set(content),
+            status: Set(MessageStatus::Complete),
+            error: Set(error),
+            tool_call_id: Set(Some(tool_call_id)),
+            ..Default::default()
+        };
+        self.insert_leaf(active).await
+    }
+
+    /// Inserts a message, extending its session's active branch if that ends
+    /// at the message's parent. Replies generated side by side thus leave the
+    /// branch on the first of them until the user switches.
+    async fn insert_leaf(&self, active: conversation_message::ActiveModel) -> Result<conversation_message::Model> {
+        let txn = self.pool.begin().await.map_err(AppError::from)?;
+        let message = active.insert(&txn).await.map_err(AppError::from)?;
+        let at_parent = match message.parent_id {
+            Some(parent_id) => conversation_session::Column::ActiveMessageId.eq(parent_id),
+            None => conversation_session::Column::ActiveMessageId.is_null(),
+        };
+        conversation_session::Entity::update_many()
+            .col_expr(conversation_session::Column::ActiveMessageId, Expr::value(message.id))
+            .filter(conversation_session::Column::Id.eq(message.session_id))
+            .filter(at_parent)
+            .exec(&txn)
+            .await
+            .map_err(AppError::from)?;
         txn.commit().await.map_err(AppError::from)?;
-        Ok(saved)
+        Ok(message)
+    }
+
+    /// Marks replies left `streaming` by a previous process as failed.
+    pub async fn fail_interrupted(&self) -> Result<u64> {
+        let res = conversation_message::Entity::update_many()
+            .col_expr(conversation_message::Column::Status, Expr::value(MessageStatus::Error.to_value()))
+            .col_expr(conversation_message::Column::Error, Expr::value("Interrupted by a server restart"))
+            .filter(conversation_message::Column::Status.eq(MessageStatus::Streaming))
+            .exec(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+        Ok(res.rows_affected)
+    }
+
+    pub async fn get_by_id(&self, id: Uuid) -> Result<Option<conversation_message::Model>> {
+        conversation_message::Entity::find_by_id(id)
+            .one(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// The session's active branch, from the first message down to the active one.
+    pub async fn list_by_session(&self, session_id: Uuid) -> Result<Vec<BranchMessage>> {
+        let active_message_id = conversation_session::Entity::find_by_id(session_id)
+            .one(&self.pool)
+            .await
+            .map_err(AppError::from)?
+            .and_then(|s| s.active_message_id);
+        let messages = self.list_all_by_session(session_id).await?;
+        let Some(leaf) = active_message_id.or_else(|| messages.last().map(|m| m.id)) else {
+            return Ok(Vec::new());
+        };
+
+        // Messages are in creation order, so siblings are too
+        let mut children: HashMap<Option<Uuid>, Vec<Uuid>> = HashMap::new();
+        for message in &messages {
+            children.entry(message.parent_id).or_default().push(message.id);
+        }
+        let mut by_id: HashMap<Uuid, conversation_message::Model> = messages.into_iter().map(|m| (m.id, m)).collect();
+
+        let mut branch = Vec::new();
+        let mut next = Some(leaf);
+        while let Some(message) = next.and_then(|id| by_id.remove(&id)) {
+            let siblings = &children[&message.parent_id];
+            next = message.parent_id;
+            branch.push(BranchMessage {
+                sibling_index: siblings.iter().position(|id| *id == message.id).unwrap_or_default(),
+                sibling_count: siblings.len(),
+                message,
+            });
+        }
+        branch.reverse();
+        Ok(branch)
+    }
+
+    /// The versions of a message: those sharing its parent, in creation order.
+    pub async fn list_siblings(&self, message: &conversation_message::Model) -> Result<Vec<conversation_message::Model>> {
+        let parent = match message.parent_id {
+            Some(parent_id) => conversation_message::Column::ParentId.eq(parent_id),
+            None => conversation_message::Column::ParentId.is_null(),
+        };
+        conversation_message::Entity::find()
+            .filter(conversation_message::Column::SessionId.eq(message.session_id))
+            .filter(parent)
+            .order_by_asc(conversation_message::Column::CreatedAt)
+            .order_by_asc(conversation_message::Column::Id)
+            .all(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// The newest branch below `id`, following the latest child at each step.
+    pub async fn latest_leaf(&self, session_id: Uuid, id: Uuid) -> Result<Uuid> {
+        let mut latest_child: HashMap<Uuid, Uuid> = HashMap::new();
+        for message in self.list_all_by_session(session_id).await? {
+            if let Some(parent_id) = message.parent_id {
+                latest_child.insert(parent_id, message.id);
+            }
+        }
+        let mut leaf = id;
+        while let Some(child) = latest_child.get(&leaf) {
+            leaf = *child;
+        }
+        Ok(leaf)
     }
 
-    pub async fn list_by_session(&self, session_id: Uuid) -> Result<Vec<conversation_message::Model>> {
+    /// Every message of the session across all branches, in creation order.
+    pub async fn list_all_by_session(&self, session_id: Uuid) -> Result<Vec<conversation_message::Model>> {
         conversation_message::Entity::find()
             .filter(conversation_message::Column::SessionId.eq(session_id))
             .order_by_asc(conversation_message::Column::CreatedAt)
+            .order_by_asc(conversation_message::Column::Id)
             .all(&self.pool)
             .await
             .map_err(AppError::from)
@@ -3,7 +3,7 @@ use sea_orm::ActiveValue::Set;
 use uuid::Uuid;
 use chrono::Utc;
 
-use crate::{error::{AppError, Result}, models::conversation_session, utils::ToUuidV7};
+use crate::{error::{AppError, Result}, models::{conversation_session::{self, ContextStrategy, ContextSummary, ModelIds}, provider_model::GenerationParams}, utils::ToUuidV7};
 
 pub struct ConversationSessionRepo {
     pub pool: DatabaseConnection,
@@ -54,4 +54,59 @@ impl ConversationSessionRepo {
         };
         active.update(&self.pool).await.map_err(AppError::from)
     }
+
+    pub async fn update_fallback_models(&self, id: Uuid, fallback_model_ids: Option<ModelIds>) -> Result<conversation_session::Model> {
+        let active = conversation_session::ActiveModel {
+            id: Set(id),
+            fallback_model_ids: Set(fallback_model_ids),
+            ..Default::default()
+        };
+        active.update(&self.pool).await.map_err(AppError::from)
+    }
+
+    pub async fn update_system_prompt(&self, id: Uuid, system_prompt: Option<String>) -> Result<conversation_session::Model> {
+        let active = conversation_session::ActiveModel {
+            id: Set(id),
+            system_prompt: Set(system_prompt),
+            ..Default::default()
+        };
+        active.update(&self.pool).await.map_err(AppError::from)
+    }
+
+    pub async fn update_active_message(&self, id: Uuid, message_id: Option<Uuid>) -> Result<conversation_session::Model> {
+        let active = conversation_session::ActiveModel {
+            id: Set(id),
+            active_message_id: Set(message_id),
+            ..Default::default()
+        };
+        active.update(&self.pool).await.map_err(AppError::from)
+    }
+
+    pub async fn update_context_strategy(&self, id: Uuid, strategy: Option<ContextStrategy>) -> Result<conversation_session::Model> {
+        let active = conversation_session::ActiveModel {
+            id: Set(id),
+            context_strategy: Set(strategy),
+            ..Default::default()
+        };
+        active.update(&self.pool).await.map_err(AppError::from)
+    }
+
+    pub async fn update_context_summary(&self, id: Uuid, summary: Option<ContextSummary>) -> Result<()> {
+        let active = conversation_session::ActiveModel {
+            id: Set(id),
+            context_summary: Set(summary),
+            ..Default::default()
+        };
+        active.update(&self.pool).await.map_err(AppError::from)?;
+        Ok(())
+    }
+
+    pub async fn update_generation_params(&self, id: Uuid, params: Option<GenerationParams>) -> Result<conversation_session::Model> {
+        let active = conversation_session::ActiveModel {
+            id: Set(id),
+            generation_params: Set(params),
+            ..Default::default()
+        };
+        active.update(&self.pool).await.map_err(AppError::from)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,145 @@
+use redis::{
+    aio::{ConnectionManager, MultiplexedConnection},
+    streams::{StreamMaxlen, StreamReadOptions, StreamReadReply},
+    AsyncCommands, Client,
+};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::error::Result;
+
+/// How long a generation's events stay replayable after its last one.
+const STREAM_TTL_SECS: i64 = 3600;
+/// Cap on the events buffered per generation; long replies stream a few thousand deltas.
+const MAX_STREAM_LEN: usize = 20_000;
+/// Most entries returned by one read.
+const READ_BATCH: usize = 500;
+/// How long a follower's mark on a session lasts; followers renew it on every read.
+const FOLLOWER_TTL_SECS: u64 = 15;
+
+/// Appended once a generation is over, so followers know to stop. Never sent to clients.
+pub const END_EVENT: &str = "end";
+
+/// A buffered event: its stream entry ID, SSE event name and JSON data.
+#[derive(Debug, Clone)]
+pub struct StreamEntry {
+    pub id: String,
+    pub name: String,
+    pub data: String,
+}
+
+/// Buffers the events of in-flight generations in Redis streams, keyed by the
+/// assistant message each generation started with, so they can be replayed
+/// and followed from any connection or instance.
+pub struct GenerationStreamRepo {
+    client: Client,
+    conn: ConnectionManager,
+}
+
+impl GenerationStreamRepo {
+    pub fn new(client: Client, conn: ConnectionManager) -> Self { Self { client, conn } }
+
+    fn key(message_id: Uuid) -> String {
+        format!("palette:generation:{}", message_id)
+    }
+
+    fn session_key(session_id: Uuid) -> String {
+        format!("palette:session:{}:generation", session_id)
+    }
+
+    fn follower_key(session_id: Uuid) -> String {
+        format!("palette:session:{}:followed", session_id)
+    }
+
+    /// Buffers events under the entry IDs they were sent with, which must
+    /// increase, in one round trip.
+    pub async fn append(&self, message_id: Uuid, entries: &[StreamEntry]) -> Result<()> {
+        let key = Self::key(message_id);
+        let mut pipe = redis::pipe();
+        for entry in entries {
+            pipe.xadd_maxlen(&key, StreamMaxlen::Approx(MAX_STREAM_LEN), &entry.id, &[("name", &entry.name), ("data", &entry.data)])
+                .ignore();
+        }
+        pipe.expire(&key, STREAM_TTL_SECS).ignore();
+        let mut conn = self.conn.clone();
+        let _: () = pipe.query_async(&mut conn).await?;
+        Ok(())
+    }
+
+    /// Marks the end of a generation's events for its followers.
+    pub async fn end(&self, message_id: Uuid) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let _: String = conn
+            .xadd_maxlen(Self::key(message_id), StreamMaxlen::Approx(MAX_STREAM_LEN), "*", &[("name", END_EVENT), ("data", "")])
+            .await?;
+        Ok(())
+    }
+
+    /// Records the generation a session's followers should attach to, dropping
+    /// anything buffered by an earlier generation into the same message.
+    pub async fn set_current(&self, session_id: Uuid, message_id: Uuid) -> Result<()> {
+        let mut conn = self.conn.clone();
+        let _: () = redis::pipe()
+            .del(Self::key(message_id))
+            .ignore()
+            .set_ex(Self::session_key(session_id), message_id.to_string(), STREAM_TTL_SECS as u64)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Whether a follower has read any of the session's generations lately,
+    /// from this instance or another.
+    pub async fn is_followed(&self, session_id: Uuid) -> Result<bool> {
+        let mut conn = self.conn.clone();
+        Ok(conn.exists(Self::follower_key(session_id)).await?)
+    }
+
+    /// The session's latest generation, while its events are still buffered.
+    pub async fn current(&self, session_id: Uuid) -> Result<Option<Uuid>> {
+        let mut conn = self.conn.clone();
+        let id: Option<String> = conn.get(Self::session_key(session_id)).await?;
+        Ok(id.and_then(|id| Uuid::parse_str(&id).ok()))
+    }
+
+    pub async fn exists(&self, message_id: Uuid) -> Result<bool> {
+        let mut conn = self.conn.clone();
+        Ok(conn.exists(Self::key(message_id)).await?)
+    }
+
+    /// Opens a reader on its own connection, as a blocking read would stall
+    /// every other command on the shared one.
+    pub async fn reader(&self, session_id: Uuid, message_id: Uuid) -> Result<StreamReader> {
+        let conn = self.client.get_multiplexed_async_connection().await?;
+        Ok(StreamReader { conn, key: Self::key(message_id), follower_key: Self::follower_key(session_id) })
+    }
+}
+
+pub struct StreamReader {
+    conn: MultiplexedConnection,
+    key: String,
+    follower_key: String,
+}
+
+impl StreamReader {
+    /// Entries after `after`, waiting up to `block` for new ones when there are
+    /// none. Each read marks the session as followed, which keeps its
+    /// generations running after the client that started them has gone.
+    pub async fn read_after(&mut self, after: &str, block: Duration) -> Result<Vec<StreamEntry>> {
+        let _: () = self.conn.set_ex(&self.follower_key, 1, FOLLOWER_TTL_SECS.max(block.as_secs() + 5)).await?;
+        let options = StreamReadOptions::default().count(READ_BATCH).block(block.as_millis() as usize);
+        let reply: Option<StreamReadReply> = self.conn.xread_options(&[&self.key], &[after], &options).await?;
+        let entries = reply
+            .into_iter()
+            .flat_map(|reply| reply.keys)
+            .flat_map(|key| key.ids)
+            .map(|entry| StreamEntry {
+                name: entry.get("name").unwrap_or_default(),
+                data: entry.get("data").unwrap_or_default(),
+                id: entry.id,
+            })
+            .collect();
+        Ok(entries)
+    }
+}
@@ -0,0 +1,71 @@
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, DeleteResult};
+use sea_orm::ActiveValue::Set;
+use uuid::Uuid;
+use chrono::Utc;
+
+use crate::{error::{AppError, Result}, models::mcp_server, utils::ToUuidV7};
+
+pub struct McpServerRepo {
+    pub pool: DatabaseConnection,
+}
+
+impl McpServerRepo {
+    pub fn new(pool: DatabaseConnection) -> Self { Self { pool } }
+
+    pub async fn list_by_user(&self, user_id: Uuid) -> Result<Vec<mcp_server::Model>> {
+        mcp_server::Entity::find()
+            .filter(mcp_server::Column::UserId.eq(user_id))
+            .order_by_asc(mcp_server::Column::Name)
+            .all(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    pub async fn list_enabled_by_user(&self, user_id: Uuid) -> Result<Vec<mcp_server::Model>> {
+        mcp_server::Entity::find()
+            .filter(mcp_server::Column::UserId.eq(user_id))
+            .filter(mcp_server::Column::Enabled.eq(true))
+            .order_by_asc(mcp_server::Column::Name)
+            .all(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    pub async fn get_by_id_for_user(&self, user_id: Uuid, id: Uuid) -> Result<Option<mcp_server::Model>> {
+        mcp_server::Entity::find()
+            .filter(mcp_server::Column::UserId.eq(user_id))
+            .filter(mcp_server::Column::Id.eq(id))
+            .one(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    pub async fn get_by_name_for_user(&self, user_id: Uuid, name: &str) -> Result<Option<mcp_server::Model>> {
+        mcp_server::Entity::find()
+            .filter(mcp_server::Column::UserId.eq(user_id))
+            .filter(mcp_server::Column::Name.eq(name))
+            .one(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// Inserts a server for the user; the id and owner are assigned here.
+    pub async fn create(&self, user_id: Uuid, mut active: mcp_server::ActiveModel) -> Result<mcp_server::Model> {
+        active.id = Set(Utc::now().to_uuid_v7());
+        active.user_id = Set(user_id);
+        active.insert(&self.pool).await.map_err(AppError::from)
+    }
+
+    pub async fn update(&self, active: mcp_server::ActiveModel) -> Result<mcp_server::Model> {
+        active.update(&self.pool).await.map_err(AppError::from)
+    }
+
+    pub async fn delete_by_id_for_user(&self, user_id: Uuid, id: Uuid) -> Result<DeleteResult> {
+        mcp_server::Entity::delete_many()
+            .filter(mcp_server::Column::UserId.eq(user_id))
+            .filter(mcp_server::Column::Id.eq(id))
+            .exec(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+}
@@ -0,0 +1,112 @@
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, DeleteResult};
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use crate::{error::{AppError, Result}, models::message_attachment::{self, AttachmentInfo}, utils::ToUuidV7};
+
+pub struct MessageAttachmentRepo {
+    pub pool: DatabaseConnection,
+}
+
+impl MessageAttachmentRepo {
+    pub fn new(pool: DatabaseConnection) -> Self { Self { pool } }
+
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        file_name: String,
+        mime_type: String,
+        data: Vec<u8>,
+    ) -> Result<message_attachment::Model> {
+        let active = message_attachment::ActiveModel {
+            id: Set(Utc::now().to_uuid_v7()),
+            user_id: Set(user_id),
+            message_id: Set(None),
+            file_name: Set(file_name),
+            mime_type: Set(mime_type),
+            size: Set(data.len() as i32),
+            data: Set(data),
+            ..Default::default()
+        };
+        active.insert(&self.pool).await.map_err(AppError::from)
+    }
+
+    pub async fn get_by_id_for_user(&self, user_id: Uuid, id: Uuid) -> Result<Option<message_attachment::Model>> {
+        message_attachment::Entity::find()
+            .filter(message_attachment::Column::UserId.eq(user_id))
+            .filter(message_attachment::Column::Id.eq(id))
+            .one(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// Uploads of the user that haven't been sent yet, among `ids`.
+    pub async fn list_unattached_for_user(&self, user_id: Uuid, ids: &[Uuid]) -> Result<Vec<AttachmentInfo>> {
+        message_attachment::Entity::find()
+            .filter(message_attachment::Column::UserId.eq(user_id))
+            .filter(message_attachment::Column::Id.is_in(ids.iter().copied()))
+            .filter(message_attachment::Column::MessageId.is_null())
+            .into_partial_model::<AttachmentInfo>()
+            .all(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    pub async fn list_info_by_messages(&self, message_ids: &[Uuid]) -> Result<Vec<AttachmentInfo>> {
+        message_attachment::Entity::find()
+            .filter(message_attachment::Column::MessageId.is_in(message_ids.iter().copied()))
+            .order_by_asc(message_attachment::Column::Id)
+            .into_partial_model::<AttachmentInfo>()
+            .all(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// Attachments with their contents, for replaying messages to a provider.
+    pub async fn list_by_messages(&self, message_ids: &[Uuid]) -> Result<Vec<message_attachment::Model>> {
+        message_attachment::Entity::find()
+            .filter(message_attachment::Column::MessageId.is_in(message_ids.iter().copied()))
+            .order_by_asc(message_attachment::Column::Id)
+            .all(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// Links unsent uploads to the message they were sent with.
+    pub async fn attach(&self, user_id: Uuid, ids: &[Uuid], message_id: Uuid) -> Result<u64> {
+        let res = message_attachment::Entity::update_many()
+            .col_expr(message_attachment::Column::MessageId, Expr::value(message_id))
+            .col_expr(message_attachment::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(message_attachment::Column::UserId.eq(user_id))
+            .filter(message_attachment::Column::Id.is_in(ids.iter().copied()))
+            .filter(message_attachment::Column::MessageId.is_null())
+            .exec(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+        Ok(res.rows_affected)
+    }
+
+    /// Removes an upload that hasn't been sent; sent attachments go with their message.
+    pub async fn delete_unattached_for_user(&self, user_id: Uuid, id: Uuid) -> Result<DeleteResult> {
+        message_attachment::Entity::delete_many()
+            .filter(message_attachment::Column::UserId.eq(user_id))
+            .filter(message_attachment::Column::Id.eq(id))
+            .filter(message_attachment::Column::MessageId.is_null())
+            .exec(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// Clears out uploads that were never sent.
+    pub async fn delete_unattached_before(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let res = message_attachment::Entity::delete_many()
+            .filter(message_attachment::Column::MessageId.is_null())
+            .filter(message_attachment::Column::CreatedAt.lt(cutoff))
+            .exec(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+        Ok(res.rows_affected)
+    }
+}
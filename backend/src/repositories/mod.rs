@@ -2,4 +2,10 @@ pub mod user_repo;
 pub mod provider_repo;
 pub mod provider_model_repo;
 pub mod conversation_session_repo;
-pub mod conversation_message_repo;
\ No newline at end of file
+pub mod conversation_message_repo;
+pub mod mcp_server_repo;
+pub mod message_attachment_repo;
+pub mod generation_stream_repo;
+pub mod api_key_repo;
+pub mod personal_access_token_repo;
+pub mod spending_repo;
\ No newline at end of file
@@ -0,0 +1,84 @@
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use sea_orm::sea_query::Expr;
+use sea_orm::ActiveValue::Set;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    error::{AppError, Result},
+    models::personal_access_token::{self, TokenScopes},
+    utils::ToUuidV7,
+};
+
+pub struct PersonalAccessTokenRepo {
+    pub pool: DatabaseConnection,
+}
+
+impl PersonalAccessTokenRepo {
+    pub fn new(pool: DatabaseConnection) -> Self { Self { pool } }
+
+    pub async fn list_by_user(&self, user_id: Uuid) -> Result<Vec<personal_access_token::Model>> {
+        personal_access_token::Entity::find()
+            .filter(personal_access_token::Column::UserId.eq(user_id))
+            .order_by_desc(personal_access_token::Column::CreatedAt)
+            .all(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        name: String,
+        prefix: String,
+        token_hash: String,
+        scopes: TokenScopes,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<personal_access_token::Model> {
+        let active = personal_access_token::ActiveModel {
+            id: Set(Utc::now().to_uuid_v7()),
+            user_id: Set(user_id),
+            name: Set(name),
+            prefix: Set(prefix),
+            token_hash: Set(token_hash),
+            scopes: Set(scopes),
+            expires_at: Set(expires_at.map(Into::into)),
+            ..Default::default()
+        };
+        active.insert(&self.pool).await.map_err(AppError::from)
+    }
+
+    /// The token with this hash, unless it has expired.
+    pub async fn get_active_by_hash(&self, token_hash: &str) -> Result<Option<personal_access_token::Model>> {
+        personal_access_token::Entity::find()
+            .filter(personal_access_token::Column::TokenHash.eq(token_hash))
+            .filter(
+                personal_access_token::Column::ExpiresAt.is_null()
+                    .or(personal_access_token::Column::ExpiresAt.gt(Utc::now())),
+            )
+            .one(&self.pool)
+            .await
+            .map_err(AppError::from)
+    }
+
+    /// Returns whether the token was found.
+    pub async fn delete(&self, user_id: Uuid, id: Uuid) -> Result<bool> {
+        let res = personal_access_token::Entity::delete_many()
+            .filter(personal_access_token::Column::UserId.eq(user_id))
+            .filter(personal_access_token::Column::Id.eq(id))
+            .exec(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+        Ok(res.rows_affected > 0)
+    }
+
+    pub async fn touch(&self, id: Uuid) -> Result<()> {
+        personal_access_token::Entity::update_many()
+            .col_expr(personal_access_token::Column::LastUsedAt, Expr::value(Utc::now()))
+            .filter(personal_access_token::Column::Id.eq(id))
+            .exec(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+        Ok(())
+    }
+}
@@ -4,7 +4,7 @@ use uuid::Uuid;
 use chrono::Utc;
 use rust_decimal::Decimal;
 
-use crate::{error::{AppError, Result}, models::provider_model, utils::ToUuidV7};
+use crate::{error::{AppError, Result}, models::provider_model::{self, GenerationParams}, utils::ToUuidV7};
 
 pub struct ProviderModelRepo {
     pub pool: DatabaseConnection,
@@ -59,6 +59,7 @@ impl ProviderModelRepo {
         active.insert(&self.pool).await.map_err(AppError::from)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn update_model(
         &self,
         id: Uuid,
@@ -66,6 +67,8 @@ impl ProviderModelRepo {
         name: Option<String>,
         input_price: Option<Decimal>,
         output_price: Option<Decimal>,
+        generation_params: Option<Option<GenerationParams>>,
+        context_length: Option<Option<i32>>,
     ) -> Result<provider_model::Model> {
         let mut active = provider_model::ActiveModel {
             id: Set(id),
@@ -75,6 +78,8 @@ impl ProviderModelRepo {
         if let Some(v) = name { active.name = Set(v); }
         if let Some(v) = input_price { active.input_price_per_million = Set(v); }
         if let Some(v) = output_price { active.output_price_per_million = Set(v); }
+        if let Some(v) = generation_params { active.generation_params = Set(v); }
+        if let Some(v) = context_length { active.context_length = Set(v); }
         
         active.update(&self.pool).await.map_err(AppError::from)
     }
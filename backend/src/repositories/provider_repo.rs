@@ -3,7 +3,7 @@ use sea_orm::ActiveValue::Set;
 use uuid::Uuid;
 use chrono::Utc;
 
-use crate::{error::{AppError, Result}, models::{user_provider::{self, ProviderType}, provider_model}, utils::ToUuidV7};
+use crate::{error::{AppError, Result}, models::{user::SpendingBudget, user_provider::{self, ProviderOptions, ProviderType}, provider_model}, utils::ToUuidV7};
 
 pub struct ProviderRepo {
     pub pool: DatabaseConnection,
@@ -45,6 +45,7 @@ impl ProviderRepo {
         provider_type: ProviderType,
         url: String,
         key: Option<String>,
+        options: Option<ProviderOptions>,
     ) -> Result<user_provider::Model> {
         let id = Utc::now().to_uuid_v7();
         let active = user_provider::ActiveModel {
@@ -54,6 +55,7 @@ impl ProviderRepo {
             provider_type: Set(provider_type),
             url: Set(url),
             key: Set(key),
+            options: Set(options),
             ..Default::default()
         };
         active.insert(&self.pool).await.map_err(AppError::from)
@@ -66,6 +68,7 @@ impl ProviderRepo {
         provider_type: Option<ProviderType>,
         url: Option<String>,
         key: Option<Option<String>>,
+        options: Option<Option<ProviderOptions>>,
     ) -> Result<user_provider::Model> {
         let mut active = user_provider::ActiveModel {
             id: Set(id),
@@ -75,10 +78,20 @@ impl ProviderRepo {
         if let Some(v) = provider_type { active.provider_type = Set(v); }
         if let Some(v) = url { active.url = Set(v); }
         if let Some(v) = key { active.key = Set(v); }
+        if let Some(v) = options { active.options = Set(v); }
 
         active.update(&self.pool).await.map_err(AppError::from)
     }
 
+    pub async fn update_budget(&self, id: Uuid, budget: Option<SpendingBudget>) -> Result<user_provider::Model> {
+        let active = user_provider::ActiveModel {
+            id: Set(id),
+            budget: Set(budget),
+            ..Default::default()
+        };
+        active.update(&self.pool).await.map_err(AppError::from)
+    }
+
     pub async fn delete_by_id_for_user(&self, user_id: Uuid, id: Uuid) -> Result<DeleteResult> {
         user_provider::Entity::delete_many()
             .filter(user_provider::Column::UserId.eq(user_id))
@@ -0,0 +1,66 @@
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect};
+use sea_orm::ActiveValue::Set;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use crate::{
+    error::{AppError, Result},
+    models::{provider_model, spending_record::{self, SpendSource}},
+    repositories::conversation_message_repo::MessageUsage,
+    utils::ToUuidV7,
+};
+
+pub struct SpendingRepo {
+    pub pool: DatabaseConnection,
+}
+
+impl SpendingRepo {
+    pub fn new(pool: DatabaseConnection) -> Self { Self { pool } }
+
+    /// Adds a billed call to the user's and the model's provider's spending.
+    pub async fn record(
+        &self,
+        user_id: Uuid,
+        model: &provider_model::Model,
+        source: SpendSource,
+        usage: &MessageUsage,
+    ) -> Result<spending_record::Model> {
+        let active = spending_record::ActiveModel {
+            id: Set(Utc::now().to_uuid_v7()),
+            user_id: Set(user_id),
+            provider_id: Set(Some(model.provider_id)),
+            provider_model_id: Set(Some(model.id)),
+            model: Set(model.model_id.clone()),
+            source: Set(source),
+            prompt_tokens: Set(Some(usage.prompt_tokens)),
+            completion_tokens: Set(Some(usage.completion_tokens)),
+            cost: Set(usage.cost),
+            ..Default::default()
+        };
+        active.insert(&self.pool).await.map_err(AppError::from)
+    }
+
+    /// Total the user has spent since `since`, through conversations and the API alike.
+    pub async fn total_cost_for_user(&self, user_id: Uuid, since: DateTime<Utc>) -> Result<Decimal> {
+        self.total_cost(spending_record::Column::UserId.eq(user_id), since).await
+    }
+
+    /// Total spent on any of the provider's models since `since`.
+    pub async fn total_cost_for_provider(&self, provider_id: Uuid, since: DateTime<Utc>) -> Result<Decimal> {
+        self.total_cost(spending_record::Column::ProviderId.eq(provider_id), since).await
+    }
+
+    async fn total_cost(&self, owner: sea_orm::sea_query::SimpleExpr, since: DateTime<Utc>) -> Result<Decimal> {
+        let total = spending_record::Entity::find()
+            .select_only()
+            .column_as(spending_record::Column::Cost.sum(), "total")
+            .filter(owner)
+            .filter(spending_record::Column::CreatedAt.gte(since))
+            .into_tuple::<Option<Decimal>>()
+            .one(&self.pool)
+            .await
+            .map_err(AppError::from)?;
+        Ok(total.flatten().unwrap_or_default())
+    }
+}
@@ -4,7 +4,7 @@ use uuid::Uuid;
 use chrono::Utc;
 
 use crate::error::{AppError, Result};
-use crate::models::user;
+use crate::models::user::{self, UserPreferences};
 use crate::utils::ToUuidV7;
 
 pub struct UserRepo {
@@ -52,4 +52,13 @@ impl UserRepo {
         };
         active_model.insert(&self.pool).await.map_err(AppError::from)
     }
+
+    pub async fn update_preferences(&self, id: Uuid, preferences: Option<UserPreferences>) -> Result<user::Model> {
+        let active_model = user::ActiveModel {
+            id: Set(id),
+            preferences: Set(preferences),
+            ..Default::default()
+        };
+        active_model.update(&self.pool).await.map_err(AppError::from)
+    }
 }
\ No newline at end of file
@@ -1,10 +1,17 @@
 use axum::{
+    Extension,
     Router,
+    extract::DefaultBodyLimit,
     routing::{get, post, put, delete}
 };
 
 use crate::{
-    http::handlers::{auth_handler, user_provider_handler, provider_model_handler, conversation_handler},
+    http::{
+        extractors::jwt::RequiredScope,
+        handlers::{auth_handler, user_handler, user_provider_handler, provider_model_handler, conversation_handler, mcp_server_handler, attachment_handler, ws_handler, api_key_handler, openai_handler, personal_access_token_handler},
+    },
+    models::personal_access_token::TokenScope,
+    services::attachment_service::MAX_ATTACHMENT_BYTES,
     state::AppState,
 };
 
@@ -14,23 +21,101 @@ pub fn create_routes() -> Router<AppState> {
         .route("/api/auth/register", post(auth_handler::register))
         .route("/api/auth/login", post(auth_handler::login))
 
-        // User Providers
+        // User Preferences
+        .route("/api/users/me/preferences", get(user_handler::get_preferences))
+        .route("/api/users/me/preferences", put(user_handler::update_preferences))
+        .route("/api/users/me/instructions", get(user_handler::get_custom_instructions))
+        .route("/api/users/me/instructions", put(user_handler::update_custom_instructions))
+
+        // API Keys
+        .route("/api/api-keys", get(api_key_handler::list_api_keys))
+        .route("/api/api-keys", post(api_key_handler::create_api_key))
+        .route("/api/api-keys/{id}", delete(api_key_handler::revoke_api_key))
+
+        // Personal Access Tokens
+        .route("/api/tokens", get(personal_access_token_handler::list_tokens))
+        .route("/api/tokens", post(personal_access_token_handler::create_token))
+        .route("/api/tokens/{id}", delete(personal_access_token_handler::revoke_token))
+
+        // OpenAI-compatible API, authenticated with API keys
+        .route("/v1/models", get(openai_handler::list_models))
+        .route("/v1/chat/completions", post(openai_handler::chat_completions))
+
+        .merge(provider_routes())
+        .merge(conversation_routes())
+}
+
+/// Routes personal access tokens may use, given one of `scopes`. Everything
+/// else only accepts login tokens.
+fn scoped(routes: Router<AppState>, scopes: &'static [TokenScope]) -> Router<AppState> {
+    routes.route_layer(Extension(RequiredScope(scopes)))
+}
+
+fn provider_routes() -> Router<AppState> {
+    // Listing providers and their models is also needed to pick a model to chat with
+    let browse = Router::new()
         .route("/api/providers", get(user_provider_handler::list_providers))
+        .route("/api/providers/{id}", get(user_provider_handler::get_provider));
+
+    let manage = Router::new()
+        // User Providers
         .route("/api/providers", post(user_provider_handler::create_provider))
-        .route("/api/providers/{id}", get(user_provider_handler::get_provider))
         .route("/api/providers/{id}", put(user_provider_handler::update_provider))
         .route("/api/providers/{id}", delete(user_provider_handler::delete_provider))
+        .route("/api/providers/{id}/budget", put(user_provider_handler::update_provider_budget))
         .route("/api/providers/check/{id}", post(user_provider_handler::check_provider),)
 
         // Provider Models
         .route("/api/providers/{provider_id}/models", post(provider_model_handler::create_model))
+        .route("/api/providers/{provider_id}/models/discover", get(provider_model_handler::discover_models))
+        .route("/api/providers/{provider_id}/models/import", post(provider_model_handler::import_models))
         .route("/api/providers/{provider_id}/models/{id}", put(provider_model_handler::update_model))
         .route("/api/providers/{provider_id}/models/{id}", delete(provider_model_handler::delete_model))
 
-        // Conversations
+        // MCP Servers
+        .route("/api/mcp-servers", get(mcp_server_handler::list_servers))
+        .route("/api/mcp-servers", post(mcp_server_handler::create_server))
+        .route("/api/mcp-servers/{id}", get(mcp_server_handler::get_server))
+        .route("/api/mcp-servers/{id}", put(mcp_server_handler::update_server))
+        .route("/api/mcp-servers/{id}", delete(mcp_server_handler::delete_server))
+        .route("/api/mcp-servers/{id}/tools", get(mcp_server_handler::list_tools));
+
+    scoped(browse, &[TokenScope::ManageProviders, TokenScope::Chat])
+        .merge(scoped(manage, &[TokenScope::ManageProviders]))
+}
+
+fn conversation_routes() -> Router<AppState> {
+    let read = Router::new()
         .route("/api/conversations", get(conversation_handler::list_conversations))
-        .route("/api/conversations", post(conversation_handler::create_conversation))
         .route("/api/conversations/{id}/messages", get(conversation_handler::list_messages))
+        .route("/api/conversations/{id}/stream", get(conversation_handler::follow_generation))
+        .route("/api/conversations/{id}/system-prompt", get(conversation_handler::get_system_prompt))
+        .route("/api/attachments/{id}", get(attachment_handler::get_attachment));
+
+    let chat = Router::new()
+        // Conversations
+        .route("/api/conversations", post(conversation_handler::create_conversation))
         .route("/api/conversations/{id}/messages", post(conversation_handler::send_message))
+        .route("/api/conversations/{id}/compare", post(conversation_handler::compare_message))
+        .route("/api/conversations/{id}/messages/{message_id}/retry", post(conversation_handler::retry_message))
+        .route("/api/conversations/{id}/messages/{message_id}/edit", post(conversation_handler::edit_message))
+        .route("/api/conversations/{id}/messages/{message_id}/regenerate", post(conversation_handler::regenerate_message))
+        .route("/api/conversations/{id}/messages/{message_id}/switch", post(conversation_handler::switch_branch))
+        .route("/api/conversations/{id}/cancel", post(conversation_handler::cancel_generation))
+        .route("/api/conversations/{id}/fallbacks", put(conversation_handler::update_fallback_models))
+        .route("/api/conversations/{id}/params", put(conversation_handler::update_generation_params))
+        .route("/api/conversations/{id}/context-strategy", put(conversation_handler::update_context_strategy))
+        .route("/api/conversations/{id}/system-prompt", put(conversation_handler::update_system_prompt))
         .route("/api/conversations/{id}", delete(conversation_handler::delete_conversation))
-}
\ No newline at end of file
+
+        // Attachments (room is left for the multipart framing around the file)
+        .route("/api/attachments", post(attachment_handler::upload_attachment)
+            .layer(DefaultBodyLimit::max(MAX_ATTACHMENT_BYTES + 64 * 1024)))
+        .route("/api/attachments/{id}", delete(attachment_handler::delete_attachment))
+
+        // WebSocket transport for conversations
+        .route("/api/ws", get(ws_handler::connect));
+
+    scoped(read, &[TokenScope::ReadConversations])
+        .merge(scoped(chat, &[TokenScope::Chat]))
+}
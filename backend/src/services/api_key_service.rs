@@ -0,0 +1,69 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::api_key,
+    repositories::api_key_repo::{ApiKeyRepo, ApiKeyUsageTotals},
+    utils::{generate_secret, hash_secret},
+};
+
+/// Marks Palette API keys, so they're recognisable in config files and secret scanners.
+pub const API_KEY_PREFIX: &str = "pal-";
+/// Characters of a key kept in the clear to tell keys apart.
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+#[derive(Clone)]
+pub struct ApiKeyService {
+    pub repo: Arc<ApiKeyRepo>,
+}
+
+impl ApiKeyService {
+    pub fn new(repo: Arc<ApiKeyRepo>) -> Self {
+        Self { repo }
+    }
+
+    pub async fn list(&self, user_id: Uuid) -> Result<Vec<(api_key::Model, ApiKeyUsageTotals)>> {
+        let keys = self.repo.list_by_user(user_id).await?;
+        let mut usage = self.repo.usage_by_user(user_id).await?;
+        Ok(keys
+            .into_iter()
+            .map(|key| {
+                let totals = usage.remove(&key.id).unwrap_or_default();
+                (key, totals)
+            })
+            .collect())
+    }
+
+    /// Creates a key and returns it with its secret, which isn't stored.
+    pub async fn create(&self, user_id: Uuid, name: String) -> Result<(api_key::Model, String)> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::BadRequest("API key name is required".to_string()));
+        }
+        let secret = generate_secret(API_KEY_PREFIX);
+        let prefix = secret[..DISPLAY_PREFIX_LEN].to_string();
+        let key = self.repo.create(user_id, name, prefix, hash_secret(&secret)).await?;
+        Ok((key, secret))
+    }
+
+    pub async fn revoke(&self, user_id: Uuid, id: Uuid) -> Result<()> {
+        if self.repo.revoke(user_id, id).await? {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("API key not found".to_string()))
+        }
+    }
+
+    /// The key a secret belongs to, unless it has been revoked.
+    pub async fn authenticate(&self, secret: &str) -> Result<Option<api_key::Model>> {
+        if !secret.starts_with(API_KEY_PREFIX) {
+            return Ok(None);
+        }
+        let key = self.repo.get_active_by_hash(&hash_secret(secret)).await?;
+        if let Some(key) = &key {
+            self.repo.touch(key.id).await?;
+        }
+        Ok(key)
+    }
+}
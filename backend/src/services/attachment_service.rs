@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::message_attachment,
+    repositories::message_attachment_repo::MessageAttachmentRepo,
+};
+
+/// Largest image accepted; Anthropic rejects anything bigger.
+pub const MAX_ATTACHMENT_BYTES: usize = 5 * 1024 * 1024;
+pub const MAX_ATTACHMENTS_PER_MESSAGE: usize = 10;
+const MAX_FILE_NAME_LEN: usize = 255;
+
+/// Detects the image type from its magic bytes rather than trusting the upload's content type.
+fn sniff_image_type(data: &[u8]) -> Option<&'static str> {
+    match data {
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some("image/png"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ => None,
+    }
+}
+
+pub struct AttachmentService {
+    pub repo: Arc<MessageAttachmentRepo>,
+}
+
+impl AttachmentService {
+    pub fn new(repo: Arc<MessageAttachmentRepo>) -> Self {
+        Self { repo }
+    }
+
+    /// Stores an uploaded image until it is sent with a message.
+    pub async fn upload(&self, user_id: Uuid, file_name: Option<String>, data: Vec<u8>) -> Result<message_attachment::Model> {
+        if data.is_empty() {
+            return Err(AppError::BadRequest("The file is empty".to_string()));
+        }
+        if data.len() > MAX_ATTACHMENT_BYTES {
+            return Err(AppError::BadRequest(format!(
+                "Images can be at most {} MB",
+                MAX_ATTACHMENT_BYTES / (1024 * 1024)
+            )));
+        }
+        let mime_type = sniff_image_type(&data)
+            .ok_or_else(|| AppError::BadRequest("Only PNG, JPEG, GIF and WebP images are supported".to_string()))?;
+
+        let file_name = file_name
+            .map(|name| name.trim().chars().take(MAX_FILE_NAME_LEN).collect::<String>())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "image".to_string());
+
+        self.repo.create(user_id, file_name, mime_type.to_string(), data).await
+    }
+
+    pub async fn get(&self, user_id: Uuid, id: Uuid) -> Result<message_attachment::Model> {
+        match self.repo.get_by_id_for_user(user_id, id).await? {
+            Some(model) => Ok(model),
+            None => Err(AppError::NotFound("Attachment not found".to_string())),
+        }
+    }
+
+    /// Discards an upload that hasn't been sent yet.
+    pub async fn delete(&self, user_id: Uuid, id: Uuid) -> Result<()> {
+        let res = self.repo.delete_unattached_for_user(user_id, id).await?;
+        if res.rows_affected == 0 {
+            Err(AppError::NotFound("Attachment not found".to_string()))
+        } else {
+            Ok(())
+        }
+    }
+}
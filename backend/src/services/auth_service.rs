@@ -83,7 +83,7 @@ impl AuthService {
 
     fn generate_token(&self, user_id: uuid::Uuid) -> Result<String> {
         let now = Utc::now();
-        let exp = now.timestamp() + &self.jwt_config.expires_in;
+        let exp = now.timestamp() + self.jwt_config.expires_in;
 
         let claims = Claims { sub: user_id, exp };
 
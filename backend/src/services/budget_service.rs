@@ -0,0 +1,132 @@
+use chrono::{DateTime, Datelike, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::{user::SpendingBudget, user_provider},
+    repositories::{spending_repo::SpendingRepo, user_repo::UserRepo},
+};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetScope {
+    User,
+    Provider,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+/// A budget that has crossed its warning threshold but not its limit.
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetWarning {
+    pub scope: BudgetScope,
+    pub period: BudgetPeriod,
+    pub spent: Decimal,
+    pub limit: Decimal,
+}
+
+#[derive(Clone)]
+pub struct BudgetService {
+    pub user_repo: Arc<UserRepo>,
+    pub spending_repo: Arc<SpendingRepo>,
+}
+
+impl BudgetService {
+    pub fn new(user_repo: Arc<UserRepo>, spending_repo: Arc<SpendingRepo>) -> Self {
+        Self { user_repo, spending_repo }
+    }
+
+    /// Checks the user's and the provider's budgets before a request is sent.
+    /// Usage is only known once a reply finishes, so the turn that crosses a
+    /// limit is allowed to complete and the next one is refused.
+    pub async fn check(&self, user_id: Uuid, provider: &user_provider::Model) -> Result<Vec<BudgetWarning>> {
+        let user = self
+            .user_repo
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let mut warnings = Vec::new();
+        if let Some(budget) = user.preferences.and_then(|p| p.budget) {
+            self.check_budget(BudgetScope::User, user_id, &budget, &mut warnings).await?;
+        }
+        if let Some(budget) = provider.budget.as_ref() {
+            self.check_budget(BudgetScope::Provider, provider.id, budget, &mut warnings).await?;
+        }
+        Ok(warnings)
+    }
+
+    async fn check_budget(
+        &self,
+        scope: BudgetScope,
+        owner_id: Uuid,
+        budget: &SpendingBudget,
+        warnings: &mut Vec<BudgetWarning>,
+    ) -> Result<()> {
+        let now = Utc::now();
+        let warn_at = Decimal::from(budget.warn_at_percent.unwrap_or(SpendingBudget::DEFAULT_WARN_AT_PERCENT));
+
+        for (period, limit) in [
+            (BudgetPeriod::Daily, budget.daily_limit),
+            (BudgetPeriod::Monthly, budget.monthly_limit),
+        ] {
+            let Some(limit) = limit else { continue };
+            let since = period_start(period, now);
+            // Conversations and API requests draw on the same budgets
+            let spent = match scope {
+                BudgetScope::User => self.spending_repo.total_cost_for_user(owner_id, since).await?,
+                BudgetScope::Provider => self.spending_repo.total_cost_for_provider(owner_id, since).await?,
+            };
+
+            if spent >= limit {
+                return Err(AppError::LimitExceeded(format!(
+                    "{} {} budget of {} reached ({} spent)",
+                    scope.label(),
+                    period.label(),
+                    limit,
+                    spent.round_dp(4)
+                )));
+            }
+            if spent * Decimal::from(100) >= limit * warn_at {
+                warnings.push(BudgetWarning { scope, period, spent, limit });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BudgetScope {
+    fn label(self) -> &'static str {
+        match self {
+            BudgetScope::User => "User",
+            BudgetScope::Provider => "Provider",
+        }
+    }
+}
+
+impl BudgetPeriod {
+    fn label(self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "daily",
+            BudgetPeriod::Monthly => "monthly",
+        }
+    }
+}
+
+/// Budgets reset at midnight UTC and on the first of the month.
+fn period_start(period: BudgetPeriod, now: DateTime<Utc>) -> DateTime<Utc> {
+    let date = now.date_naive();
+    let date = match period {
+        BudgetPeriod::Daily => date,
+        BudgetPeriod::Monthly => date.with_day(1).unwrap_or(date),
+    };
+    date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
@@ -0,0 +1,141 @@
+use tiktoken_rs::{
+    cl100k_base_singleton, o200k_base_singleton, p50k_base_singleton, r50k_base_singleton,
+    tokenizer::{get_tokenizer, Tokenizer},
+    CoreBPE,
+};
+
+use crate::{
+    clients::llm_client::{ChatMessagePayload, ToolDefinition},
+    models::user_provider::{self, ProviderType},
+};
+
+/// Room left for the reply when the request doesn't set `max_tokens`, capped
+/// at a quarter of the window for small models.
+const DEFAULT_REPLY_TOKENS: u32 = 4096;
+/// Role markers and separators each message adds on top of its content.
+const MESSAGE_OVERHEAD: usize = 4;
+/// Rough cost of an image; providers charge between a few hundred and ~1.5k tokens.
+const IMAGE_TOKENS: usize = 1000;
+/// Longest summary of dropped turns, and the room kept free for it.
+pub const SUMMARY_MAX_TOKENS: u32 = 1024;
+
+pub const SUMMARY_PROMPT: &str = "You condense the start of a conversation so it can continue without it. \
+Write a concise summary of the conversation below, merged with the summary so far if there is one. \
+Keep facts, decisions, names, numbers, code identifiers and open questions; drop pleasantries. \
+Reply with the summary only.";
+
+/// Counts tokens with the model's BPE when it's a known OpenAI model, and
+/// estimates them from the text otherwise. Estimates err on the high side.
+pub struct TokenEstimator {
+    bpe: Option<&'static CoreBPE>,
+}
+
+impl TokenEstimator {
+    pub fn for_model(provider: &user_provider::Model, model_id: &str) -> Self {
+        let tokenizer = get_tokenizer(model_id).or_else(|| {
+            // OpenAI-style deployments often use custom names for current models
+            matches!(provider.provider_type, ProviderType::OpenAI | ProviderType::Azure).then_some(Tokenizer::O200kBase)
+        });
+        let bpe = tokenizer.map(|tokenizer| match tokenizer {
+            Tokenizer::O200kBase => o200k_base_singleton(),
+            Tokenizer::Cl100kBase => cl100k_base_singleton(),
+            Tokenizer::P50kBase | Tokenizer::P50kEdit => p50k_base_singleton(),
+            Tokenizer::R50kBase | Tokenizer::Gpt2 => r50k_base_singleton(),
+        });
+        Self { bpe }
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        match self.bpe {
+            Some(bpe) => bpe.encode_ordinary(text).len(),
+            // About four characters per token for ASCII text; scripts such as
+            // CJK come close to one token per character
+            None => {
+                let ascii = text.bytes().filter(u8::is_ascii).count();
+                let other = text.chars().filter(|c| !c.is_ascii()).count();
+                ascii.div_ceil(4) + other
+            }
+        }
+    }
+
+    pub fn count_message(&self, message: &ChatMessagePayload) -> usize {
+        let calls: usize = message
+            .tool_calls
+            .iter()
+            .map(|call| self.count(&call.name) + self.count(&call.arguments))
+            .sum();
+        MESSAGE_OVERHEAD + self.count(&message.content) + calls + message.images.len() * IMAGE_TOKENS
+    }
+
+    pub fn count_tools(&self, tools: &[ToolDefinition]) -> usize {
+        if tools.is_empty() {
+            return 0;
+        }
+        self.count(&serde_json::to_string(tools).unwrap_or_default())
+    }
+}
+
+/// Tokens left for the history once the reply and the tool definitions are accounted for.
+pub fn history_budget(context_length: u32, max_tokens: Option<u32>, tool_tokens: usize) -> usize {
+    let reply = max_tokens.unwrap_or(DEFAULT_REPLY_TOKENS.min(context_length / 4));
+    (context_length as usize).saturating_sub(reply as usize + tool_tokens)
+}
+
+/// A history split for a context window.
+pub struct Window {
+    /// The leading system messages, always kept.
+    pub system: Vec<ChatMessagePayload>,
+    /// The oldest turns, which don't fit.
+    pub dropped: Vec<ChatMessagePayload>,
+    pub kept: Vec<ChatMessagePayload>,
+}
+
+impl Window {
+    /// Splits `messages` so the kept ones fit in `budget` tokens, keeping at
+    /// most `keep_last` messages when set. The current turn, from the last user
+    /// message on, is never dropped. The kept history always starts at a user
+    /// message, so no tool result loses its call and providers that require a
+    /// user turn first accept it.
+    pub fn split(
+        messages: Vec<ChatMessagePayload>,
+        estimator: &TokenEstimator,
+        budget: usize,
+        keep_last: Option<usize>,
+    ) -> Self {
+        let leading = messages.iter().take_while(|m| m.role == "system").count();
+        let mut rest = messages;
+        let history = rest.split_off(leading);
+        let system = rest;
+
+        let counts: Vec<usize> = history.iter().map(|m| estimator.count_message(m)).collect();
+        let mut total: usize = system.iter().map(|m| estimator.count_message(m)).sum::<usize>() + counts.iter().sum::<usize>();
+        let current_turn = history.iter().rposition(|m| m.role == "user").unwrap_or(0);
+        let min_start = keep_last.map_or(0, |n| history.len().saturating_sub(n));
+
+        let mut start = 0;
+        while start < current_turn && (total > budget || start < min_start || history[start].role != "user") {
+            total -= counts[start];
+            start += 1;
+        }
+
+        let mut history = history;
+        let kept = history.split_off(start);
+        Window { system, dropped: history, kept }
+    }
+}
+
+/// Renders dropped turns as plain text for the model that summarizes them.
+pub fn transcript(messages: &[ChatMessagePayload]) -> String {
+    let mut lines = Vec::new();
+    for message in messages {
+        let mut line = format!("{}: {}", message.role, message.content);
+        if !message.images.is_empty() {
+            line.push_str(&format!(" [{} image(s)]", message.images.len()));
+        }
+        for call in &message.tool_calls {
+            line.push_str(&format!("\n{}: called {}({})", message.role, call.name, call.arguments));
+        }
+        lines.push(line);
+    }
+    lines.join("\n\n")
+}
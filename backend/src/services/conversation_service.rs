@@ -1,47 +1,144 @@
-use futures::Stream;
-use futures::StreamExt;
-use std::sync::Arc;
-use tokio::sync::mpsc;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::{
+    stream::{self, select_all},
+    Stream, StreamExt,
+};
+use std::{collections::{HashMap, HashSet}, sync::Arc, time::{Duration, Instant}};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use uuid::Uuid;
 
 use crate::{
-    clients::llm_client::{ChatMessagePayload, LlmClient},
+    clients::llm_client::{check_params, ChatMessagePayload, ImageData, LlmClient},
     error::{AppError, Result},
+    http::dto::conversation_schema::{ComparisonEvent, StreamedEvent},
     models::{
-        conversation_message::{self, ChatRole},
-        conversation_session,
+        conversation_message::{self, BranchMessage, ChatRole, MessageStatus, ToolCall},
+        conversation_session::{self, ContextStrategy, ModelIds},
+        message_attachment::{self, AttachmentInfo},
+        provider_model::{self, GenerationParams},
+        user_provider,
     },
     repositories::{
         conversation_message_repo::ConversationMessageRepo,
-        conversation_session_repo::ConversationSessionRepo, provider_model_repo::ProviderModelRepo,
-        provider_repo::ProviderRepo,
+        conversation_session_repo::ConversationSessionRepo, message_attachment_repo::MessageAttachmentRepo,
+        generation_stream_repo::{GenerationStreamRepo, StreamEntry, END_EVENT},
+        provider_model_repo::ProviderModelRepo,
+        provider_repo::ProviderRepo, user_repo::UserRepo,
+    },
+    services::{
+        attachment_service::MAX_ATTACHMENTS_PER_MESSAGE,
+        budget_service::{BudgetService, BudgetWarning},
+        generation::{Generation, GenerationRunner},
+        generation_registry::GenerationRegistry,
+        mcp_server_service::McpServerService,
     },
+    tools::registry::ToolRegistry,
+    utils::non_blank,
 };
 
+/// Most models one prompt can be sent to side by side.
+pub const MAX_COMPARED_MODELS: usize = 4;
+
+/// How long a follower waits on each read for new events.
+const FOLLOW_BLOCK: Duration = Duration::from_secs(10);
+/// Followers stop after this long without events, in case the generation died
+/// without ending its stream.
+const FOLLOW_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Replays stored messages to the provider. Failed and still-streaming
+/// replies are skipped; cancelled ones keep whatever was generated. Tool calls
+/// are only replayed alongside their results, as providers reject unanswered calls.
+/// `images` holds the attachments of each message.
+fn history_payload(
+    history: Vec<conversation_message::Model>,
+    mut images: HashMap<Uuid, Vec<ImageData>>,
+) -> Vec<ChatMessagePayload> {
+    let answered: HashSet<String> = history.iter().filter_map(|m| m.tool_call_id.clone()).collect();
+    history
+        .into_iter()
+        .filter(|m| matches!(m.status, MessageStatus::Complete | MessageStatus::Cancelled))
+        .filter_map(|m| {
+            let tool_calls: Vec<ToolCall> = m
+                .tool_calls
+                .map(|calls| calls.0)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|c| answered.contains(&c.id))
+                .collect();
+            let images = images.remove(&m.id).unwrap_or_default();
+            if m.content.is_empty() && images.is_empty() && tool_calls.is_empty() && m.role != ChatRole::Tool {
+                return None;
+            }
+            Some(ChatMessagePayload {
+                role: m.role.as_str().to_string(),
+                content: m.content,
+                images,
+                tool_calls,
+                tool_call_id: m.tool_call_id,
+                message_id: Some(m.id),
+            })
+        })
+        .collect()
+}
+
+fn image_data(attachment: message_attachment::Model) -> ImageData {
+    ImageData { mime_type: attachment.mime_type, data: STANDARD.encode(attachment.data) }
+}
+
+/// What a reply is generated with, resolved before the turn is stored.
+struct ReplySetup {
+    model: provider_model::Model,
+    provider: user_provider::Model,
+    params: GenerationParams,
+    budget_warnings: Vec<BudgetWarning>,
+    fallbacks: Vec<(provider_model::Model, user_provider::Model)>,
+    tools: ToolRegistry,
+    context_strategy: ContextStrategy,
+}
+
 #[derive(Clone)]
 pub struct ConversationService {
     pub session_repo: Arc<ConversationSessionRepo>,
     pub message_repo: Arc<ConversationMessageRepo>,
+    pub attachment_repo: Arc<MessageAttachmentRepo>,
+    pub user_repo: Arc<UserRepo>,
     pub provider_model_repo: Arc<ProviderModelRepo>,
     pub provider_repo: Arc<ProviderRepo>,
+    pub budget_service: Arc<BudgetService>,
     pub llm_client: Arc<dyn LlmClient>,
+    pub generations: Arc<GenerationRegistry>,
+    pub mcp_service: Arc<McpServerService>,
+    pub streams: Option<Arc<GenerationStreamRepo>>,
+    pub tools: ToolRegistry,
 }
 
 impl ConversationService {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         session_repo: Arc<ConversationSessionRepo>,
         message_repo: Arc<ConversationMessageRepo>,
+        attachment_repo: Arc<MessageAttachmentRepo>,
+        user_repo: Arc<UserRepo>,
         provider_model_repo: Arc<ProviderModelRepo>,
         provider_repo: Arc<ProviderRepo>,
+        budget_service: Arc<BudgetService>,
         llm_client: Arc<dyn LlmClient>,
+        mcp_service: Arc<McpServerService>,
+        streams: Option<Arc<GenerationStreamRepo>>,
     ) -> Self {
         Self {
             session_repo,
             message_repo,
+            attachment_repo,
+            user_repo,
             provider_model_repo,
             provider_repo,
+            budget_service,
             llm_client,
+            generations: Arc::new(GenerationRegistry::default()),
+            mcp_service,
+            streams,
+            tools: ToolRegistry::builtin(),
         }
     }
 
@@ -53,11 +150,12 @@ impl ConversationService {
         self.session_repo.create(user_id).await
     }
 
+    /// The session's active branch together with the metadata of its attachments.
     pub async fn list_messages(
         &self,
         user_id: Uuid,
         session_id: Uuid,
-    ) -> Result<Vec<conversation_message::Model>> {
+    ) -> Result<(Vec<BranchMessage>, Vec<AttachmentInfo>)> {
         let session = self
             .session_repo
             .get_by_id(session_id)
@@ -66,16 +164,258 @@ impl ConversationService {
         if session.user_id != user_id {
             return Err(AppError::Forbidden("Session not accessible".to_string()));
         }
-        self.message_repo.list_by_session(session_id).await
+        let messages = self.message_repo.list_by_session(session_id).await?;
+        let ids: Vec<Uuid> = messages.iter().map(|m| m.message.id).collect();
+        let attachments = self.attachment_repo.list_info_by_messages(&ids).await?;
+        Ok((messages, attachments))
     }
 
+    /// Continues the active branch with a new prompt.
     pub async fn send_message(
         &self,
         user_id: Uuid,
         session_id: Uuid,
         content: String,
+        attachment_ids: Vec<Uuid>,
+        provider_model_id: Uuid,
+        params: GenerationParams,
+    ) -> Result<impl Stream<Item = StreamedEvent>> {
+        let session = self
+            .session_repo
+            .get_by_id(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        if session.user_id != user_id {
+            return Err(AppError::Forbidden("Session not accessible".to_string()));
+        }
+
+        let history = self.active_branch(session.id).await?;
+        let mut replies = self
+            .start_turn(user_id, session, history, content, attachment_ids, &[provider_model_id], params)
+            .await?;
+        Ok(replies.remove(0).1)
+    }
+
+    /// Sends one prompt to several models at once. Their replies are siblings,
+    /// streamed as one response with each event tagged by its model; the first
+    /// continues the conversation unless the user switches to another. The
+    /// merged events carry no IDs; each reply can be followed on its own instead.
+    pub async fn compare_message(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        content: String,
+        attachment_ids: Vec<Uuid>,
+        provider_model_ids: Vec<Uuid>,
+        params: GenerationParams,
+    ) -> Result<impl Stream<Item = ComparisonEvent>> {
+        let mut seen = HashSet::new();
+        if !provider_model_ids.iter().all(|id| seen.insert(*id)) {
+            return Err(AppError::BadRequest("Each model can only be compared once".to_string()));
+        }
+        if !(2..=MAX_COMPARED_MODELS).contains(&provider_model_ids.len()) {
+            return Err(AppError::BadRequest(format!(
+                "Between 2 and {} models can be compared",
+                MAX_COMPARED_MODELS
+            )));
+        }
+
+        let session = self
+            .session_repo
+            .get_by_id(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        if session.user_id != user_id {
+            return Err(AppError::Forbidden("Session not accessible".to_string()));
+        }
+
+        let history = self.active_branch(session.id).await?;
+        let replies = self
+            .start_turn(user_id, session, history, content, attachment_ids, &provider_model_ids, params)
+            .await?;
+        Ok(select_all(replies.into_iter().map(|(provider_model_id, stream)| {
+            stream.map(move |streamed| ComparisonEvent { provider_model_id, event: streamed.event })
+        })))
+    }
+
+    /// Sends a new version of an earlier prompt. It becomes a sibling of the
+    /// original, so the branch that followed it is kept and can be switched back to.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn edit_message(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        message_id: Uuid,
+        content: String,
+        attachment_ids: Vec<Uuid>,
         provider_model_id: Uuid,
-    ) -> Result<impl Stream<Item = Result<String>>> {
+        params: GenerationParams,
+    ) -> Result<impl Stream<Item = StreamedEvent>> {
+        let session = self
+            .session_repo
+            .get_by_id(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        if session.user_id != user_id {
+            return Err(AppError::Forbidden("Session not accessible".to_string()));
+        }
+
+        let mut history = self.active_branch(session.id).await?;
+        let position = history
+            .iter()
+            .position(|m| m.id == message_id)
+            .ok_or_else(|| AppError::NotFound("Message not found".to_string()))?;
+        if history[position].role != ChatRole::User {
+            return Err(AppError::BadRequest("Only prompts can be edited".to_string()));
+        }
+        history.truncate(position);
+        let mut replies = self
+            .start_turn(user_id, session, history, content, attachment_ids, &[provider_model_id], params)
+            .await?;
+        Ok(replies.remove(0).1)
+    }
+
+    /// Stores a prompt after `history` and streams each model's reply to it.
+    /// Fallbacks only apply to a single model, as a comparison asks for specific ones.
+    #[allow(clippy::too_many_arguments)]
+    async fn start_turn(
+        &self,
+        user_id: Uuid,
+        session: conversation_session::Model,
+        history: Vec<conversation_message::Model>,
+        content: String,
+        attachment_ids: Vec<Uuid>,
+        provider_model_ids: &[Uuid],
+        params: GenerationParams,
+    ) -> Result<Vec<(Uuid, UnboundedReceiverStream<StreamedEvent>)>> {
+        let mut attachment_ids = attachment_ids;
+        attachment_ids.sort();
+        attachment_ids.dedup();
+        if content.trim().is_empty() && attachment_ids.is_empty() {
+            return Err(AppError::BadRequest("A message needs text or an attachment".to_string()));
+        }
+        if attachment_ids.len() > MAX_ATTACHMENTS_PER_MESSAGE {
+            return Err(AppError::BadRequest(format!(
+                "At most {} attachments can be sent with a message",
+                MAX_ATTACHMENTS_PER_MESSAGE
+            )));
+        }
+
+        let with_fallbacks = provider_model_ids.len() == 1;
+        let mut setups = Vec::with_capacity(provider_model_ids.len());
+        for provider_model_id in provider_model_ids {
+            setups.push(self.reply_setup(user_id, &session, *provider_model_id, params.clone(), with_fallbacks).await?);
+        }
+
+        let pending = self.attachment_repo.list_unattached_for_user(user_id, &attachment_ids).await?;
+        if pending.len() != attachment_ids.len() {
+            return Err(AppError::BadRequest("Attachment not found or already sent".to_string()));
+        }
+
+        // Build chat history before the new turn is stored
+        let parent_id = history.last().map(|m| m.id);
+        let images = self.history_images(&history).await?;
+        let mut messages: Vec<ChatMessagePayload> = self.system_message(user_id, &session).await?.into_iter().collect();
+        messages.extend(history_payload(history, images));
+
+        // An edit branches off the active path; point it at the new prompt's parent
+        if session.active_message_id != parent_id {
+            self.session_repo.update_active_message(session.id, parent_id).await?;
+        }
+        // The prompt is kept even if generation later fails
+        let user_message = self.message_repo.create_user_message(session.id, parent_id, content.clone()).await?;
+        let mut prompt = ChatMessagePayload::text(ChatRole::User.as_str(), content.clone());
+        prompt.message_id = Some(user_message.id);
+        if !attachment_ids.is_empty() {
+            self.attachment_repo.attach(user_id, &attachment_ids, user_message.id).await?;
+            let attachments = self.attachment_repo.list_by_messages(&[user_message.id]).await?;
+            prompt.images = attachments.into_iter().map(image_data).collect();
+        }
+        messages.push(prompt);
+        let mut replies = Vec::with_capacity(setups.len());
+        for setup in setups {
+            let assistant = self
+                .message_repo
+                .create_assistant_placeholder(session.id, user_message.id, setup.model.id)
+                .await?;
+            replies.push((assistant.id, setup));
+        }
+
+        let mut title_source = (session.title.is_none() && !content.trim().is_empty()).then_some(content);
+        let generations: Vec<Generation> = replies
+            .into_iter()
+            .map(|(message_id, setup)| {
+                // One title is enough
+                let title_source = title_source.take();
+                self.generation(&session, setup, messages.clone(), Some(user_message.id), message_id, title_source)
+            })
+            .collect();
+        let model_ids: Vec<Uuid> = generations.iter().map(|g| g.model.id).collect();
+        let streams = self.runner().spawn_group(generations);
+        Ok(model_ids.into_iter().zip(streams).collect())
+    }
+
+    /// Generates a failed or cancelled reply again in place, optionally with another model.
+    pub async fn retry_message(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        message_id: Uuid,
+        provider_model_id: Option<Uuid>,
+        params: GenerationParams,
+    ) -> Result<impl Stream<Item = StreamedEvent>> {
+        let session = self
+            .session_repo
+            .get_by_id(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        if session.user_id != user_id {
+            return Err(AppError::Forbidden("Session not accessible".to_string()));
+        }
+
+        let mut history = self.active_branch(session.id).await?;
+        let position = history
+            .iter()
+            .position(|m| m.id == message_id)
+            .ok_or_else(|| AppError::NotFound("Message not found".to_string()))?;
+        let message = &history[position];
+        if message.role != ChatRole::Assistant || position + 1 != history.len() {
+            return Err(AppError::Conflict("Only the latest reply can be retried".to_string()));
+        }
+        if !matches!(message.status, MessageStatus::Error | MessageStatus::Cancelled) {
+            return Err(AppError::Conflict("Only failed or cancelled replies can be retried".to_string()));
+        }
+
+        let provider_model_id = provider_model_id
+            .or(message.provider_model_id)
+            .ok_or_else(|| AppError::BadRequest("A model is required to retry this reply".to_string()))?;
+        let setup = self.reply_setup(user_id, &session, provider_model_id, params, true).await?;
+
+        history.truncate(position);
+        let prompt = history.iter().rev().find(|m| m.role == ChatRole::User);
+        let user_message_id = prompt.map(|m| m.id);
+        let title_source = prompt
+            .filter(|m| session.title.is_none() && !m.content.trim().is_empty())
+            .map(|m| m.content.clone());
+        let images = self.history_images(&history).await?;
+        let mut messages: Vec<ChatMessagePayload> = self.system_message(user_id, &session).await?.into_iter().collect();
+        messages.extend(history_payload(history, images));
+        let assistant = self.message_repo.restart_assistant(message_id, setup.model.id).await?;
+
+        let generation = self.generation(&session, setup, messages, user_message_id, assistant.id, title_source);
+        Ok(self.runner().spawn(generation))
+    }
+
+    /// Generates another answer to the prompt a reply on the active branch
+    /// answered. The new reply is a sibling of that answer, which is kept.
+    pub async fn regenerate_message(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        message_id: Uuid,
+        provider_model_id: Option<Uuid>,
+        params: GenerationParams,
+    ) -> Result<impl Stream<Item = StreamedEvent>> {
         let session = self
             .session_repo
             .get_by_id(session_id)
@@ -85,6 +425,210 @@ impl ConversationService {
             return Err(AppError::Forbidden("Session not accessible".to_string()));
         }
 
+        let mut history = self.active_branch(session.id).await?;
+        let position = history
+            .iter()
+            .position(|m| m.id == message_id)
+            .ok_or_else(|| AppError::NotFound("Message not found".to_string()))?;
+        let message = &history[position];
+        if message.role != ChatRole::Assistant {
+            return Err(AppError::BadRequest("Only replies can be regenerated".to_string()));
+        }
+        if message.status == MessageStatus::Streaming {
+            return Err(AppError::Conflict("The reply is still being generated".to_string()));
+        }
+        // A reply after tool calls answers the same prompt as the first one
+        let prompt_position = history[..position]
+            .iter()
+            .rposition(|m| m.role == ChatRole::User)
+            .ok_or_else(|| AppError::BadRequest("The reply has no prompt to answer".to_string()))?;
+
+        let provider_model_id = provider_model_id
+            .or(message.provider_model_id)
+            .ok_or_else(|| AppError::BadRequest("A model is required to regenerate this reply".to_string()))?;
+        let setup = self.reply_setup(user_id, &session, provider_model_id, params, true).await?;
+
+        history.truncate(prompt_position + 1);
+        let prompt = &history[prompt_position];
+        let user_message_id = prompt.id;
+        let title_source = (session.title.is_none() && !prompt.content.trim().is_empty()).then(|| prompt.content.clone());
+        let images = self.history_images(&history).await?;
+        let mut messages: Vec<ChatMessagePayload> = self.system_message(user_id, &session).await?.into_iter().collect();
+        messages.extend(history_payload(history, images));
+        // The new reply branches off at the prompt
+        self.session_repo.update_active_message(session.id, Some(user_message_id)).await?;
+        let assistant = self
+            .message_repo
+            .create_assistant_placeholder(session.id, user_message_id, setup.model.id)
+            .await?;
+
+        let generation = self.generation(&session, setup, messages, Some(user_message_id), assistant.id, title_source);
+        Ok(self.runner().spawn(generation))
+    }
+
+    /// Shows the sibling at `sibling_index` of a message on the active branch,
+    /// continuing down its most recent branch.
+    pub async fn switch_branch(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        message_id: Uuid,
+        sibling_index: usize,
+    ) -> Result<(Vec<BranchMessage>, Vec<AttachmentInfo>)> {
+        let session = self
+            .session_repo
+            .get_by_id(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        if session.user_id != user_id {
+            return Err(AppError::Forbidden("Session not accessible".to_string()));
+        }
+        if self.generations.is_running(session.id) {
+            return Err(AppError::Conflict("A reply is still being generated".to_string()));
+        }
+
+        let message = self
+            .message_repo
+            .get_by_id(message_id)
+            .await?
+            .filter(|m| m.session_id == session.id)
+            .ok_or_else(|| AppError::NotFound("Message not found".to_string()))?;
+        let siblings = self.message_repo.list_siblings(&message).await?;
+        let target = siblings
+            .get(sibling_index)
+            .ok_or_else(|| AppError::BadRequest(format!("The message has {} versions", siblings.len())))?;
+        let leaf = self.message_repo.latest_leaf(session.id, target.id).await?;
+        self.session_repo.update_active_message(session.id, Some(leaf)).await?;
+        self.list_messages(user_id, session.id).await
+    }
+
+    async fn active_branch(&self, session_id: Uuid) -> Result<Vec<conversation_message::Model>> {
+        let branch = self.message_repo.list_by_session(session_id).await?;
+        Ok(branch.into_iter().map(|m| m.message).collect())
+    }
+
+    /// Resolves the model a reply is generated with and everything checked
+    /// before anything is stored.
+    async fn reply_setup(
+        &self,
+        user_id: Uuid,
+        session: &conversation_session::Model,
+        provider_model_id: Uuid,
+        params: GenerationParams,
+        with_fallbacks: bool,
+    ) -> Result<ReplySetup> {
+        let (model, provider) = self.resolve_model(user_id, provider_model_id).await?;
+        let params = params.or(session.generation_params.as_ref());
+        check_params(&provider, &params.clone().or(model.generation_params.as_ref()))?;
+        let budget_warnings = self.budget_service.check(user_id, &provider).await?;
+        let fallbacks = if with_fallbacks {
+            self.fallback_models(user_id, session, model.id, &params).await?
+        } else {
+            Vec::new()
+        };
+        let tools = self.tools_for(user_id, &provider, &fallbacks).await?;
+        let context_strategy = self.context_strategy(user_id, session).await?;
+        Ok(ReplySetup { model, provider, params, budget_warnings, fallbacks, tools, context_strategy })
+    }
+
+    fn generation(
+        &self,
+        session: &conversation_session::Model,
+        setup: ReplySetup,
+        messages: Vec<ChatMessagePayload>,
+        user_message_id: Option<Uuid>,
+        message_id: Uuid,
+        title_source: Option<String>,
+    ) -> Generation {
+        Generation {
+            session_id: session.id,
+            user_message_id,
+            message_id,
+            provider: setup.provider,
+            model: setup.model,
+            messages,
+            fallbacks: setup.fallbacks,
+            params: setup.params,
+            context_strategy: setup.context_strategy,
+            context_summary: session.context_summary.clone(),
+            tools: setup.tools,
+            budget_warnings: setup.budget_warnings,
+            title_source,
+        }
+    }
+
+    /// The user's custom instructions followed by the session's system prompt,
+    /// as the single system message that opens the history. Neither is stored
+    /// as a message, so edits apply to the next turn of existing conversations.
+    async fn system_message(
+        &self,
+        user_id: Uuid,
+        session: &conversation_session::Model,
+    ) -> Result<Option<ChatMessagePayload>> {
+        let instructions = self
+            .user_repo
+            .get_user_by_id(user_id)
+            .await?
+            .and_then(|u| u.preferences)
+            .and_then(|p| p.custom_instructions);
+        let parts: Vec<String> = [instructions, session.system_prompt.clone()]
+            .into_iter()
+            .filter_map(non_blank)
+            .collect();
+        Ok((!parts.is_empty()).then(|| ChatMessagePayload::text(ChatRole::System.as_str(), parts.join("\n\n"))))
+    }
+
+    /// The session's context strategy, or the user's default one.
+    async fn context_strategy(&self, user_id: Uuid, session: &conversation_session::Model) -> Result<ContextStrategy> {
+        if let Some(strategy) = &session.context_strategy {
+            return Ok(strategy.clone());
+        }
+        let strategy = self
+            .user_repo
+            .get_user_by_id(user_id)
+            .await?
+            .and_then(|u| u.preferences)
+            .and_then(|p| p.context_strategy);
+        Ok(strategy.unwrap_or_default())
+    }
+
+    /// Loads the images attached to `history`, keyed by message.
+    async fn history_images(&self, history: &[conversation_message::Model]) -> Result<HashMap<Uuid, Vec<ImageData>>> {
+        let ids: Vec<Uuid> = history.iter().filter(|m| m.role == ChatRole::User).map(|m| m.id).collect();
+        let mut images: HashMap<Uuid, Vec<ImageData>> = HashMap::new();
+        for attachment in self.attachment_repo.list_by_messages(&ids).await? {
+            if let Some(message_id) = attachment.message_id {
+                images.entry(message_id).or_default().push(image_data(attachment));
+            }
+        }
+        Ok(images)
+    }
+
+    /// The built-in tools plus those of the user's MCP servers. Servers are
+    /// only contacted when one of the candidate models can call tools.
+    async fn tools_for(
+        &self,
+        user_id: Uuid,
+        provider: &user_provider::Model,
+        fallbacks: &[(provider_model::Model, user_provider::Model)],
+    ) -> Result<ToolRegistry> {
+        let mut tools = self.tools.clone();
+        let can_call_tools = std::iter::once(provider)
+            .chain(fallbacks.iter().map(|(_, p)| p))
+            .any(|p| self.llm_client.supports_tools(p));
+        if can_call_tools {
+            for tool in self.mcp_service.tools_for_user(user_id).await? {
+                tools.register(tool);
+            }
+        }
+        Ok(tools)
+    }
+
+    async fn resolve_model(
+        &self,
+        user_id: Uuid,
+        provider_model_id: Uuid,
+    ) -> Result<(provider_model::Model, user_provider::Model)> {
         let model = self
             .provider_model_repo
             .get_by_id(provider_model_id)
@@ -95,90 +639,255 @@ impl ConversationService {
             .get_by_id_for_user(user_id, model.provider_id)
             .await?
             .ok_or_else(|| AppError::Forbidden("Provider not accessible".to_string()))?;
+        Ok((model, provider))
+    }
 
-        // Build chat history
-        let history = self.message_repo.list_by_session(session.id).await?;
-        let mut messages_payload: Vec<ChatMessagePayload> = history
-            .into_iter()
-            .map(|m| ChatMessagePayload {
-                role: m.role.as_str().to_string(),
-                content: m.content,
-            })
-            .collect();
-        messages_payload.push(ChatMessagePayload {
-            role: ChatRole::User.as_str().to_string(),
-            content: content.clone(),
-        });
+    /// Sets the session's fallback list; `None` or an empty list defers to the user's default.
+    pub async fn update_fallback_models(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        fallback_model_ids: Option<Vec<Uuid>>,
+    ) -> Result<conversation_session::Model> {
+        let session = self
+            .session_repo
+            .get_by_id(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        if session.user_id != user_id {
+            return Err(AppError::Forbidden("Session not accessible".to_string()));
+        }
 
-        // Call LLM
-        let mut llm_stream = self
-            .llm_client
-            .chat(&provider, &model.model_id, messages_payload)
-            .await?;
+        let mut ids: Vec<Uuid> = Vec::new();
+        for id in fallback_model_ids.unwrap_or_default() {
+            if !ids.contains(&id) {
+                self.resolve_model(user_id, id).await?;
+                ids.push(id);
+            }
+        }
+        let ids = (!ids.is_empty()).then_some(ModelIds(ids));
+        self.session_repo.update_fallback_models(session.id, ids).await
+    }
 
-        let (tx, rx) = mpsc::unbounded_channel();
-
-        let message_repo = self.message_repo.clone();
-        let session_repo = self.session_repo.clone();
-        let llm_client_for_title = self.llm_client.clone();
-        let provider_for_title = provider.clone();
-        let model_id_for_title = model.model_id.clone();
-        let content_for_save = content.clone();
-        let session_id = session.id;
-        let session_title_is_none = session.title.is_none();
-
-        tokio::spawn(async move {
-            let mut full_response = String::new();
-
-            while let Some(item) = llm_stream.next().await {
-                match item {
-                    Ok(chunk) => {
-                        full_response.push_str(&chunk);
-                        if tx.send(Ok(chunk)).is_err() {
-                            // TODO
-                        }
-                    }
-                    Err(e) => {
-                        let _ = tx.send(Err(e));
-                        // TODO
-                    }
+    pub async fn get_system_prompt(&self, user_id: Uuid, session_id: Uuid) -> Result<Option<String>> {
+        let session = self
+            .session_repo
+            .get_by_id(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        if session.user_id != user_id {
+            return Err(AppError::Forbidden("Session not accessible".to_string()));
+        }
+        Ok(session.system_prompt)
+    }
+
+    /// Sets the session's system prompt; `None` or blank text removes it.
+    pub async fn update_system_prompt(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        system_prompt: Option<String>,
+    ) -> Result<conversation_session::Model> {
+        let session = self
+            .session_repo
+            .get_by_id(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        if session.user_id != user_id {
+            return Err(AppError::Forbidden("Session not accessible".to_string()));
+        }
+        self.session_repo.update_system_prompt(session.id, non_blank(system_prompt)).await
+    }
+
+    /// Sets the session's default generation parameters; `None` or an empty set clears them.
+    pub async fn update_generation_params(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        params: Option<GenerationParams>,
+    ) -> Result<conversation_session::Model> {
+        let session = self
+            .session_repo
+            .get_by_id(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        if session.user_id != user_id {
+            return Err(AppError::Forbidden("Session not accessible".to_string()));
+        }
+        // Sessions aren't tied to a model, so provider-specific limits are checked per message
+        let params = params.filter(|p| !p.is_empty());
+        self.session_repo.update_generation_params(session.id, params).await
+    }
+
+    /// Sets how the session's history is shortened once it outgrows the
+    /// model's context window; `None` falls back to the user's default.
+    pub async fn update_context_strategy(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        strategy: Option<ContextStrategy>,
+    ) -> Result<conversation_session::Model> {
+        let session = self
+            .session_repo
+            .get_by_id(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        if session.user_id != user_id {
+            return Err(AppError::Forbidden("Session not accessible".to_string()));
+        }
+        if strategy.as_ref().is_some_and(|s| !s.is_valid()) {
+            return Err(AppError::BadRequest("At least one message must be kept".to_string()));
+        }
+        self.session_repo.update_context_strategy(session.id, strategy).await
+    }
+
+    /// Resolves the session's fallback list, or the user's default one. Models
+    /// that were deleted, whose provider is over budget or doesn't accept `params`
+    /// are skipped rather than failing the turn, since they are only needed if the primary fails.
+    async fn fallback_models(
+        &self,
+        user_id: Uuid,
+        session: &conversation_session::Model,
+        primary_id: Uuid,
+        params: &GenerationParams,
+    ) -> Result<Vec<(provider_model::Model, user_provider::Model)>> {
+        let ids = match session.fallback_model_ids.clone() {
+            Some(ModelIds(ids)) => ids,
+            None => self
+                .user_repo
+                .get_user_by_id(user_id)
+                .await?
+                .and_then(|u| u.preferences)
+                .and_then(|p| p.fallback_model_ids)
+                .unwrap_or_default(),
+        };
+
+        let mut fallbacks: Vec<(provider_model::Model, user_provider::Model)> = Vec::new();
+        for id in ids {
+            if id == primary_id || fallbacks.iter().any(|(m, _)| m.id == id) {
+                continue;
+            }
+            let resolved = match self.resolve_model(user_id, id).await {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    tracing::debug!("Skipping fallback model {}: {}", id, e);
+                    continue;
                 }
+            };
+            if let Err(e) = self.budget_service.check(user_id, &resolved.1).await {
+                tracing::debug!("Skipping fallback model {}: {}", id, e);
+                continue;
             }
-
-            // Save to DB
-            if !full_response.is_empty() {
-                // We don't need the result here since we are in background task, but log error if needed
-                let _ = message_repo
-                    .create_pair(session_id, content_for_save.clone(), full_response)
-                    .await;
+            if let Err(e) = check_params(&resolved.1, &params.clone().or(resolved.0.generation_params.as_ref())) {
+                tracing::debug!("Skipping fallback model {}: {}", id, e);
+                continue;
             }
+            fallbacks.push(resolved);
+        }
+        Ok(fallbacks)
+    }
+
+    fn runner(&self) -> GenerationRunner {
+        GenerationRunner {
+            message_repo: self.message_repo.clone(),
+            session_repo: self.session_repo.clone(),
+            spending_repo: self.budget_service.spending_repo.clone(),
+            llm_client: self.llm_client.clone(),
+            generations: self.generations.clone(),
+            streams: self.streams.clone(),
+        }
+    }
+
+    /// Replays the buffered events of a generation after `last_event_id`, then
+    /// follows it live until it ends. Follows the session's latest generation
+    /// unless `message_id` names the assistant message another one started with.
+    pub async fn follow_generation(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        message_id: Option<Uuid>,
+        last_event_id: Option<String>,
+    ) -> Result<impl Stream<Item = StreamEntry>> {
+        let session = self
+            .session_repo
+            .get_by_id(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        if session.user_id != user_id {
+            return Err(AppError::Forbidden("Session not accessible".to_string()));
+        }
 
-            // Handle Title Generation (after message is done)
-            if session_title_is_none {
-                let title_messages = vec![
-                    ChatMessagePayload { role: "system".to_string(), content: "You are a conversation title assistant. Based on the user's message below, generate a short, clear title (max 20 characters). Do not include quotes or periods.".to_string() },
-                    ChatMessagePayload { role: "user".to_string(), content: content_for_save },
-                ];
-                // We use stream chat but just collect it since we don't have a non-streaming client anymore
-                if let Ok(mut stream) = llm_client_for_title
-                    .chat(&provider_for_title, &model_id_for_title, title_messages)
-                    .await
-                {
-                    let mut title_text = String::new();
-                    while let Some(res) = stream.next().await {
-                        if let Ok(chunk) = res {
-                            title_text.push_str(&chunk);
-                        }
+        let after = last_event_id.unwrap_or_else(|| "0".to_string());
+        let valid = after == "0"
+            || after.split_once('-').is_some_and(|(ms, seq)| ms.parse::<u64>().is_ok() && seq.parse::<u64>().is_ok());
+        if !valid {
+            return Err(AppError::BadRequest("Invalid Last-Event-ID".to_string()));
+        }
+
+        let streams = self
+            .streams
+            .as_ref()
+            .ok_or_else(|| AppError::NotFound("Following generations needs Redis, which isn't configured".to_string()))?;
+        let generation_id = match message_id {
+            Some(message_id) => self
+                .message_repo
+                .get_by_id(message_id)
+                .await?
+                .filter(|m| m.session_id == session.id)
+                .map(|m| m.id)
+                .ok_or_else(|| AppError::NotFound("Message not found".to_string()))?,
+            None => streams
+                .current(session.id)
+                .await?
+                .ok_or_else(|| AppError::NotFound("No recent generation to follow".to_string()))?,
+        };
+        if !streams.exists(generation_id).await? {
+            return Err(AppError::NotFound("The generation's events are no longer available".to_string()));
+        }
+
+        let reader = streams.reader(session.id, generation_id).await?;
+        let follow = stream::unfold(Some((reader, after, Instant::now())), |state| async move {
+            let (mut reader, after, last_event) = state?;
+            loop {
+                let mut entries = match reader.read_after(&after, FOLLOW_BLOCK).await {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        tracing::warn!("Failed to follow a generation: {}", e);
+                        return None;
                     }
-                    title_text = title_text.trim().to_string();
-                    if !title_text.is_empty() {
-                        let _ = session_repo.update_title(session_id, title_text).await;
+                };
+                let Some(last) = entries.last() else {
+                    // Nothing new; give up on generations that died without ending their stream
+                    if last_event.elapsed() > FOLLOW_IDLE_TIMEOUT {
+                        return None;
                     }
+                    continue;
+                };
+                let after = last.id.clone();
+                if let Some(end) = entries.iter().position(|e| e.name == END_EVENT) {
+                    entries.truncate(end);
+                    return Some((entries, None));
                 }
+                return Some((entries, Some((reader, after, Instant::now()))));
             }
         });
+        Ok(follow.flat_map(stream::iter))
+    }
 
-        Ok(UnboundedReceiverStream::new(rx))
+    pub async fn cancel_generation(&self, user_id: Uuid, session_id: Uuid) -> Result<()> {
+        let session = self
+            .session_repo
+            .get_by_id(session_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+        if session.user_id != user_id {
+            return Err(AppError::Forbidden("Session not accessible".to_string()));
+        }
+        if self.generations.cancel(session_id) {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("No generation in progress".to_string()))
+        }
     }
 
     pub async fn delete_session(&self, user_id: Uuid, session_id: Uuid) -> Result<()> {
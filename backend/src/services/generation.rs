@@ -0,0 +1,1033 @@
+use chrono::Utc;
+use futures::StreamExt;
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tokio::{sync::mpsc, time::Instant};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::{
+    clients::llm_client::{ChatMessagePayload, ChatStreamEvent, FinishReason, LlmClient, TokenUsage, ToolDefinition},
+    error::AppError,
+    http::dto::conversation_schema::{ConversationEvent, EventModel, StreamErrorCode, StreamedEvent, EVENT_PROTOCOL_VERSION},
+    models::{
+        conversation_message::{ChatRole, MessageStatus, ToolCall},
+        conversation_session::{ContextStrategy, ContextSummary},
+        provider_model::{self, GenerationParams},
+        spending_record::SpendSource,
+        user_provider,
+    },
+    repositories::{
+        conversation_message_repo::{AssistantReply, ConversationMessageRepo, MessageUsage},
+        conversation_session_repo::ConversationSessionRepo,
+        generation_stream_repo::{GenerationStreamRepo, StreamEntry},
+        spending_repo::SpendingRepo,
+    },
+    services::{
+        budget_service::BudgetWarning,
+        context_window::{
+            history_budget, transcript, TokenEstimator, Window, SUMMARY_MAX_TOKENS, SUMMARY_PROMPT,
+        },
+        generation_registry::GenerationRegistry,
+    },
+    tools::registry::{ToolOutput, ToolRegistry},
+    utils::ToUuidV7,
+};
+
+/// Upper bound on model calls in one turn, so a model that keeps calling
+/// tools can't loop forever. The last round of tool results is still stored.
+const MAX_TOOL_STEPS: usize = 8;
+
+/// A generation nobody has followed for this long is cancelled, so replies
+/// nobody will read aren't paid for.
+const DETACHED_STOP: Duration = Duration::from_secs(30);
+/// How often a generation checks whether anyone is still following it.
+const FOLLOWER_CHECK: Duration = Duration::from_secs(5);
+/// Most events buffered in one round trip to Redis.
+const BUFFER_BATCH: usize = 256;
+
+/// Everything a background generation needs once the request has been validated.
+pub struct Generation {
+    pub session_id: Uuid,
+    pub user_message_id: Option<Uuid>,
+    pub message_id: Uuid,
+    pub provider: user_provider::Model,
+    pub model: provider_model::Model,
+    pub messages: Vec<ChatMessagePayload>,
+    /// Models tried in order if `model` fails before producing output.
+    pub fallbacks: Vec<(provider_model::Model, user_provider::Model)>,
+    /// The request's parameters over the session's; each model's defaults fill the rest.
+    pub params: GenerationParams,
+    pub context_strategy: ContextStrategy,
+    /// The session's cached summary, for the `summarize` strategy.
+    pub context_summary: Option<ContextSummary>,
+    pub tools: ToolRegistry,
+    pub budget_warnings: Vec<BudgetWarning>,
+    /// The first prompt of an untitled session, used to generate its title.
+    pub title_source: Option<String>,
+}
+
+/// The shared handles a generation needs after the request that started it is gone.
+#[derive(Clone)]
+pub struct GenerationRunner {
+    pub message_repo: Arc<ConversationMessageRepo>,
+    pub session_repo: Arc<ConversationSessionRepo>,
+    pub spending_repo: Arc<SpendingRepo>,
+    pub llm_client: Arc<dyn LlmClient>,
+    pub generations: Arc<GenerationRegistry>,
+    /// Where events are buffered for followers, when Redis is configured.
+    pub streams: Option<Arc<GenerationStreamRepo>>,
+}
+
+impl GenerationRunner {
+    /// Streams a reply into an existing assistant message in the background.
+    /// Each model call ends as a complete, error or cancelled message; tool calls
+    /// add `tool` messages and a new assistant message for the next call.
+    pub fn spawn(self, generation: Generation) -> UnboundedReceiverStream<StreamedEvent> {
+        let cancel_token = self.generations.register(generation.session_id, generation.message_id);
+        let group_id = generation.message_id;
+        self.start(generation, group_id, cancel_token)
+    }
+
+    /// Streams several replies at once, as for a comparison. They are
+    /// registered together so cancelling the session stops all of them.
+    pub fn spawn_group(self, generations: Vec<Generation>) -> Vec<UnboundedReceiverStream<StreamedEvent>> {
+        let Some(first) = generations.first() else {
+            return Vec::new();
+        };
+        let (session_id, group_id) = (first.session_id, first.message_id);
+        let cancel_token = self.generations.register_group(session_id, group_id, generations.len());
+        generations
+            .into_iter()
+            .map(|generation| self.clone().start(generation, group_id, cancel_token.clone()))
+            .collect()
+    }
+
+    fn start(self, generation: Generation, group_id: Uuid, cancel_token: CancellationToken) -> UnboundedReceiverStream<StreamedEvent> {
+        let Generation {
+            session_id,
+            user_message_id,
+            message_id,
+            provider,
+            model,
+            messages,
+            fallbacks,
+            params,
+            context_strategy,
+            context_summary,
+            tools,
+            budget_warnings,
+            title_source,
+        } = generation;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let _ = tx.send(ConversationEvent::Start {
+            version: EVENT_PROTOCOL_VERSION,
+            session_id,
+            user_message_id,
+            assistant_message_id: message_id,
+            model: EventModel::from(&model),
+        });
+        for warning in budget_warnings {
+            let _ = tx.send(ConversationEvent::Warning(warning));
+        }
+        let (client_tx, client_rx) = mpsc::unbounded_channel();
+        tokio::spawn(relay_events(self.streams.clone(), session_id, message_id, cancel_token.clone(), rx, client_tx));
+
+        let run = Run {
+            runner: self,
+            tx,
+            cancel_token,
+            group_id,
+            session_id,
+            provider,
+            model,
+            fallbacks: fallbacks.into_iter(),
+            params,
+            context_strategy,
+            context_summary,
+            definitions: tools.definitions(),
+            tools,
+        };
+        tokio::spawn(run.execute(message_id, messages, title_source));
+
+        UnboundedReceiverStream::new(client_rx)
+    }
+}
+
+/// Passes each event of a generation on to the client that started it, and
+/// buffers it so the generation can be resumed and followed from other
+/// connections. Buffering runs alongside, so Redis never holds up the client.
+/// Without Redis nobody can reattach, so the generation is cancelled as soon
+/// as that client goes away; with it, once neither that client nor any
+/// follower has been attached for `DETACHED_STOP`.
+async fn relay_events(
+    streams: Option<Arc<GenerationStreamRepo>>,
+    session_id: Uuid,
+    message_id: Uuid,
+    cancel_token: CancellationToken,
+    mut rx: mpsc::UnboundedReceiver<ConversationEvent>,
+    tx: mpsc::UnboundedSender<StreamedEvent>,
+) {
+    let buffer = streams.clone().map(|streams| {
+        let (buffer_tx, buffer_rx) = mpsc::unbounded_channel();
+        (buffer_tx, tokio::spawn(buffer_events(streams, session_id, message_id, buffer_rx)))
+    });
+    // Entry IDs are given out here so events can be sent before they are
+    // buffered. Starting from the current time keeps them after those of
+    // any Last-Event-ID left from an earlier generation into the message.
+    let first_ms = Utc::now().timestamp_millis();
+    let mut seq = 0u64;
+
+    let mut check = tokio::time::interval(FOLLOWER_CHECK);
+    let mut detached_since: Option<Instant> = None;
+    loop {
+        tokio::select! {
+            event = rx.recv() => {
+                let Some(event) = event else { break };
+                seq += 1;
+                let id = buffer.as_ref().map(|(buffer_tx, _)| {
+                    let id = format!("{}-{}", first_ms, seq);
+                    let data = serde_json::to_string(&event).unwrap_or_default();
+                    let _ = buffer_tx.send(StreamEntry { id: id.clone(), name: event.name().to_string(), data });
+                    id
+                });
+                let _ = tx.send(StreamedEvent { id, event });
+            }
+            _ = tx.closed(), if streams.is_none() && !cancel_token.is_cancelled() => {
+                cancel_token.cancel();
+            }
+            _ = check.tick(), if streams.is_some() => {
+                if is_attached(streams.as_deref(), session_id, &tx).await {
+                    detached_since = None;
+                } else if detached_since.get_or_insert_with(Instant::now).elapsed() >= DETACHED_STOP {
+                    cancel_token.cancel();
+                }
+            }
+        }
+    }
+
+    if let Some((buffer_tx, buffering)) = buffer {
+        drop(buffer_tx);
+        let _ = buffering.await;
+    }
+}
+
+/// Whether the client that started a generation, or anyone following its
+/// session, is still reading. A failed lookup counts as attached.
+async fn is_attached(streams: Option<&GenerationStreamRepo>, session_id: Uuid, tx: &mpsc::UnboundedSender<StreamedEvent>) -> bool {
+    if !tx.is_closed() {
+        return true;
+    }
+    let Some(streams) = streams else { return false };
+    streams.is_followed(session_id).await.unwrap_or_else(|e| {
+        tracing::warn!("Failed to check the followers of session {}: {}", session_id, e);
+        true
+    })
+}
+
+/// Writes a generation's events to its Redis stream. Events that arrive
+/// while a write is in flight go out together in the next one.
+async fn buffer_events(
+    streams: Arc<GenerationStreamRepo>,
+    session_id: Uuid,
+    message_id: Uuid,
+    mut rx: mpsc::UnboundedReceiver<StreamEntry>,
+) {
+    if let Err(e) = streams.set_current(session_id, message_id).await {
+        tracing::warn!("Failed to record the generation of session {}: {}", session_id, e);
+    }
+    let mut batch = Vec::with_capacity(BUFFER_BATCH);
+    while rx.recv_many(&mut batch, BUFFER_BATCH).await > 0 {
+        if let Err(e) = streams.append(message_id, &batch).await {
+            tracing::warn!("Failed to buffer events of generation {}: {}", message_id, e);
+        }
+        batch.clear();
+    }
+    if let Err(e) = streams.end(message_id).await {
+        tracing::warn!("Failed to end the buffered stream of generation {}: {}", message_id, e);
+    }
+}
+
+/// What one model call produced.
+struct Reply {
+    status: MessageStatus,
+    finish_reason: FinishReason,
+    content: String,
+    reasoning: String,
+    usage: Option<TokenUsage>,
+    error: Option<String>,
+    tool_calls: Vec<ToolCall>,
+}
+
+struct Run {
+    runner: GenerationRunner,
+    tx: mpsc::UnboundedSender<ConversationEvent>,
+    cancel_token: CancellationToken,
+    /// The registry entry this run belongs to, shared by the replies of a comparison.
+    group_id: Uuid,
+    session_id: Uuid,
+    provider: user_provider::Model,
+    model: provider_model::Model,
+    fallbacks: std::vec::IntoIter<(provider_model::Model, user_provider::Model)>,
+    params: GenerationParams,
+    context_strategy: ContextStrategy,
+    context_summary: Option<ContextSummary>,
+    tools: ToolRegistry,
+    definitions: Vec<ToolDefinition>,
+}
+
+impl Run {
+    /// Stops on an explicit cancel, or once nobody has followed the
+    /// generation for a while (see `relay_events`).
+    fn stopped(&self) -> bool {
+        self.cancel_token.is_cancelled()
+    }
+
+    /// The parameters for the current model.
+    fn model_params(&self) -> GenerationParams {
+        self.params.clone().or(self.model.generation_params.as_ref())
+    }
+
+    async fn execute(mut self, mut message_id: Uuid, mut messages: Vec<ChatMessagePayload>, title_source: Option<String>) {
+        let generation_id = message_id;
+        let mut steps = 0;
+
+        let (status, finish_reason) = loop {
+            steps += 1;
+            let reply = self.stream_reply(messages.clone()).await;
+            let (status, finish_reason) = (reply.status.clone(), reply.finish_reason);
+            let content = reply.content.clone();
+            let tool_calls = reply.tool_calls.clone();
+
+            if !self.save_reply(message_id, reply).await {
+                break (MessageStatus::Error, FinishReason::Error);
+            }
+            if status != MessageStatus::Complete || tool_calls.is_empty() {
+                break (status, finish_reason);
+            }
+
+            messages.push(ChatMessagePayload {
+                role: ChatRole::Assistant.as_str().to_string(),
+                content,
+                tool_calls: tool_calls.clone(),
+                message_id: Some(message_id),
+                ..Default::default()
+            });
+            match self.run_tools(message_id, tool_calls).await {
+                Ok(results) => messages.extend(results),
+                Err(e) => {
+                    tracing::error!("Failed to save tool results for {}: {}", message_id, e);
+                    self.internal_error("Failed to save the tool results");
+                    break (MessageStatus::Error, FinishReason::Error);
+                }
+            }
+            if self.stopped() {
+                break (MessageStatus::Cancelled, FinishReason::Cancelled);
+            }
+            if steps >= MAX_TOOL_STEPS {
+                break (MessageStatus::Complete, FinishReason::ToolCalls);
+            }
+
+            // The next step follows the last tool result
+            let parent_id = messages.last().and_then(|m| m.message_id).unwrap_or(message_id);
+            match self.runner.message_repo.create_assistant_placeholder(self.session_id, parent_id, self.model.id).await {
+                Ok(next) => {
+                    message_id = next.id;
+                    let _ = self.tx.send(ConversationEvent::Step { assistant_message_id: next.id });
+                }
+                Err(e) => {
+                    tracing::error!("Failed to continue generation {}: {}", generation_id, e);
+                    self.internal_error("Failed to save the reply");
+                    break (MessageStatus::Error, FinishReason::Error);
+                }
+            }
+        };
+        self.runner.generations.finish(self.session_id, self.group_id);
+        let _ = self.tx.send(ConversationEvent::Done { finish_reason });
+
+        if let Some(content) = title_source.filter(|_| status == MessageStatus::Complete) {
+            self.generate_title(content).await;
+        }
+    }
+
+    /// Calls the model, moving down the fallback list while it fails, and streams its output.
+    async fn stream_reply(&mut self, messages: Vec<ChatMessagePayload>) -> Reply {
+        let mut reply = Reply {
+            status: MessageStatus::Complete,
+            finish_reason: FinishReason::Stop,
+            content: String::new(),
+            reasoning: String::new(),
+            usage: None,
+            error: None,
+            tool_calls: Vec::new(),
+        };
+
+        // Provider failures that survive the client's retries move on to the next fallback
+        let started = loop {
+            let params = self.model_params();
+            let messages = self.fit_context(messages.clone(), &params).await;
+            let res = tokio::select! {
+                _ = self.cancel_token.cancelled() => break None,
+                res = self.runner.llm_client.chat(
+                    &self.provider,
+                    &self.model.model_id,
+                    messages,
+                    &self.definitions,
+                    &params,
+                ) => res,
+            };
+            let err = match res {
+                Err(AppError::Upstream(err)) => err,
+                res => break Some(res),
+            };
+            let Some((next_model, next_provider)) = self.fallbacks.next() else {
+                break Some(Err(AppError::Upstream(err)));
+            };
+            tracing::warn!("Model {} failed ({}), falling back to {}", self.model.model_id, err, next_model.model_id);
+            let _ = self.tx.send(ConversationEvent::Fallback {
+                from: EventModel::from(&self.model),
+                to: EventModel::from(&next_model),
+                reason: err.to_string(),
+            });
+            self.model = next_model;
+            self.provider = next_provider;
+        };
+
+        reply.status = match started {
+            None => MessageStatus::Cancelled,
+            Some(Err(e)) => {
+                reply.error = Some(e.to_string());
+                MessageStatus::Error
+            }
+            Some(Ok(mut llm_stream)) => {
+                let mut calls: BTreeMap<usize, ToolCall> = BTreeMap::new();
+                let status = loop {
+                    let item = tokio::select! {
+                        _ = self.cancel_token.cancelled() => break MessageStatus::Cancelled,
+                        item = llm_stream.next() => item,
+                    };
+                    match item {
+                        Some(Ok(ChatStreamEvent::Delta(text))) => {
+                            reply.content.push_str(&text);
+                            let _ = self.tx.send(ConversationEvent::Delta { text });
+                        }
+                        Some(Ok(ChatStreamEvent::Reasoning(text))) => {
+                            reply.reasoning.push_str(&text);
+                            let _ = self.tx.send(ConversationEvent::Reasoning { text });
+                        }
+                        Some(Ok(ChatStreamEvent::ToolCall(chunk))) => {
+                            let call = calls.entry(chunk.index).or_insert_with(|| ToolCall {
+                                id: String::new(),
+                                name: String::new(),
+                                arguments: String::new(),
+                            });
+                            if let Some(id) = chunk.id {
+                                call.id = id;
+                            }
+                            if let Some(name) = chunk.name {
+                                call.name = name;
+                            }
+                            call.arguments.push_str(&chunk.arguments);
+                        }
+                        Some(Ok(ChatStreamEvent::Usage(reported))) => reply.usage.get_or_insert_default().merge(reported),
+                        Some(Ok(ChatStreamEvent::Finish(reason))) => reply.finish_reason = reason,
+                        Some(Err(e)) => {
+                            reply.error = Some(e.to_string());
+                            break MessageStatus::Error;
+                        }
+                        None => break MessageStatus::Complete,
+                    }
+                };
+                // Dropping the provider stream closes the upstream connection
+                drop(llm_stream);
+
+                reply.tool_calls = calls
+                    .into_values()
+                    .filter(|c| !c.name.is_empty())
+                    .map(|mut c| {
+                        if c.id.is_empty() {
+                            c.id = format!("call_{}", Utc::now().to_uuid_v7().simple());
+                        }
+                        c
+                    })
+                    .collect();
+                status
+            }
+        };
+
+        match reply.status {
+            MessageStatus::Cancelled => reply.finish_reason = FinishReason::Cancelled,
+            MessageStatus::Error => reply.finish_reason = FinishReason::Error,
+            _ => {}
+        }
+        reply
+    }
+
+    /// Shortens the history to fit the current model's context window, which
+    /// is only known for models that set a context length.
+    async fn fit_context(&mut self, messages: Vec<ChatMessagePayload>, params: &GenerationParams) -> Vec<ChatMessagePayload> {
+        let Some(context_length) = self.model.context_length.filter(|l| *l > 0) else {
+            return messages;
+        };
+        let estimator = TokenEstimator::for_model(&self.provider, &self.model.model_id);
+        let budget = history_budget(context_length as u32, params.max_tokens, estimator.count_tools(&self.definitions));
+
+        let (window, summary) = match self.context_strategy.clone() {
+            ContextStrategy::DropOldest => (Window::split(messages, &estimator, budget, None), None),
+            ContextStrategy::KeepLast { messages: n } => (Window::split(messages, &estimator, budget, Some(n as usize)), None),
+            ContextStrategy::Summarize => {
+                let window = Window::split(messages, &estimator, budget.saturating_sub(SUMMARY_MAX_TOKENS as usize), None);
+                let summary = if window.dropped.is_empty() {
+                    None
+                } else {
+                    self.summarize(&window.dropped, &estimator, budget).await
+                };
+                (window, summary)
+            }
+        };
+        if !window.dropped.is_empty() {
+            tracing::debug!(
+                "Dropped {} messages of session {} to fit {} ({} tokens, summarized: {})",
+                window.dropped.len(), self.session_id, self.model.model_id, context_length, summary.is_some()
+            );
+        }
+
+        let summary = summary.map(|text| {
+            ChatMessagePayload::text(ChatRole::System.as_str(), format!("Summary of the earlier conversation:\n\n{}", text))
+        });
+        window.system.into_iter().chain(summary).chain(window.kept).collect()
+    }
+
+    /// Summarizes `dropped` in chunks that fit the model, continuing from the
+    /// session's cached summary when it covers the start of them. Returns `None`
+    /// when the model fails, leaving the turns simply dropped.
+    async fn summarize(&mut self, dropped: &[ChatMessagePayload], estimator: &TokenEstimator, budget: usize) -> Option<String> {
+        let mut summary = None;
+        let mut pending = dropped;
+        if let Some(cached) = &self.context_summary {
+            if let Some(position) = dropped.iter().position(|m| m.message_id == Some(cached.through_message_id)) {
+                summary = Some(cached.text.clone());
+                pending = &dropped[position + 1..];
+            }
+        }
+        let Some(through_message_id) = pending.iter().rev().find_map(|m| m.message_id) else {
+            return summary;
+        };
+
+        // Room for the instructions, the summary so far and the new summary
+        let chunk_budget = budget.saturating_sub(2 * SUMMARY_MAX_TOKENS as usize + estimator.count(SUMMARY_PROMPT));
+        let mut rest = pending;
+        while !rest.is_empty() {
+            let mut tokens = 0;
+            let mut len = 0;
+            while len < rest.len() && (len == 0 || tokens + estimator.count_message(&rest[len]) <= chunk_budget) {
+                tokens += estimator.count_message(&rest[len]);
+                len += 1;
+            }
+            summary = Some(self.summarize_chunk(summary.as_deref(), &rest[..len]).await?);
+            rest = &rest[len..];
+        }
+
+        let text = summary?;
+        let cached = ContextSummary { text: text.clone(), through_message_id };
+        if let Err(e) = self.runner.session_repo.update_context_summary(self.session_id, Some(cached.clone())).await {
+            tracing::warn!("Failed to cache the summary of session {}: {}", self.session_id, e);
+        }
+        self.context_summary = Some(cached);
+        Some(text)
+    }
+
+    async fn summarize_chunk(&self, previous: Option<&str>, messages: &[ChatMessagePayload]) -> Option<String> {
+        let mut prompt = String::new();
+        if let Some(previous) = previous {
+            prompt.push_str(&format!("Summary so far:\n{}\n\n", previous));
+        }
+        prompt.push_str(&format!("Conversation:\n{}", transcript(messages)));
+        let request = vec![
+            ChatMessagePayload::text(ChatRole::System.as_str(), SUMMARY_PROMPT.to_string()),
+            ChatMessagePayload::text(ChatRole::User.as_str(), prompt),
+        ];
+        let params = GenerationParams { max_tokens: Some(SUMMARY_MAX_TOKENS), ..Default::default() };
+
+        let started = tokio::select! {
+            _ = self.cancel_token.cancelled() => return None,
+            res = self.runner.llm_client.chat(&self.provider, &self.model.model_id, request, &[], &params) => res,
+        };
+        let mut stream = match started {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::warn!("Failed to summarize session {}: {}", self.session_id, e);
+                return None;
+            }
+        };
+        let mut text = String::new();
+        let mut usage: Option<TokenUsage> = None;
+        let failed = loop {
+            let item = tokio::select! {
+                _ = self.cancel_token.cancelled() => break true,
+                item = stream.next() => item,
+            };
+            match item {
+                Some(Ok(ChatStreamEvent::Delta(chunk))) => text.push_str(&chunk),
+                Some(Ok(ChatStreamEvent::Usage(reported))) => usage.get_or_insert_default().merge(reported),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    tracing::warn!("Failed to summarize session {}: {}", self.session_id, e);
+                    break true;
+                }
+                None => break false,
+            }
+        };
+        // The call is billed whether or not its summary gets used
+        self.record_spend(SpendSource::Summary, usage).await;
+        if failed {
+            return None;
+        }
+        let text = text.trim().to_string();
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// Stores a finished model call and reports its usage and errors. Returns
+    /// whether the message was saved.
+    async fn save_reply(&self, message_id: Uuid, reply: Reply) -> bool {
+        // Providers that don't report usage leave tokens and cost unknown rather than zero
+        let usage = self.record_spend(SpendSource::Reply, reply.usage).await;
+        if let Some(usage) = &usage {
+            let _ = self.tx.send(ConversationEvent::Usage {
+                prompt_tokens: usage.prompt_tokens as u32,
+                completion_tokens: usage.completion_tokens as u32,
+                reasoning_tokens: usage.reasoning_tokens.map(|t| t as u32),
+                cost: usage.cost,
+            });
+        }
+        if let Some(message) = &reply.error {
+            let _ = self.tx.send(ConversationEvent::Error {
+                code: StreamErrorCode::ProviderError,
+                message: message.clone(),
+            });
+        }
+
+        let saved = self
+            .runner
+            .message_repo
+            .finish_assistant(
+                message_id,
+                AssistantReply {
+                    provider_model_id: self.model.id,
+                    content: reply.content,
+                    reasoning: (!reply.reasoning.is_empty()).then_some(reply.reasoning),
+                    status: reply.status,
+                    error: reply.error,
+                    usage,
+                    tool_calls: reply.tool_calls,
+                    generation_params: self.model_params(),
+                },
+            )
+            .await;
+        if let Err(e) = saved {
+            tracing::error!("Failed to save assistant message {}: {}", message_id, e);
+            self.internal_error("Failed to save the reply");
+            return false;
+        }
+        true
+    }
+
+    /// Runs the requested tools in order and stores their results. Calls left
+    /// once the generation is stopped are answered with a cancellation notice,
+    /// so every stored tool call has a result when the history is replayed.
+    async fn run_tools(&self, message_id: Uuid, calls: Vec<ToolCall>) -> crate::error::Result<Vec<ChatMessagePayload>> {
+        let mut results = Vec::with_capacity(calls.len());
+        let mut parent_id = message_id;
+        for call in calls {
+            let _ = self.tx.send(ConversationEvent::ToolCall {
+                message_id,
+                id: call.id.clone(),
+                name: call.name.clone(),
+                arguments: call.arguments.clone(),
+            });
+
+            let cancelled = || ToolOutput::error("The tool call was cancelled".to_string());
+            let output = if self.stopped() {
+                cancelled()
+            } else {
+                tokio::select! {
+                    _ = self.cancel_token.cancelled() => cancelled(),
+                    output = self.tools.call(&call) => output,
+                }
+            };
+
+            let error = output.is_error.then(|| output.content.clone());
+            let stored = self
+                .runner
+                .message_repo
+                .create_tool_message(self.session_id, parent_id, call.id.clone(), output.content.clone(), error)
+                .await?;
+            parent_id = stored.id;
+            let _ = self.tx.send(ConversationEvent::ToolResult {
+                message_id: stored.id,
+                tool_call_id: call.id.clone(),
+                content: output.content.clone(),
+                is_error: output.is_error,
+            });
+
+            results.push(ChatMessagePayload {
+                role: ChatRole::Tool.as_str().to_string(),
+                content: output.content,
+                tool_call_id: Some(call.id),
+                message_id: Some(stored.id),
+                ..Default::default()
+            });
+        }
+        Ok(results)
+    }
+
+    /// Prices a model call's usage and adds it to the spending ledger, which
+    /// budgets are checked against. A failed write is logged, not fatal.
+    async fn record_spend(&self, source: SpendSource, usage: Option<TokenUsage>) -> Option<MessageUsage> {
+        let usage = MessageUsage::priced(&self.model, usage?);
+        if let Err(e) = self.runner.spending_repo.record(self.provider.user_id, &self.model, source, &usage).await {
+            tracing::error!("Failed to record spending of session {}: {}", self.session_id, e);
+        }
+        Some(usage)
+    }
+
+    fn internal_error(&self, message: &str) {
+        let _ = self.tx.send(ConversationEvent::Error {
+            code: StreamErrorCode::InternalError,
+            message: message.to_string(),
+        });
+    }
+
+    async fn generate_title(&self, content: String) {
+        let title_messages = vec![
+            ChatMessagePayload::text("system", "You are a conversation title assistant. Based on the user's message below, generate a short, clear title (max 20 characters). Do not include quotes or periods.".to_string()),
+            ChatMessagePayload::text("user", content),
+        ];
+        // We use stream chat but just collect it since we don't have a non-streaming client anymore
+        let Ok(mut stream) = self
+            .runner
+            .llm_client
+            .chat(&self.provider, &self.model.model_id, title_messages, &[], &GenerationParams::default())
+            .await
+        else {
+            return;
+        };
+
+        let mut title_text = String::new();
+        let mut usage: Option<TokenUsage> = None;
+        while let Some(res) = stream.next().await {
+            match res {
+                Ok(ChatStreamEvent::Delta(chunk)) => title_text.push_str(&chunk),
+                Ok(ChatStreamEvent::Usage(reported)) => usage.get_or_insert_default().merge(reported),
+                _ => {}
+            }
+        }
+        self.record_spend(SpendSource::Title, usage).await;
+        title_text = title_text.trim().to_string();
+        if !title_text.is_empty() && self.runner.session_repo.update_title(self.session_id, title_text.clone()).await.is_ok() {
+            let _ = self.tx.send(ConversationEvent::Title { title: title_text });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clients::llm_client::ToolCallChunk,
+        models::{conversation_message, conversation_session, spending_record, user_provider::ProviderType},
+        repositories::conversation_message_repo::ConversationMessageRepo,
+    };
+    use futures::stream::{self, BoxStream};
+    use rust_decimal::Decimal;
+    use sea_orm::{
+        ColumnTrait, ConnectOptions, ConnectionTrait, Database, DatabaseConnection, EntityTrait, QueryFilter, Schema,
+    };
+    use std::sync::Mutex;
+
+    /// Plays back one scripted reply per call, repeating the last one, and
+    /// records the messages each call was sent.
+    struct ScriptedClient {
+        replies: Vec<Vec<ChatStreamEvent>>,
+        requests: Mutex<Vec<Vec<ChatMessagePayload>>>,
+        /// Keeps each stream open after its reply, like a provider still generating.
+        stall: bool,
+    }
+
+    impl ScriptedClient {
+        fn new(replies: Vec<Vec<ChatStreamEvent>>) -> Arc<Self> {
+            Arc::new(Self { replies, requests: Mutex::new(Vec::new()), stall: false })
+        }
+
+        fn stalling(replies: Vec<Vec<ChatStreamEvent>>) -> Arc<Self> {
+            Arc::new(Self { replies, requests: Mutex::new(Vec::new()), stall: true })
+        }
+
+        fn requests(&self) -> Vec<Vec<ChatMessagePayload>> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LlmClient for ScriptedClient {
+        async fn chat(
+            &self,
+            _provider: &user_provider::Model,
+            _model_id: &str,
+            messages: Vec<ChatMessagePayload>,
+            _tools: &[ToolDefinition],
+            _params: &GenerationParams,
+        ) -> crate::error::Result<BoxStream<'static, crate::error::Result<ChatStreamEvent>>> {
+            let mut requests = self.requests.lock().unwrap();
+            let reply = self.replies[requests.len().min(self.replies.len() - 1)].clone();
+            requests.push(messages);
+            let events = stream::iter(reply.into_iter().map(Ok));
+            if self.stall {
+                Ok(events.chain(stream::pending()).boxed())
+            } else {
+                Ok(events.boxed())
+            }
+        }
+
+        fn supports_tools(&self, _provider: &user_provider::Model) -> bool {
+            true
+        }
+    }
+
+    fn tool_call(id: Option<&str>, name: Option<&str>, arguments: &str) -> ChatStreamEvent {
+        ChatStreamEvent::ToolCall(ToolCallChunk {
+            index: 0,
+            id: id.map(str::to_string),
+            name: name.map(str::to_string),
+            arguments: arguments.to_string(),
+        })
+    }
+
+    /// Asks for `1+2` with the arguments split over several chunks, as providers stream them.
+    fn calculator_call() -> Vec<ChatStreamEvent> {
+        vec![
+            tool_call(Some("call_1"), Some("calculator"), ""),
+            tool_call(None, None, "{\"expre"),
+            tool_call(None, None, "ssion\": \"1"),
+            tool_call(None, None, "+2\"}"),
+            ChatStreamEvent::Finish(FinishReason::ToolCalls),
+        ]
+    }
+
+    fn answer(text: &str) -> Vec<ChatStreamEvent> {
+        vec![ChatStreamEvent::Delta(text.to_string()), ChatStreamEvent::Finish(FinishReason::Stop)]
+    }
+
+    async fn database() -> DatabaseConnection {
+        // One connection, as each in-memory SQLite connection is its own database
+        let mut options = ConnectOptions::new("sqlite::memory:");
+        options.max_connections(1).sqlx_logging(false);
+        let db = Database::connect(options).await.unwrap();
+        db.execute_unprepared("PRAGMA foreign_keys = OFF").await.unwrap();
+
+        let backend = db.get_database_backend();
+        let schema = Schema::new(backend);
+        for table in [
+            schema.create_table_from_entity(conversation_session::Entity),
+            schema.create_table_from_entity(conversation_message::Entity),
+            schema.create_table_from_entity(spending_record::Entity),
+        ] {
+            db.execute(backend.build(&table)).await.unwrap();
+        }
+        db
+    }
+
+    struct Outcome {
+        events: Vec<ConversationEvent>,
+        messages: Vec<conversation_message::Model>,
+        first_message_id: Uuid,
+    }
+
+    struct Started {
+        db: DatabaseConnection,
+        generations: Arc<GenerationRegistry>,
+        events: UnboundedReceiverStream<StreamedEvent>,
+        session_id: Uuid,
+        first_message_id: Uuid,
+    }
+
+    async fn start(client: Arc<ScriptedClient>) -> Started {
+        let db = database().await;
+        let message_repo = Arc::new(ConversationMessageRepo::new(db.clone()));
+        let now = Utc::now().fixed_offset();
+        let provider = user_provider::Model {
+            id: Uuid::now_v7(),
+            user_id: Uuid::now_v7(),
+            name: "Scripted".to_string(),
+            provider_type: ProviderType::OpenAI,
+            url: "http://localhost".to_string(),
+            key: None,
+            options: None,
+            budget: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let model = provider_model::Model {
+            id: Uuid::now_v7(),
+            provider_id: provider.id,
+            model_id: "scripted".to_string(),
+            name: "Scripted".to_string(),
+            input_price_per_million: Decimal::ZERO,
+            output_price_per_million: Decimal::ZERO,
+            generation_params: None,
+            context_length: None,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let session_id = Uuid::now_v7();
+        let first = message_repo.create_assistant_placeholder(session_id, Uuid::now_v7(), model.id).await.unwrap();
+        let generations = Arc::new(GenerationRegistry::default());
+        let runner = GenerationRunner {
+            message_repo,
+            session_repo: Arc::new(ConversationSessionRepo::new(db.clone())),
+            spending_repo: Arc::new(SpendingRepo::new(db.clone())),
+            llm_client: client,
+            generations: generations.clone(),
+            streams: None,
+        };
+        let generation = Generation {
+            session_id,
+            user_message_id: None,
+            message_id: first.id,
+            provider,
+            model,
+            messages: vec![ChatMessagePayload::text(ChatRole::User.as_str(), "What is 1+2?".to_string())],
+            fallbacks: Vec::new(),
+            params: GenerationParams::default(),
+            context_strategy: ContextStrategy::default(),
+            context_summary: None,
+            tools: ToolRegistry::builtin(),
+            budget_warnings: Vec::new(),
+            title_source: None,
+        };
+
+        let events = runner.spawn(generation);
+        Started { db, generations, events, session_id, first_message_id: first.id }
+    }
+
+    async fn generate(client: Arc<ScriptedClient>) -> Outcome {
+        let Started { db, events, session_id, first_message_id, .. } = start(client).await;
+        let events = events.map(|e| e.event).collect().await;
+        let mut stored = conversation_message::Entity::find()
+            .filter(conversation_message::Column::SessionId.eq(session_id))
+            .all(&db)
+            .await
+            .unwrap();
+        // IDs made in the same millisecond don't sort, so follow the branch instead
+        let mut messages = Vec::new();
+        let mut next = Some(first_message_id);
+        while let Some(id) = next {
+            let at = stored.iter().position(|m| m.id == id).unwrap();
+            messages.push(stored.swap_remove(at));
+            next = stored.iter().find(|m| m.parent_id == Some(id)).map(|m| m.id);
+        }
+        assert!(stored.is_empty(), "messages off the branch: {:?}", stored);
+        Outcome { events, messages, first_message_id }
+    }
+
+    fn finish_reason(events: &[ConversationEvent]) -> Option<FinishReason> {
+        events.iter().find_map(|e| match e {
+            ConversationEvent::Done { finish_reason } => Some(*finish_reason),
+            _ => None,
+        })
+    }
+
+    #[tokio::test]
+    async fn accumulates_tool_call_arguments_across_chunks() {
+        let client = ScriptedClient::new(vec![calculator_call(), answer("1+2 is 3.")]);
+        let outcome = generate(client.clone()).await;
+
+        let expected = ToolCall {
+            id: "call_1".to_string(),
+            name: "calculator".to_string(),
+            arguments: "{\"expression\": \"1+2\"}".to_string(),
+        };
+        let streamed: Vec<_> = outcome
+            .events
+            .iter()
+            .filter_map(|e| match e {
+                ConversationEvent::ToolCall { id, name, arguments, .. } => Some((id, name, arguments)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(streamed, vec![(&expected.id, &expected.name, &expected.arguments)]);
+
+        let first = outcome.messages.iter().find(|m| m.id == outcome.first_message_id).unwrap();
+        assert_eq!(first.tool_calls.as_ref().map(|c| c.0.clone()), Some(vec![expected.clone()]));
+
+        // The second call sees the whole call and its result
+        let requests = client.requests();
+        assert_eq!(requests.len(), 2);
+        let replayed = &requests[1];
+        let call_turn = replayed.iter().find(|m| m.role == "assistant").unwrap();
+        assert_eq!(call_turn.tool_calls, vec![expected]);
+        let result = replayed.last().unwrap();
+        assert_eq!((result.role.as_str(), result.content.as_str()), ("tool", "3"));
+        assert_eq!(result.tool_call_id.as_deref(), Some("call_1"));
+
+        assert_eq!(finish_reason(&outcome.events), Some(FinishReason::Stop));
+    }
+
+    #[tokio::test]
+    async fn stores_tool_results_as_tool_messages() {
+        let client = ScriptedClient::new(vec![calculator_call(), answer("1+2 is 3.")]);
+        let outcome = generate(client).await;
+
+        let roles: Vec<_> = outcome.messages.iter().map(|m| m.role.clone()).collect();
+        assert_eq!(roles, vec![ChatRole::Assistant, ChatRole::Tool, ChatRole::Assistant]);
+
+        let (call, result, reply) = (&outcome.messages[0], &outcome.messages[1], &outcome.messages[2]);
+        assert_eq!(result.parent_id, Some(call.id));
+        assert_eq!(result.tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(result.content, "3");
+        assert_eq!(result.error, None);
+        assert_eq!(reply.parent_id, Some(result.id));
+        assert_eq!(reply.content, "1+2 is 3.");
+        assert!(outcome.messages.iter().all(|m| m.status == MessageStatus::Complete));
+    }
+
+    #[tokio::test]
+    async fn stops_after_max_tool_steps() {
+        let client = ScriptedClient::new(vec![calculator_call()]);
+        let outcome = generate(client.clone()).await;
+
+        assert_eq!(client.requests().len(), MAX_TOOL_STEPS);
+        assert_eq!(finish_reason(&outcome.events), Some(FinishReason::ToolCalls));
+
+        // The last round of tool results is stored, with no reply after it
+        let count = |role: ChatRole| outcome.messages.iter().filter(|m| m.role == role).count();
+        assert_eq!(count(ChatRole::Assistant), MAX_TOOL_STEPS);
+        assert_eq!(count(ChatRole::Tool), MAX_TOOL_STEPS);
+        assert_eq!(outcome.messages.last().map(|m| m.role.clone()), Some(ChatRole::Tool));
+    }
+
+    #[tokio::test]
+    async fn cancels_at_once_when_the_client_goes_away_without_redis() {
+        let client = ScriptedClient::stalling(vec![answer("Still thinking")]);
+        let Started { db, generations, mut events, session_id, first_message_id } = start(client).await;
+
+        while !matches!(events.next().await.map(|e| e.event), Some(ConversationEvent::Delta { .. })) {}
+        assert!(generations.is_running(session_id));
+        drop(events);
+
+        // Well inside FOLLOWER_CHECK, so only the immediate cancel can get there in time
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while generations.is_running(session_id) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the generation kept running after its client went away");
+
+        let message = conversation_message::Entity::find_by_id(first_message_id).one(&db).await.unwrap().unwrap();
+        assert_eq!(message.status, MessageStatus::Cancelled);
+        assert_eq!(message.content, "Still thinking");
+    }
+}
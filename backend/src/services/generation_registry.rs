@@ -0,0 +1,66 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// The generations running for a session: one reply, or the replies of a comparison.
+struct Running {
+    id: Uuid,
+    token: CancellationToken,
+    remaining: usize,
+}
+
+/// Tracks the in-flight generation of each session so it can be cancelled
+/// from a request other than the one streaming it.
+#[derive(Default)]
+pub struct GenerationRegistry {
+    generations: Mutex<HashMap<Uuid, Running>>,
+}
+
+impl GenerationRegistry {
+    /// Registers a generation for the session, cancelling any previous one.
+    pub fn register(&self, session_id: Uuid, generation_id: Uuid) -> CancellationToken {
+        self.register_group(session_id, generation_id, 1)
+    }
+
+    /// Registers `size` generations sharing one token, cancelled together.
+    /// The entry stays until each of them has finished.
+    pub fn register_group(&self, session_id: Uuid, generation_id: Uuid, size: usize) -> CancellationToken {
+        let token = CancellationToken::new();
+        let mut generations = self.generations.lock().unwrap_or_else(|e| e.into_inner());
+        let running = Running { id: generation_id, token: token.clone(), remaining: size };
+        if let Some(previous) = generations.insert(session_id, running) {
+            previous.token.cancel();
+        }
+        token
+    }
+
+    /// Returns whether a generation was running.
+    pub fn cancel(&self, session_id: Uuid) -> bool {
+        let generations = self.generations.lock().unwrap_or_else(|e| e.into_inner());
+        match generations.get(&session_id) {
+            Some(running) => {
+                running.token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn is_running(&self, session_id: Uuid) -> bool {
+        let generations = self.generations.lock().unwrap_or_else(|e| e.into_inner());
+        generations.contains_key(&session_id)
+    }
+
+    /// Removes the session's entry once the last generation of its group is
+    /// done, unless a newer generation has replaced it.
+    pub fn finish(&self, session_id: Uuid, generation_id: Uuid) {
+        let mut generations = self.generations.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(running) = generations.get_mut(&session_id).filter(|r| r.id == generation_id) {
+            running.remaining = running.remaining.saturating_sub(1);
+            if running.remaining == 0 {
+                generations.remove(&session_id);
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use futures::future::join_all;
+use sea_orm::{prelude::DateTimeWithTimeZone, ActiveValue::Set, IntoActiveModel};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{
+    clients::mcp_client::{McpSession, McpToolInfo},
+    config::McpConfig,
+    error::{AppError, Result},
+    http::dto::mcp_schema::{CreateMcpServerRequest, UpdateMcpServerRequest},
+    models::mcp_server::{self, McpTransport, StringList, StringMap},
+    repositories::mcp_server_repo::McpServerRepo,
+    tools::{mcp::McpTool, registry::Tool},
+};
+
+/// A live session, reused until the server's settings change or it goes away.
+struct CachedSession {
+    updated_at: DateTimeWithTimeZone,
+    session: Arc<McpSession>,
+    tools: Vec<McpToolInfo>,
+}
+
+pub struct McpServerService {
+    pub repo: Arc<McpServerRepo>,
+    config: McpConfig,
+    http: reqwest::Client,
+    sessions: Mutex<HashMap<Uuid, CachedSession>>,
+}
+
+impl McpServerService {
+    pub fn new(repo: Arc<McpServerRepo>, config: McpConfig) -> Self {
+        Self { repo, config, http: reqwest::Client::new(), sessions: Mutex::default() }
+    }
+
+    pub async fn list(&self, user_id: Uuid) -> Result<Vec<mcp_server::Model>> {
+        self.repo.list_by_user(user_id).await
+    }
+
+    pub async fn get(&self, user_id: Uuid, id: Uuid) -> Result<mcp_server::Model> {
+        match self.repo.get_by_id_for_user(user_id, id).await? {
+            Some(model) => Ok(model),
+            None => Err(AppError::NotFound("MCP server not found".to_string())),
+        }
+    }
+
+    pub async fn create(&self, user_id: Uuid, request: CreateMcpServerRequest) -> Result<mcp_server::Model> {
+        self.check_transport(&request.transport, request.command.as_deref(), request.url.as_deref())?;
+        if self.repo.get_by_name_for_user(user_id, &request.name).await?.is_some() {
+            return Err(AppError::Conflict("MCP server name already exists".to_string()));
+        }
+
+        let active = mcp_server::ActiveModel {
+            name: Set(request.name),
+            transport: Set(request.transport),
+            command: Set(request.command),
+            args: Set(request.args.map(StringList)),
+            env: Set(request.env.map(StringMap)),
+            url: Set(request.url),
+            headers: Set(request.headers.map(StringMap)),
+            enabled: Set(request.enabled.unwrap_or(true)),
+            allowed_tools: Set(request.allowed_tools.map(StringList)),
+            ..Default::default()
+        };
+        self.repo.create(user_id, active).await
+    }
+
+    pub async fn update(&self, user_id: Uuid, id: Uuid, request: UpdateMcpServerRequest) -> Result<mcp_server::Model> {
+        let current = self.get(user_id, id).await?;
+
+        if let Some(ref new_name) = request.name {
+            if &current.name != new_name && self.repo.get_by_name_for_user(user_id, new_name).await?.is_some() {
+                return Err(AppError::Conflict("MCP server name already exists".to_string()));
+            }
+        }
+
+        let transport = request.transport.unwrap_or_else(|| current.transport.clone());
+        let command = request.command.unwrap_or_else(|| current.command.clone());
+        let url = request.url.unwrap_or_else(|| current.url.clone());
+        self.check_transport(&transport, command.as_deref(), url.as_deref())?;
+
+        let mut active = current.into_active_model();
+        if let Some(name) = request.name {
+            active.name = Set(name);
+        }
+        active.transport = Set(transport);
+        active.command = Set(command);
+        active.url = Set(url);
+        if let Some(args) = request.args {
+            active.args = Set(args.map(StringList));
+        }
+        if let Some(env) = request.env {
+            active.env = Set(env.map(StringMap));
+        }
+        if let Some(headers) = request.headers {
+            active.headers = Set(headers.map(StringMap));
+        }
+        if let Some(enabled) = request.enabled {
+            active.enabled = Set(enabled);
+        }
+        if let Some(allowed_tools) = request.allowed_tools {
+            active.allowed_tools = Set(allowed_tools.map(StringList));
+        }
+
+        let updated = self.repo.update(active).await?;
+        self.sessions.lock().await.remove(&id);
+        Ok(updated)
+    }
+
+    pub async fn delete(&self, user_id: Uuid, id: Uuid) -> Result<()> {
+        let res = self.repo.delete_by_id_for_user(user_id, id).await?;
+        self.sessions.lock().await.remove(&id);
+        if res.rows_affected == 0 {
+            Err(AppError::NotFound("MCP server not found".to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Connects to the server and lists its tools, flagging those that pass the allowlist.
+    pub async fn list_tools(&self, user_id: Uuid, id: Uuid) -> Result<Vec<(McpToolInfo, bool)>> {
+        let server = self.get(user_id, id).await?;
+        self.check_transport(&server.transport, server.command.as_deref(), server.url.as_deref())?;
+        let (_, tools) = self.connect(&server).await?;
+        Ok(tools.into_iter().map(|tool| {
+            let allowed = server.allows(&tool.name);
+            (tool, allowed)
+        }).collect())
+    }
+
+    /// Tools from the user's enabled servers. A server that can't be reached
+    /// is skipped so it doesn't block the conversation.
+    pub async fn tools_for_user(&self, user_id: Uuid) -> Result<Vec<Arc<dyn Tool>>> {
+        let servers: Vec<_> = self
+            .repo
+            .list_enabled_by_user(user_id)
+            .await?
+            .into_iter()
+            .filter(|s| s.transport != McpTransport::Stdio || self.config.allow_stdio)
+            .collect();
+
+        let connected = join_all(servers.iter().map(|server| self.connect(server))).await;
+
+        let mut tools: Vec<Arc<dyn Tool>> = Vec::new();
+        for (server, result) in servers.iter().zip(connected) {
+            let (session, server_tools) = match result {
+                Ok(pair) => pair,
+                Err(e) => {
+                    tracing::warn!("Skipping MCP server {} ({}): {}", server.name, server.id, e);
+                    continue;
+                }
+            };
+            for tool in server_tools.into_iter().filter(|t| server.allows(&t.name)) {
+                tools.push(Arc::new(McpTool::new(session.clone(), server.name.clone(), tool)));
+            }
+        }
+        Ok(tools)
+    }
+
+    async fn connect(&self, server: &mcp_server::Model) -> Result<(Arc<McpSession>, Vec<McpToolInfo>)> {
+        {
+            let sessions = self.sessions.lock().await;
+            if let Some(cached) = sessions.get(&server.id) {
+                if cached.updated_at == server.updated_at && !cached.session.is_closed() {
+                    return Ok((cached.session.clone(), cached.tools.clone()));
+                }
+            }
+        }
+
+        // Connect without holding the lock; a slow server shouldn't stall everyone else's
+        let session = Arc::new(McpSession::connect(server, &self.http).await?);
+        let tools = session.list_tools().await?;
+        self.sessions.lock().await.insert(
+            server.id,
+            CachedSession { updated_at: server.updated_at, session: session.clone(), tools: tools.clone() },
+        );
+        Ok((session, tools))
+    }
+
+    fn check_transport(&self, transport: &McpTransport, command: Option<&str>, url: Option<&str>) -> Result<()> {
+        match transport {
+            McpTransport::Stdio => {
+                if !self.config.allow_stdio {
+                    return Err(AppError::BadRequest("Stdio MCP servers are disabled on this instance".to_string()));
+                }
+                if command.is_none_or(|c| c.trim().is_empty()) {
+                    return Err(AppError::BadRequest("A command is required for stdio MCP servers".to_string()));
+                }
+            }
+            McpTransport::Http => {
+                let valid = url
+                    .and_then(|u| reqwest::Url::parse(u).ok())
+                    .is_some_and(|u| matches!(u.scheme(), "http" | "https"));
+                if !valid {
+                    return Err(AppError::BadRequest("A valid http(s) URL is required for HTTP MCP servers".to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,14 @@
 pub mod auth_service;
 pub mod user_provider_service;
 pub mod provider_model_service;
-pub mod conversation_service;
\ No newline at end of file
+pub mod context_window;
+pub mod conversation_service;
+pub mod user_service;
+pub mod budget_service;
+pub mod generation_registry;
+pub mod generation;
+pub mod mcp_server_service;
+pub mod attachment_service;
+pub mod api_key_service;
+pub mod openai_proxy_service;
+pub mod personal_access_token_service;
\ No newline at end of file
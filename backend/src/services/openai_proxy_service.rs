@@ -0,0 +1,648 @@
+use chrono::Utc;
+use futures::{
+    stream::{self, BoxStream},
+    Stream, StreamExt,
+};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{sync::mpsc, time::Instant};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    clients::llm_client::{
+        check_params, without_tools, ChatMessagePayload, ChatStreamEvent, FinishReason, ImageData, LlmClient,
+        TokenUsage, ToolDefinition,
+    },
+    error::{AppError, Result},
+    http::dto::openai_schema::*,
+    models::{api_key, conversation_message::ToolCall, provider_model::{self, GenerationParams}, user_provider},
+    models::spending_record::SpendSource,
+    repositories::{api_key_repo::ApiKeyRepo, conversation_message_repo::MessageUsage, provider_repo::ProviderRepo, spending_repo::SpendingRepo},
+    services::{budget_service::BudgetService, context_window::TokenEstimator},
+    utils::ToUuidV7,
+};
+
+/// How long the provider's stream is still read for its usage after the client has gone.
+const USAGE_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One of the user's models as the OpenAI-compatible API names it.
+pub struct ProxyModel {
+    pub name: String,
+    pub model: provider_model::Model,
+    pub provider: user_provider::Model,
+}
+
+/// A completion under way. `events` are the provider's, ending early with an
+/// error if it fails midway.
+pub struct ProxyCompletion {
+    pub id: String,
+    pub created: i64,
+    /// The model as the request named it, echoed in every response.
+    pub model: String,
+    pub events: UnboundedReceiverStream<Result<ChatStreamEvent>>,
+}
+
+/// What a streamed completion sends, one SSE `data:` line each.
+pub enum ProxyChunk {
+    Chunk(ChatCompletionChunk),
+    /// The provider failed after the response had started.
+    Error(AppError),
+    Done,
+}
+
+/// Serves the user's providers through the OpenAI Chat Completions API, so
+/// tools built for OpenAI can use them with a Palette API key.
+#[derive(Clone)]
+pub struct OpenAiProxyService {
+    pub provider_repo: Arc<ProviderRepo>,
+    pub api_key_repo: Arc<ApiKeyRepo>,
+    pub budget_service: Arc<BudgetService>,
+    pub llm_client: Arc<dyn LlmClient>,
+}
+
+impl OpenAiProxyService {
+    pub fn new(
+        provider_repo: Arc<ProviderRepo>,
+        api_key_repo: Arc<ApiKeyRepo>,
+        budget_service: Arc<BudgetService>,
+        llm_client: Arc<dyn LlmClient>,
+    ) -> Self {
+        Self { provider_repo, api_key_repo, budget_service, llm_client }
+    }
+
+    /// The user's models, named by their model id when no other provider has
+    /// it and `<provider name>/<model id>` otherwise.
+    pub async fn list_models(&self, user_id: Uuid) -> Result<Vec<ProxyModel>> {
+        let mut providers = self.provider_repo.list_with_models_by_user_id(user_id).await?;
+        providers.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for model in providers.iter().flat_map(|(_, models)| models) {
+            *counts.entry(model.model_id.clone()).or_default() += 1;
+        }
+
+        let mut listed = Vec::new();
+        for (provider, mut models) in providers {
+            models.sort_by(|a, b| a.model_id.cmp(&b.model_id));
+            for model in models {
+                let name = if counts[&model.model_id] == 1 {
+                    model.model_id.clone()
+                } else {
+                    qualified_name(&provider, &model)
+                };
+                listed.push(ProxyModel { name, model, provider: provider.clone() });
+            }
+        }
+        Ok(listed)
+    }
+
+    /// Finds the model a request names, by either of its names.
+    async fn resolve(&self, user_id: Uuid, name: &str) -> Result<ProxyModel> {
+        let models = self.list_models(user_id).await?;
+
+        let same_id: Vec<&ProxyModel> = models.iter().filter(|m| m.model.model_id == name).collect();
+        if same_id.len() > 1 {
+            let names: Vec<String> = same_id.iter().map(|m| format!("`{}`", m.name)).collect();
+            return Err(AppError::BadRequest(format!(
+                "The model `{}` is served by several providers; use one of {}",
+                name,
+                names.join(", ")
+            )));
+        }
+
+        models
+            .into_iter()
+            .find(|m| m.model.model_id == name || qualified_name(&m.provider, &m.model) == name)
+            .ok_or_else(|| {
+                AppError::NotFound(format!("The model `{}` does not exist or you do not have access to it.", name))
+            })
+    }
+
+    /// Sends the request to the provider of the model it names. Usage is
+    /// recorded against `key` once the provider's stream ends.
+    pub async fn chat(&self, key: &api_key::Model, request: ChatCompletionRequest) -> Result<ProxyCompletion> {
+        if request.n.is_some_and(|n| n != 1) {
+            return Err(AppError::BadRequest("Only n=1 is supported".to_string()));
+        }
+        if request.messages.is_empty() {
+            return Err(AppError::BadRequest("messages must not be empty".to_string()));
+        }
+
+        let ProxyModel { model, provider, .. } = self.resolve(key.user_id, &request.model).await?;
+
+        let mut messages = request.messages.into_iter().map(message_payload).collect::<Result<Vec<_>>>()?;
+        let tools: Vec<ToolDefinition> = request
+            .tools
+            .into_iter()
+            .map(|tool| ToolDefinition {
+                name: tool.function.name,
+                description: tool.function.description.unwrap_or_default(),
+                parameters: tool
+                    .function
+                    .parameters
+                    .unwrap_or_else(|| serde_json::json!({ "type": "object", "properties": {} })),
+            })
+            .collect();
+        if !self.llm_client.supports_tools(&provider) {
+            if !tools.is_empty() {
+                return Err(AppError::BadRequest("This model's provider doesn't support tools".to_string()));
+            }
+            messages = without_tools(messages);
+        }
+
+        let params = GenerationParams {
+            temperature: request.temperature,
+            top_p: request.top_p,
+            max_tokens: request.max_completion_tokens.or(request.max_tokens),
+            stop: request.stop.map(StopSequences::into_vec),
+            presence_penalty: request.presence_penalty,
+            frequency_penalty: request.frequency_penalty,
+            seed: request.seed,
+            reasoning_effort: request.reasoning_effort,
+        }
+        .or(model.generation_params.as_ref());
+        params.validate()?;
+        check_params(&provider, &params)?;
+
+        self.budget_service.check(key.user_id, &provider).await?;
+
+        let estimator = TokenEstimator::for_model(&provider, &model.model_id);
+        let prompt_tokens = messages.iter().map(|m| estimator.count_message(m)).sum::<usize>() + estimator.count_tools(&tools);
+        let estimate = UsageEstimate { estimator, prompt_tokens, output: String::new() };
+
+        let upstream = self.llm_client.chat(&provider, &model.model_id, messages, &tools, &params).await?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        let ledger = Ledger {
+            api_key_repo: self.api_key_repo.clone(),
+            spending_repo: self.budget_service.spending_repo.clone(),
+            user_id: key.user_id,
+            api_key_id: key.id,
+        };
+        tokio::spawn(relay(upstream, tx, ledger, estimate, model, request.model.clone()));
+
+        let now = Utc::now();
+        Ok(ProxyCompletion {
+            id: format!("chatcmpl-{}", now.to_uuid_v7().simple()),
+            created: now.timestamp(),
+            model: request.model,
+            events: UnboundedReceiverStream::new(rx),
+        })
+    }
+}
+
+/// Waits for the whole reply.
+pub async fn complete(completion: ProxyCompletion) -> Result<ChatCompletion> {
+    let ProxyCompletion { id, created, model, mut events } = completion;
+
+    let mut content = String::new();
+    let mut calls: BTreeMap<usize, ToolCall> = BTreeMap::new();
+    let mut usage: Option<TokenUsage> = None;
+    let mut finish = FinishReason::Stop;
+    while let Some(event) = events.next().await {
+        match event? {
+            ChatStreamEvent::Delta(text) => content.push_str(&text),
+            // Chat Completions has no field for reasoning
+            ChatStreamEvent::Reasoning(_) => {}
+            ChatStreamEvent::ToolCall(chunk) => {
+                let call = calls.entry(chunk.index).or_insert_with(|| ToolCall {
+                    id: String::new(),
+                    name: String::new(),
+                    arguments: String::new(),
+                });
+                if let Some(id) = chunk.id {
+                    call.id = id;
+                }
+                if let Some(name) = chunk.name {
+                    call.name = name;
+                }
+                call.arguments.push_str(&chunk.arguments);
+            }
+            ChatStreamEvent::Usage(reported) => usage.get_or_insert_default().merge(reported),
+            ChatStreamEvent::Finish(reason) => finish = reason,
+        }
+    }
+
+    let tool_calls: Vec<ChatCompletionToolCall> = calls
+        .into_values()
+        .map(|call| ChatCompletionToolCall {
+            id: call.id,
+            kind: "function".to_string(),
+            function: FunctionCall { name: call.name, arguments: call.arguments },
+        })
+        .collect();
+    let finish_reason = finish_reason(finish, !tool_calls.is_empty());
+    let content = (!content.is_empty() || tool_calls.is_empty()).then_some(content);
+
+    Ok(ChatCompletion {
+        id,
+        object: "chat.completion",
+        created,
+        model,
+        choices: vec![ChatCompletionChoice {
+            index: 0,
+            message: ChatCompletionResponseMessage { role: "assistant", content, refusal: None, tool_calls },
+            logprobs: None,
+            finish_reason,
+        }],
+        usage: usage.map(completion_usage),
+        system_fingerprint: None,
+    })
+}
+
+/// Turns the reply into chunks as OpenAI streams them: the role first, then
+/// the deltas, a last chunk with the finish reason, the usage when asked for,
+/// and `[DONE]`.
+pub fn chunks(completion: ProxyCompletion, include_usage: bool) -> impl Stream<Item = ProxyChunk> {
+    struct State {
+        id: String,
+        created: i64,
+        model: String,
+        include_usage: bool,
+        events: UnboundedReceiverStream<Result<ChatStreamEvent>>,
+        started: bool,
+        finish: FinishReason,
+        tool_calls: bool,
+        usage: Option<TokenUsage>,
+        /// What's left to send once the events have ended.
+        tail: Option<VecDeque<ProxyChunk>>,
+    }
+
+    impl State {
+        fn chunk(&self, delta: ChunkDelta, finish_reason: Option<&'static str>) -> ProxyChunk {
+            ProxyChunk::Chunk(ChatCompletionChunk {
+                id: self.id.clone(),
+                object: "chat.completion.chunk",
+                created: self.created,
+                model: self.model.clone(),
+                system_fingerprint: None,
+                choices: vec![ChunkChoice { index: 0, delta, logprobs: None, finish_reason }],
+                usage: self.include_usage.then_some(None),
+            })
+        }
+
+        fn tail(&self) -> VecDeque<ProxyChunk> {
+            let finish = finish_reason(self.finish, self.tool_calls);
+            let mut tail = VecDeque::from([self.chunk(ChunkDelta::default(), Some(finish))]);
+            if self.include_usage {
+                tail.push_back(ProxyChunk::Chunk(ChatCompletionChunk {
+                    id: self.id.clone(),
+                    object: "chat.completion.chunk",
+                    created: self.created,
+                    model: self.model.clone(),
+                    system_fingerprint: None,
+                    choices: Vec::new(),
+                    usage: Some(Some(completion_usage(self.usage.unwrap_or_default()))),
+                }));
+            }
+            tail.push_back(ProxyChunk::Done);
+            tail
+        }
+    }
+
+    let ProxyCompletion { id, created, model, events } = completion;
+    let state = State {
+        id,
+        created,
+        model,
+        include_usage,
+        events,
+        started: false,
+        finish: FinishReason::Stop,
+        tool_calls: false,
+        usage: None,
+        tail: None,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        if let Some(tail) = state.tail.as_mut() {
+            let next = tail.pop_front()?;
+            return Some((next, state));
+        }
+        if !state.started {
+            state.started = true;
+            let delta = ChunkDelta { role: Some("assistant"), content: Some(String::new()), ..Default::default() };
+            let chunk = state.chunk(delta, None);
+            return Some((chunk, state));
+        }
+
+        loop {
+            match state.events.next().await {
+                Some(Ok(ChatStreamEvent::Delta(text))) => {
+                    let chunk = state.chunk(ChunkDelta { content: Some(text), ..Default::default() }, None);
+                    return Some((chunk, state));
+                }
+                Some(Ok(ChatStreamEvent::ToolCall(call))) => {
+                    state.tool_calls = true;
+                    let delta = ToolCallDelta {
+                        index: call.index,
+                        kind: call.id.is_some().then_some("function"),
+                        id: call.id,
+                        function: FunctionCallDelta { name: call.name, arguments: call.arguments },
+                    };
+                    let chunk = state.chunk(ChunkDelta { tool_calls: vec![delta], ..Default::default() }, None);
+                    return Some((chunk, state));
+                }
+                Some(Ok(ChatStreamEvent::Reasoning(_))) => {}
+                Some(Ok(ChatStreamEvent::Usage(reported))) => state.usage.get_or_insert_default().merge(reported),
+                Some(Ok(ChatStreamEvent::Finish(reason))) => state.finish = reason,
+                Some(Err(e)) => {
+                    // The response has started, so the error can only be reported in the stream
+                    state.tail = Some(VecDeque::new());
+                    return Some((ProxyChunk::Error(e), state));
+                }
+                None => {
+                    let mut tail = state.tail();
+                    let next = tail.pop_front()?;
+                    state.tail = Some(tail);
+                    return Some((next, state));
+                }
+            }
+        }
+    })
+}
+
+/// Where a request's usage is recorded: against its key, and in the
+/// spending ledger budgets are checked against.
+struct Ledger {
+    api_key_repo: Arc<ApiKeyRepo>,
+    spending_repo: Arc<SpendingRepo>,
+    user_id: Uuid,
+    api_key_id: Uuid,
+}
+
+/// Falls back to counting tokens ourselves when the provider's usage never
+/// arrives, so every request is billed.
+struct UsageEstimate {
+    estimator: TokenEstimator,
+    prompt_tokens: usize,
+    /// Everything the model produced, relayed or not.
+    output: String,
+}
+
+impl UsageEstimate {
+    fn add(&mut self, event: &ChatStreamEvent) {
+        match event {
+            ChatStreamEvent::Delta(text) | ChatStreamEvent::Reasoning(text) => self.output.push_str(text),
+            ChatStreamEvent::ToolCall(chunk) => {
+                self.output.push_str(chunk.name.as_deref().unwrap_or_default());
+                self.output.push_str(&chunk.arguments);
+            }
+            _ => {}
+        }
+    }
+
+    fn usage(&self) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self.prompt_tokens as u32,
+            completion_tokens: self.estimator.count(&self.output) as u32,
+            ..Default::default()
+        }
+    }
+}
+
+/// Forwards the provider's events and records the request's usage once they
+/// end. Providers report usage last, so when the client goes away the rest
+/// of the stream is still read for it, for up to `USAGE_DRAIN_TIMEOUT`; if it
+/// never comes, the usage is estimated.
+async fn relay(
+    mut upstream: BoxStream<'static, Result<ChatStreamEvent>>,
+    tx: mpsc::UnboundedSender<Result<ChatStreamEvent>>,
+    ledger: Ledger,
+    mut estimate: UsageEstimate,
+    model: provider_model::Model,
+    requested: String,
+) {
+    let mut usage: Option<TokenUsage> = None;
+    let mut draining_until: Option<Instant> = None;
+    loop {
+        let event = match draining_until {
+            None => upstream.next().await,
+            Some(deadline) => match tokio::time::timeout_at(deadline, upstream.next()).await {
+                Ok(event) => event,
+                Err(_) => break,
+            },
+        };
+        let Some(event) = event else { break };
+        match &event {
+            Ok(ChatStreamEvent::Usage(reported)) => usage.get_or_insert_default().merge(*reported),
+            Ok(event) => estimate.add(event),
+            Err(_) => {}
+        }
+        let failed = event.is_err();
+        if draining_until.is_none() && tx.send(event).is_err() {
+            draining_until = Some(Instant::now() + USAGE_DRAIN_TIMEOUT);
+        }
+        if failed {
+            break;
+        }
+    }
+    drop(upstream);
+
+    let usage = MessageUsage::priced(&model, usage.unwrap_or_else(|| estimate.usage()));
+    if let Err(e) = ledger.spending_repo.record(ledger.user_id, &model, SpendSource::Api, &usage).await {
+        tracing::error!("Failed to record spending of API key {}: {}", ledger.api_key_id, e);
+    }
+    if let Err(e) = ledger.api_key_repo.record_usage(ledger.api_key_id, model.id, requested, Some(usage)).await {
+        tracing::error!("Failed to record usage of API key {}: {}", ledger.api_key_id, e);
+    }
+}
+
+fn qualified_name(provider: &user_provider::Model, model: &provider_model::Model) -> String {
+    format!("{}/{}", provider.name, model.model_id)
+}
+
+fn message_payload(message: ChatCompletionMessage) -> Result<ChatMessagePayload> {
+    let role = match message.role.as_str() {
+        "system" | "developer" => "system",
+        "user" => "user",
+        "assistant" => "assistant",
+        "tool" => "tool",
+        other => return Err(AppError::BadRequest(format!("Unsupported message role `{}`", other))),
+    };
+    if role == "tool" && message.tool_call_id.is_none() {
+        return Err(AppError::BadRequest("Tool messages need a tool_call_id".to_string()));
+    }
+
+    let mut content = String::new();
+    let mut images = Vec::new();
+    match message.content {
+        None => {}
+        Some(MessageContent::Text(text)) => content = text,
+        Some(MessageContent::Parts(parts)) => {
+            for part in parts {
+                match part {
+                    ContentPart::Text { text } => {
+                        if !content.is_empty() {
+                            content.push('\n');
+                        }
+                        content.push_str(&text);
+                    }
+                    ContentPart::ImageUrl { image_url } => images.push(image_data(&image_url.url)?),
+                    ContentPart::Unsupported => {
+                        return Err(AppError::BadRequest("Only text and image content parts are supported".to_string()));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(ChatMessagePayload {
+        role: role.to_string(),
+        content,
+        images,
+        tool_calls: message
+            .tool_calls
+            .into_iter()
+            .map(|call| ToolCall { id: call.id, name: call.function.name, arguments: call.function.arguments })
+            .collect(),
+        tool_call_id: message.tool_call_id,
+        message_id: None,
+    })
+}
+
+/// Images have to be inlined; remote URLs aren't fetched.
+fn image_data(url: &str) -> Result<ImageData> {
+    url.strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"))
+        .map(|(mime_type, data)| ImageData { mime_type: mime_type.to_string(), data: data.to_string() })
+        .ok_or_else(|| AppError::BadRequest("Images must be sent as base64 data URLs".to_string()))
+}
+
+fn finish_reason(reason: FinishReason, has_tool_calls: bool) -> &'static str {
+    match reason {
+        FinishReason::Length => "length",
+        FinishReason::ContentFilter => "content_filter",
+        FinishReason::ToolCalls => "tool_calls",
+        _ if has_tool_calls => "tool_calls",
+        _ => "stop",
+    }
+}
+
+fn completion_usage(usage: TokenUsage) -> CompletionUsage {
+    CompletionUsage {
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+        total_tokens: usage.prompt_tokens + usage.completion_tokens,
+        completion_tokens_details: (usage.reasoning_tokens > 0)
+            .then_some(CompletionTokensDetails { reasoning_tokens: usage.reasoning_tokens }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{api_key_usage, spending_record, user_provider::ProviderType};
+    use rust_decimal::Decimal;
+    use sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection, EntityTrait, Schema};
+
+    async fn database() -> DatabaseConnection {
+        // One connection, as each in-memory SQLite connection is its own database
+        let mut options = ConnectOptions::new("sqlite::memory:");
+        options.max_connections(1).sqlx_logging(false);
+        let db = Database::connect(options).await.unwrap();
+        db.execute_unprepared("PRAGMA foreign_keys = OFF").await.unwrap();
+
+        let backend = db.get_database_backend();
+        let schema = Schema::new(backend);
+        for table in [
+            schema.create_table_from_entity(api_key_usage::Entity),
+            schema.create_table_from_entity(spending_record::Entity),
+        ] {
+            db.execute(backend.build(&table)).await.unwrap();
+        }
+        db
+    }
+
+    fn model() -> (user_provider::Model, provider_model::Model) {
+        let now = Utc::now().fixed_offset();
+        let provider = user_provider::Model {
+            id: Uuid::now_v7(),
+            user_id: Uuid::now_v7(),
+            name: "Scripted".to_string(),
+            provider_type: ProviderType::Anthropic,
+            url: "http://localhost".to_string(),
+            key: None,
+            options: None,
+            budget: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let model = provider_model::Model {
+            id: Uuid::now_v7(),
+            provider_id: provider.id,
+            model_id: "scripted".to_string(),
+            name: "Scripted".to_string(),
+            input_price_per_million: Decimal::from(1_000_000),
+            output_price_per_million: Decimal::from(2_000_000),
+            generation_params: None,
+            context_length: None,
+            created_at: now,
+            updated_at: now,
+        };
+        (provider, model)
+    }
+
+    /// Relays `events` to a client that has already gone away, and returns
+    /// what was recorded against the key and in the ledger.
+    async fn relay_to_departed_client(
+        events: Vec<ChatStreamEvent>,
+        prompt_tokens: usize,
+    ) -> (api_key_usage::Model, spending_record::Model) {
+        let db = database().await;
+        let (provider, model) = model();
+        let ledger = Ledger {
+            api_key_repo: Arc::new(ApiKeyRepo::new(db.clone())),
+            spending_repo: Arc::new(SpendingRepo::new(db.clone())),
+            user_id: provider.user_id,
+            api_key_id: Uuid::now_v7(),
+        };
+        let estimate = UsageEstimate {
+            estimator: TokenEstimator::for_model(&provider, &model.model_id),
+            prompt_tokens,
+            output: String::new(),
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        drop(rx);
+
+        let upstream = stream::iter(events.into_iter().map(Ok)).boxed();
+        relay(upstream, tx, ledger, estimate, model, "scripted".to_string()).await;
+
+        let usage = api_key_usage::Entity::find().all(&db).await.unwrap();
+        let spent = spending_record::Entity::find().all(&db).await.unwrap();
+        assert_eq!((usage.len(), spent.len()), (1, 1));
+        (usage.into_iter().next().unwrap(), spent.into_iter().next().unwrap())
+    }
+
+    #[tokio::test]
+    async fn reads_on_for_usage_after_the_client_goes_away() {
+        let events = vec![
+            ChatStreamEvent::Delta("Half an ".to_string()),
+            ChatStreamEvent::Delta("answer".to_string()),
+            ChatStreamEvent::Finish(FinishReason::Stop),
+            ChatStreamEvent::Usage(TokenUsage { prompt_tokens: 12, completion_tokens: 30, ..Default::default() }),
+        ];
+        let (usage, spent) = relay_to_departed_client(events, 1).await;
+
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens), (Some(12), Some(30)));
+        assert_eq!((spent.prompt_tokens, spent.completion_tokens), (Some(12), Some(30)));
+        assert_eq!(spent.source, SpendSource::Api);
+        assert_eq!(spent.cost, Decimal::from(12 + 2 * 30));
+        assert_eq!(usage.cost, Some(spent.cost));
+    }
+
+    #[tokio::test]
+    async fn estimates_usage_the_provider_never_reports() {
+        let events = vec![ChatStreamEvent::Delta("abcdefgh".to_string()), ChatStreamEvent::Delta("ijkl".to_string())];
+        let (usage, spent) = relay_to_departed_client(events, 40).await;
+
+        // Twelve ASCII characters at about four per token
+        assert_eq!((spent.prompt_tokens, spent.completion_tokens), (Some(40), Some(3)));
+        assert_eq!(spent.cost, Decimal::from(40 + 2 * 3));
+        assert_eq!(usage.completion_tokens, Some(3));
+    }
+}
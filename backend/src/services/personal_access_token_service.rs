@@ -0,0 +1,83 @@
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::personal_access_token::{self, TokenScope, TokenScopes},
+    repositories::personal_access_token_repo::PersonalAccessTokenRepo,
+    utils::{generate_secret, hash_secret},
+};
+
+/// Marks personal access tokens, so they're told apart from login tokens and API keys.
+pub const PERSONAL_TOKEN_PREFIX: &str = "pat-";
+/// Characters of a token kept in the clear to tell tokens apart.
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+#[derive(Clone)]
+pub struct PersonalAccessTokenService {
+    pub repo: Arc<PersonalAccessTokenRepo>,
+}
+
+impl PersonalAccessTokenService {
+    pub fn new(repo: Arc<PersonalAccessTokenRepo>) -> Self {
+        Self { repo }
+    }
+
+    pub async fn list(&self, user_id: Uuid) -> Result<Vec<personal_access_token::Model>> {
+        self.repo.list_by_user(user_id).await
+    }
+
+    /// Creates a token and returns it with its secret, which isn't stored.
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        name: String,
+        scopes: Vec<TokenScope>,
+        expires_in_days: Option<u32>,
+    ) -> Result<(personal_access_token::Model, String)> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::BadRequest("Token name is required".to_string()));
+        }
+        let mut granted: Vec<TokenScope> = Vec::with_capacity(scopes.len());
+        for scope in scopes {
+            if !granted.contains(&scope) {
+                granted.push(scope);
+            }
+        }
+        if granted.is_empty() {
+            return Err(AppError::BadRequest("At least one scope is required".to_string()));
+        }
+        let expires_at = expires_in_days.map(|days| Utc::now() + Duration::days(days.into()));
+
+        let secret = generate_secret(PERSONAL_TOKEN_PREFIX);
+        let prefix = secret[..DISPLAY_PREFIX_LEN].to_string();
+        let token = self
+            .repo
+            .create(user_id, name, prefix, hash_secret(&secret), TokenScopes(granted), expires_at)
+            .await?;
+        Ok((token, secret))
+    }
+
+    pub async fn revoke(&self, user_id: Uuid, id: Uuid) -> Result<()> {
+        if self.repo.delete(user_id, id).await? {
+            Ok(())
+        } else {
+            Err(AppError::NotFound("Token not found".to_string()))
+        }
+    }
+
+    /// The token a secret belongs to, unless it has expired or been revoked.
+    /// Records the use.
+    pub async fn authenticate(&self, secret: &str) -> Result<Option<personal_access_token::Model>> {
+        if !secret.starts_with(PERSONAL_TOKEN_PREFIX) {
+            return Ok(None);
+        }
+        let token = self.repo.get_active_by_hash(&hash_secret(secret)).await?;
+        if let Some(token) = &token {
+            self.repo.touch(token.id).await?;
+        }
+        Ok(token)
+    }
+}
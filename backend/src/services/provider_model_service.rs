@@ -1,9 +1,12 @@
 use std::sync::Arc;
+use rust_decimal::Decimal;
 use uuid::Uuid;
+use validator::Validate;
 
 use crate::{
+    clients::llm_client::check_params,
     error::{AppError, Result},
-    models::{provider_model, user_provider},
+    models::{provider_model::{self, GenerationParams}, user_provider},
     repositories::{provider_model_repo::ProviderModelRepo, provider_repo::ProviderRepo},
     clients::model_info_client::ModelInfoClient,
 };
@@ -58,14 +61,26 @@ impl ProviderModelService {
         id: Uuid,
         model_id: Option<String>,
         name: Option<String>,
+        generation_params: Option<Option<GenerationParams>>,
+        context_length: Option<Option<u32>>,
     ) -> Result<provider_model::Model> {
         let current = self.get(user_id, id).await?;
 
+        if context_length.is_some_and(|l| l.is_some_and(|l| l == 0 || l > i32::MAX as u32)) {
+            return Err(AppError::BadRequest("Context length must be a positive number of tokens".to_string()));
+        }
+        let context_length = context_length.map(|l| l.map(|l| l as i32));
+
+        let generation_params = generation_params.map(|params| params.filter(|p| !p.is_empty()));
+        if let Some(Some(ref params)) = generation_params {
+            params.validate()?;
+            let provider = self.ensure_provider_owned_by(user_id, current.provider_id).await?;
+            check_params(&provider, params)?;
+        }
+
         if let Some(ref new_model_id) = model_id {
-            if new_model_id != &current.model_id {
-                if self.model_repo.get_by_model_id_in_provider(current.provider_id, new_model_id).await?.is_some() {
-                    return Err(AppError::Conflict("Model ID already exists in provider".to_string()));
-                }
+            if new_model_id != &current.model_id && self.model_repo.get_by_model_id_in_provider(current.provider_id, new_model_id).await?.is_some() {
+                return Err(AppError::Conflict("Model ID already exists in provider".to_string()));
             }
         }
 
@@ -81,7 +96,9 @@ impl ProviderModelService {
             output_price_per_million = Some(out_price);
         }
 
-        self.model_repo.update_model(id, model_id, name, input_price_per_million, output_price_per_million).await
+        self.model_repo
+            .update_model(id, model_id, name, input_price_per_million, output_price_per_million, generation_params, context_length)
+            .await
     }
 
     pub async fn delete(&self, user_id: Uuid, id: Uuid) -> Result<()> {
@@ -90,6 +107,40 @@ impl ProviderModelService {
         if res.rows_affected == 0 { Err(AppError::NotFound("Model not found".to_string())) } else { Ok(()) }
     }
 
+    /// Lists models installed on the provider, flagging the ones already imported.
+    pub async fn discover(&self, user_id: Uuid, provider_id: Uuid) -> Result<Vec<(String, bool)>> {
+        let provider = self.ensure_provider_owned_by(user_id, provider_id).await?;
+        let remote = self.model_info_client.list_models(&provider).await?;
+        let existing = self.model_repo.list_by_provider(provider_id).await?;
+
+        Ok(remote
+            .into_iter()
+            .map(|model_id| {
+                let imported = existing.iter().any(|m| m.model_id == model_id);
+                (model_id, imported)
+            })
+            .collect())
+    }
+
+    /// Imports discovered models at zero price, skipping ones that already exist.
+    pub async fn import(&self, user_id: Uuid, provider_id: Uuid, model_ids: Vec<String>) -> Result<Vec<provider_model::Model>> {
+        let provider = self.ensure_provider_owned_by(user_id, provider_id).await?;
+        let remote = self.model_info_client.list_models(&provider).await?;
+
+        let mut created = Vec::new();
+        for model_id in model_ids {
+            if !remote.contains(&model_id) {
+                return Err(AppError::NotFound(format!("Model {} is not installed on provider", model_id)));
+            }
+            if self.model_repo.get_by_model_id_in_provider(provider_id, &model_id).await?.is_some() {
+                continue;
+            }
+            let model = self.model_repo.create(provider_id, model_id.clone(), model_id, Decimal::ZERO, Decimal::ZERO).await?;
+            created.push(model);
+        }
+        Ok(created)
+    }
+
     async fn ensure_provider_owned_by(&self, user_id: Uuid, provider_id: Uuid) -> Result<user_provider::Model> {
         let provider = self.provider_repo.get_by_id_for_user(user_id, provider_id).await?;
         match provider { Some(p) => Ok(p), None => Err(AppError::Forbidden("Provider not accessible".to_string())) }
@@ -3,7 +3,7 @@ use uuid::Uuid;
 
 use crate::{
     error::{AppError, Result},
-    models::{user_provider::{self, ProviderType}, provider_model},
+    models::{user::SpendingBudget, user_provider::{self, ProviderOptions, ProviderType}, provider_model},
     repositories::provider_repo::ProviderRepo,
     clients::model_info_client::ModelInfoClient,
 };
@@ -37,14 +37,16 @@ impl UserProviderService {
         provider_type: ProviderType,
         url: String,
         key: Option<String>,
+        options: Option<ProviderOptions>,
     ) -> Result<user_provider::Model> {
         if self.repo.get_by_name_for_user(user_id, &name).await?.is_some() {
             return Err(AppError::Conflict("Provider name already exists".to_string()));
         }
 
-        self.repo.create(user_id, name, provider_type, url, key).await
+        self.repo.create(user_id, name, provider_type, url, key, options).await
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn update(
         &self,
         user_id: Uuid,
@@ -53,6 +55,7 @@ impl UserProviderService {
         provider_type: Option<ProviderType>,
         url: Option<String>,
         key: Option<Option<String>>,
+        options: Option<Option<ProviderOptions>>,
     ) -> Result<user_provider::Model> {
         let current = match self.repo.get_by_id_for_user(user_id, id).await? {
             Some(m) => m,
@@ -65,7 +68,15 @@ impl UserProviderService {
             }
         }
 
-        self.repo.update_provider(id, name, provider_type, url, key).await
+        self.repo.update_provider(id, name, provider_type, url, key, options).await
+    }
+
+    pub async fn update_budget(&self, user_id: Uuid, id: Uuid, budget: Option<SpendingBudget>) -> Result<user_provider::Model> {
+        self.get(user_id, id).await?;
+        if budget.as_ref().is_some_and(|b| !b.is_valid()) {
+            return Err(AppError::BadRequest("Budget limits must be positive and the warning threshold between 1 and 100".to_string()));
+        }
+        self.repo.update_budget(id, budget).await
     }
 
     pub async fn delete(&self, user_id: Uuid, id: Uuid) -> Result<()> {
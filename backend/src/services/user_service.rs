@@ -0,0 +1,59 @@
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::user::{UserPreferences, MAX_INSTRUCTIONS_LEN},
+    repositories::user_repo::UserRepo,
+    utils::non_blank,
+};
+
+#[derive(Clone)]
+pub struct UserService {
+    pub repo: Arc<UserRepo>,
+}
+
+impl UserService {
+    pub fn new(repo: Arc<UserRepo>) -> Self {
+        Self { repo }
+    }
+
+    pub async fn get_preferences(&self, user_id: Uuid) -> Result<UserPreferences> {
+        let user = self
+            .repo
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+        Ok(user.preferences.unwrap_or_default())
+    }
+
+    pub async fn update_preferences(&self, user_id: Uuid, mut preferences: UserPreferences) -> Result<UserPreferences> {
+        if preferences.budget.as_ref().is_some_and(|b| !b.is_valid()) {
+            return Err(AppError::BadRequest("Budget limits must be positive and the warning threshold between 1 and 100".to_string()));
+        }
+        if preferences.context_strategy.as_ref().is_some_and(|s| !s.is_valid()) {
+            return Err(AppError::BadRequest("At least one message must be kept".to_string()));
+        }
+        preferences.custom_instructions = non_blank(preferences.custom_instructions);
+        if preferences.custom_instructions.as_ref().is_some_and(|i| i.chars().count() as u64 > MAX_INSTRUCTIONS_LEN) {
+            return Err(AppError::BadRequest(format!(
+                "Custom instructions can be at most {} characters",
+                MAX_INSTRUCTIONS_LEN
+            )));
+        }
+        let updated = self.repo.update_preferences(user_id, Some(preferences)).await?;
+        Ok(updated.preferences.unwrap_or_default())
+    }
+
+    pub async fn get_custom_instructions(&self, user_id: Uuid) -> Result<Option<String>> {
+        Ok(self.get_preferences(user_id).await?.custom_instructions)
+    }
+
+    /// Replaces only the custom instructions, keeping the other preferences.
+    pub async fn update_custom_instructions(&self, user_id: Uuid, instructions: Option<String>) -> Result<Option<String>> {
+        let mut preferences = self.get_preferences(user_id).await?;
+        preferences.custom_instructions = non_blank(instructions);
+        let updated = self.repo.update_preferences(user_id, Some(preferences)).await?;
+        Ok(updated.preferences.and_then(|p| p.custom_instructions))
+    }
+}
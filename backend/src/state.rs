@@ -1,21 +1,28 @@
 use std::sync::Arc;
+use chrono::Utc;
 use axum::extract::FromRef;
 use sea_orm::DatabaseConnection;
 use crate::{
     config::Config,
-    database::{get_postgres_connection, run_migrations},
-    repositories::{user_repo::UserRepo, provider_repo::ProviderRepo, provider_model_repo::ProviderModelRepo, conversation_session_repo::ConversationSessionRepo, conversation_message_repo::ConversationMessageRepo},
-    services::{auth_service::AuthService, user_provider_service::UserProviderService, provider_model_service::ProviderModelService, conversation_service::ConversationService},
-    clients::{model_info_client::{DefaultModelInfoClient, ModelInfoClient}, llm_client::{DefaultLlmClient, LlmClient}},
+    database::{get_postgres_connection, get_redis_connection, run_migrations},
+    repositories::{user_repo::UserRepo, provider_repo::ProviderRepo, provider_model_repo::ProviderModelRepo, conversation_session_repo::ConversationSessionRepo, conversation_message_repo::ConversationMessageRepo, mcp_server_repo::McpServerRepo, message_attachment_repo::MessageAttachmentRepo, generation_stream_repo::GenerationStreamRepo, api_key_repo::ApiKeyRepo, personal_access_token_repo::PersonalAccessTokenRepo, spending_repo::SpendingRepo},
+    services::{auth_service::AuthService, user_service::UserService, budget_service::BudgetService, user_provider_service::UserProviderService, provider_model_service::ProviderModelService, conversation_service::ConversationService, mcp_server_service::McpServerService, attachment_service::AttachmentService, api_key_service::ApiKeyService, openai_proxy_service::OpenAiProxyService, personal_access_token_service::PersonalAccessTokenService},
+    clients::{model_info_client::{DefaultModelInfoClient, ModelInfoClient}, llm_client::{DefaultLlmClient, LlmClient}, retry_client::RetryingLlmClient},
 };
 
 #[derive(Clone)]
 pub struct AppState {
     pub database: DatabaseConnection,
     pub auth_service: Arc<AuthService>,
+    pub user_service: Arc<UserService>,
     pub user_provider_service: Arc<UserProviderService>,
     pub provider_model_service: Arc<ProviderModelService>,
     pub conversation_service: Arc<ConversationService>,
+    pub mcp_server_service: Arc<McpServerService>,
+    pub attachment_service: Arc<AttachmentService>,
+    pub api_key_service: Arc<ApiKeyService>,
+    pub openai_proxy_service: Arc<OpenAiProxyService>,
+    pub personal_access_token_service: Arc<PersonalAccessTokenService>,
 }
 
 impl FromRef<AppState> for DatabaseConnection {
@@ -30,6 +37,12 @@ impl FromRef<AppState> for Arc<AuthService> {
     }
 }
 
+impl FromRef<AppState> for Arc<UserService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.user_service.clone()
+    }
+}
+
 impl FromRef<AppState> for Arc<UserProviderService> {
     fn from_ref(state: &AppState) -> Self {
         state.user_provider_service.clone()
@@ -48,12 +61,56 @@ impl FromRef<AppState> for Arc<ConversationService> {
     }
 }
 
+impl FromRef<AppState> for Arc<McpServerService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.mcp_server_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<AttachmentService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.attachment_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<ApiKeyService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.api_key_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<OpenAiProxyService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.openai_proxy_service.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<PersonalAccessTokenService> {
+    fn from_ref(state: &AppState) -> Self {
+        state.personal_access_token_service.clone()
+    }
+}
+
 pub async fn create_state(config: &Config) -> Result<AppState, Box<dyn std::error::Error>> {
     let database = get_postgres_connection(&config.database_url).await?;
     run_migrations(&database).await?;
+    let stream_repo = match &config.redis_url {
+        Some(url) => {
+            let redis_client = redis::Client::open(url.as_str())?;
+            let redis = get_redis_connection(&redis_client).await?;
+            Some(Arc::new(GenerationStreamRepo::new(redis_client, redis)))
+        }
+        None => {
+            tracing::warn!("REDIS_URL is not set; streamed replies can't be resumed or followed");
+            None
+        }
+    };
     
     let user_repo = Arc::new(UserRepo::new(database.clone()));
-    let auth_service = Arc::new(AuthService::new(user_repo, config.jwt.clone()));
+    let auth_service = Arc::new(AuthService::new(user_repo.clone(), config.jwt.clone()));
+    let user_service = Arc::new(UserService::new(user_repo.clone()));
+    let personal_access_token_repo = Arc::new(PersonalAccessTokenRepo::new(database.clone()));
+    let personal_access_token_service = Arc::new(PersonalAccessTokenService::new(personal_access_token_repo));
 
     let provider_model_repo = Arc::new(ProviderModelRepo::new(database.clone()));
     let model_info_client: Arc<dyn ModelInfoClient> = Arc::new(DefaultModelInfoClient::default());
@@ -65,14 +122,31 @@ pub async fn create_state(config: &Config) -> Result<AppState, Box<dyn std::erro
 
     let session_repo = Arc::new(ConversationSessionRepo::new(database.clone()));
     let message_repo = Arc::new(ConversationMessageRepo::new(database.clone()));
-    let llm_client: Arc<dyn LlmClient> = Arc::new(DefaultLlmClient::default());
-    let conversation_service = Arc::new(ConversationService::new(session_repo, message_repo, provider_model_repo.clone(), provider_repo.clone(), llm_client));
+    message_repo.fail_interrupted().await?;
+    let attachment_repo = Arc::new(MessageAttachmentRepo::new(database.clone()));
+    attachment_repo.delete_unattached_before(Utc::now() - chrono::Duration::days(1)).await?;
+    let attachment_service = Arc::new(AttachmentService::new(attachment_repo.clone()));
+    let api_key_repo = Arc::new(ApiKeyRepo::new(database.clone()));
+    let api_key_service = Arc::new(ApiKeyService::new(api_key_repo.clone()));
+    let spending_repo = Arc::new(SpendingRepo::new(database.clone()));
+    let budget_service = Arc::new(BudgetService::new(user_repo.clone(), spending_repo));
+    let llm_client: Arc<dyn LlmClient> = Arc::new(RetryingLlmClient::new(Arc::new(DefaultLlmClient::default()), config.llm_retry.clone()));
+    let mcp_server_repo = Arc::new(McpServerRepo::new(database.clone()));
+    let mcp_server_service = Arc::new(McpServerService::new(mcp_server_repo, config.mcp.clone()));
+    let openai_proxy_service = Arc::new(OpenAiProxyService::new(provider_repo.clone(), api_key_repo, budget_service.clone(), llm_client.clone()));
+    let conversation_service = Arc::new(ConversationService::new(session_repo, message_repo, attachment_repo, user_repo, provider_model_repo.clone(), provider_repo.clone(), budget_service, llm_client, mcp_server_service.clone(), stream_repo));
 
     Ok(AppState {
         database,
         auth_service,
+        user_service,
         user_provider_service,
         provider_model_service,
         conversation_service,
+        mcp_server_service,
+        attachment_service,
+        api_key_service,
+        openai_proxy_service,
+        personal_access_token_service,
     })
 }
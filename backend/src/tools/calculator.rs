@@ -0,0 +1,296 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    clients::llm_client::ToolDefinition,
+    error::{AppError, Result},
+    tools::registry::Tool,
+};
+
+/// Longest expression accepted, in characters.
+const MAX_EXPRESSION_LEN: usize = 1000;
+/// Deepest nesting of parentheses, calls and unary signs accepted, which keeps
+/// the recursive parser well within the stack.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Deserialize)]
+struct Arguments {
+    expression: String,
+}
+
+/// Evaluates arithmetic expressions, which models are unreliable at doing in their heads.
+pub struct Calculator;
+
+#[async_trait]
+impl Tool for Calculator {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "calculator".to_string(),
+            description: "Evaluate an arithmetic expression. Supports + - * / % ^, parentheses, \
+                the constants pi and e, and the functions sqrt, abs, exp, ln, log10, log2, sin, cos, \
+                tan, asin, acos, atan, floor, ceil, round, min, max and pow."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "expression": {
+                        "type": "string",
+                        "description": "The expression to evaluate, e.g. \"(1.5 + 2) * sqrt(16)\"."
+                    }
+                },
+                "required": ["expression"]
+            }),
+        }
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> Result<String> {
+        let arguments: Arguments = serde_json::from_value(arguments)
+            .map_err(|e| AppError::BadRequest(format!("Invalid arguments: {}", e)))?;
+
+        let value = evaluate(&arguments.expression).map_err(AppError::BadRequest)?;
+        if !value.is_finite() {
+            return Err(AppError::BadRequest("The result is not a finite number".to_string()));
+        }
+        Ok(value.to_string())
+    }
+}
+
+fn evaluate(expression: &str) -> std::result::Result<f64, String> {
+    let chars: Vec<char> = expression.chars().collect();
+    if chars.len() > MAX_EXPRESSION_LEN {
+        return Err(format!("Expression is longer than {} characters", MAX_EXPRESSION_LEN));
+    }
+    let mut parser = Parser { chars, pos: 0, depth: 0 };
+    let value = parser.expr()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(value),
+        Some(c) => Err(format!("Unexpected '{}' at position {}", c, parser.pos + 1)),
+    }
+}
+
+/// Recursive descent over the usual precedence levels; `^` binds tighter than
+/// unary minus and is right-associative.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// Levels of `unary` currently on the stack; every nested subexpression passes through it.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consumes `c` if it is the next non-whitespace character.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self) -> std::result::Result<f64, String> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> std::result::Result<f64, String> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                value *= self.unary()?;
+            } else if self.eat('/') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err("Division by zero".to_string());
+                }
+                value /= divisor;
+            } else if self.eat('%') {
+                let divisor = self.unary()?;
+                if divisor == 0.0 {
+                    return Err("Division by zero".to_string());
+                }
+                value %= divisor;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> std::result::Result<f64, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err("Expression too deeply nested".to_string());
+        }
+        self.depth += 1;
+        let value = self.signed();
+        self.depth -= 1;
+        value
+    }
+
+    fn signed(&mut self) -> std::result::Result<f64, String> {
+        if self.eat('-') {
+            Ok(-self.unary()?)
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> std::result::Result<f64, String> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            Ok(base.powf(self.unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> std::result::Result<f64, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let value = self.expr()?;
+                if !self.eat(')') {
+                    return Err("Missing closing parenthesis".to_string());
+                }
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => self.identifier(),
+            Some(c) => Err(format!("Unexpected '{}' at position {}", c, self.pos + 1)),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+
+    fn number(&mut self) -> std::result::Result<f64, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        // Scientific notation, e.g. 1.5e-3
+        if matches!(self.peek(), Some('e' | 'E'))
+            && self.chars.get(self.pos + 1).is_some_and(|c| c.is_ascii_digit() || *c == '-' || *c == '+')
+        {
+            self.pos += 2;
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.pos += 1;
+            }
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse::<f64>().map_err(|_| format!("Invalid number '{}'", text))
+    }
+
+    fn identifier(&mut self) -> std::result::Result<f64, String> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        let name: String = self.chars[start..self.pos].iter().collect::<String>().to_ascii_lowercase();
+
+        match name.as_str() {
+            "pi" => return Ok(std::f64::consts::PI),
+            "e" => return Ok(std::f64::consts::E),
+            _ => {}
+        }
+
+        if !self.eat('(') {
+            return Err(format!("Unknown constant '{}'", name));
+        }
+        let mut args = Vec::new();
+        if !self.eat(')') {
+            loop {
+                args.push(self.expr()?);
+                if self.eat(')') {
+                    break;
+                }
+                if !self.eat(',') {
+                    return Err(format!("Expected ',' or ')' in call to {}", name));
+                }
+            }
+        }
+
+        let unary = |f: fn(f64) -> f64| match args.as_slice() {
+            [x] => Ok(f(*x)),
+            _ => Err(format!("{} takes one argument", name)),
+        };
+        match name.as_str() {
+            "sqrt" => unary(f64::sqrt),
+            "abs" => unary(f64::abs),
+            "exp" => unary(f64::exp),
+            "ln" => unary(f64::ln),
+            "log10" | "log" => unary(f64::log10),
+            "log2" => unary(f64::log2),
+            "sin" => unary(f64::sin),
+            "cos" => unary(f64::cos),
+            "tan" => unary(f64::tan),
+            "asin" => unary(f64::asin),
+            "acos" => unary(f64::acos),
+            "atan" => unary(f64::atan),
+            "floor" => unary(f64::floor),
+            "ceil" => unary(f64::ceil),
+            "round" => unary(f64::round),
+            "min" | "max" if !args.is_empty() => {
+                let fold = if name == "min" { f64::min } else { f64::max };
+                Ok(args.iter().copied().fold(args[0], fold))
+            }
+            "pow" => match args.as_slice() {
+                [base, exponent] => Ok(base.powf(*exponent)),
+                _ => Err("pow takes two arguments".to_string()),
+            },
+            _ => Err(format!("Unknown function '{}'", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_with_precedence() {
+        assert_eq!(evaluate("2 + 3 * 4 ^ 2 / 8"), Ok(8.0));
+        assert_eq!(evaluate("-2 ^ 2"), Ok(-4.0));
+        assert_eq!(evaluate("max(1, pow(2, 3), sqrt(16))"), Ok(8.0));
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let parens = format!("{}1{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert_eq!(evaluate(&parens), Err("Expression too deeply nested".to_string()));
+        let signs = format!("{}1", "-".repeat(MAX_DEPTH));
+        assert_eq!(evaluate(&signs), Err("Expression too deeply nested".to_string()));
+        let calls = format!("{}1{}", "abs(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert_eq!(evaluate(&calls), Err("Expression too deeply nested".to_string()));
+
+        let fits = format!("{}1{}", "(".repeat(MAX_DEPTH - 1), ")".repeat(MAX_DEPTH - 1));
+        assert_eq!(evaluate(&fits), Ok(1.0));
+    }
+
+    #[test]
+    fn rejects_long_input_before_parsing() {
+        let long = "1+".repeat(MAX_EXPRESSION_LEN / 2) + "1";
+        assert!(evaluate(&long).is_err_and(|e| e.starts_with("Expression is longer")));
+    }
+}
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use chrono::{FixedOffset, Utc};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    clients::llm_client::ToolDefinition,
+    error::{AppError, Result},
+    tools::registry::Tool,
+};
+
+#[derive(Debug, Default, Deserialize)]
+struct Arguments {
+    utc_offset: Option<String>,
+}
+
+/// Tells the model the current date and time, which it otherwise can't know.
+pub struct CurrentTime;
+
+#[async_trait]
+impl Tool for CurrentTime {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "current_time".to_string(),
+            description: "Get the current date and time.".to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "utc_offset": {
+                        "type": "string",
+                        "description": "Offset from UTC to report the time in, such as \"+02:00\" or \"-05:30\". Defaults to UTC."
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> Result<String> {
+        let arguments: Arguments = serde_json::from_value(arguments)
+            .map_err(|e| AppError::BadRequest(format!("Invalid arguments: {}", e)))?;
+
+        let offset = match arguments.utc_offset.as_deref().map(str::trim) {
+            None | Some("") | Some("Z") | Some("UTC") => FixedOffset::east_opt(0),
+            Some(offset) => offset.parse::<FixedOffset>().ok(),
+        }
+        .ok_or_else(|| AppError::BadRequest("utc_offset must look like +02:00".to_string()))?;
+
+        let now = Utc::now().with_timezone(&offset);
+        Ok(json!({
+            "datetime": now.to_rfc3339(),
+            "weekday": now.format("%A").to_string(),
+            "unix_timestamp": now.timestamp(),
+        })
+        .to_string())
+    }
+}
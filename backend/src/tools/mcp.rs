@@ -0,0 +1,61 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::{
+    clients::{
+        llm_client::ToolDefinition,
+        mcp_client::{McpSession, McpToolInfo},
+    },
+    error::{AppError, Result},
+    tools::registry::Tool,
+};
+
+/// Providers reject function names longer than this.
+const MAX_NAME_LEN: usize = 64;
+
+/// A tool served by one of the user's MCP servers. It is offered to the model
+/// as `<server>__<tool>` so tools of different servers can't collide.
+pub struct McpTool {
+    session: Arc<McpSession>,
+    server_name: String,
+    tool: McpToolInfo,
+}
+
+impl McpTool {
+    pub fn new(session: Arc<McpSession>, server_name: String, tool: McpToolInfo) -> Self {
+        Self { session, server_name, tool }
+    }
+}
+
+#[async_trait]
+impl Tool for McpTool {
+    fn definition(&self) -> ToolDefinition {
+        let name = format!("{}__{}", sanitize(&self.server_name), sanitize(&self.tool.name));
+        let description = match self.tool.description.as_deref() {
+            Some(description) if !description.trim().is_empty() => {
+                format!("[{}] {}", self.server_name, description)
+            }
+            _ => format!("[{}] {}", self.server_name, self.tool.name),
+        };
+        ToolDefinition {
+            name: name.chars().take(MAX_NAME_LEN).collect(),
+            description,
+            parameters: self.tool.input_schema.clone(),
+        }
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> Result<String> {
+        let result = self.session.call_tool(&self.tool.name, arguments).await?;
+        if result.is_error {
+            return Err(AppError::BadRequest(result.content));
+        }
+        Ok(result.content)
+    }
+}
+
+/// Function names may only contain letters, digits, `_` and `-`.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,4 @@
+pub mod registry;
+pub mod current_time;
+pub mod calculator;
+pub mod mcp;
\ No newline at end of file
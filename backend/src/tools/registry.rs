@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    clients::llm_client::ToolDefinition,
+    error::{AppError, Result},
+    models::conversation_message::ToolCall,
+    tools::{calculator::Calculator, current_time::CurrentTime},
+};
+
+const TOOL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A function the model can call while generating a reply.
+#[async_trait]
+pub trait Tool: Send + Sync {
+    fn definition(&self) -> ToolDefinition;
+
+    /// Runs the tool with the model's arguments. Errors are shown to the
+    /// model so it can correct itself, rather than failing the turn.
+    async fn call(&self, arguments: serde_json::Value) -> Result<String>;
+}
+
+/// The result of a tool call as it is sent back to the model.
+#[derive(Debug, Clone)]
+pub struct ToolOutput {
+    pub content: String,
+    pub is_error: bool,
+}
+
+impl ToolOutput {
+    pub fn error(message: String) -> Self {
+        Self { content: message, is_error: true }
+    }
+}
+
+/// The tools offered to the model for one generation.
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Vec<(ToolDefinition, Arc<dyn Tool>)>,
+}
+
+impl ToolRegistry {
+    /// Tools that are always available and need no configuration.
+    pub fn builtin() -> Self {
+        let mut registry = Self::default();
+        registry.register(Arc::new(CurrentTime));
+        registry.register(Arc::new(Calculator));
+        registry
+    }
+
+    /// Adds a tool, replacing any registered tool of the same name.
+    pub fn register(&mut self, tool: Arc<dyn Tool>) {
+        let definition = tool.definition();
+        self.tools.retain(|(d, _)| d.name != definition.name);
+        self.tools.push((definition, tool));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        self.tools.iter().map(|(d, _)| d.clone()).collect()
+    }
+
+    pub async fn call(&self, call: &ToolCall) -> ToolOutput {
+        let Some((_, tool)) = self.tools.iter().find(|(d, _)| d.name == call.name) else {
+            return ToolOutput::error(format!("Unknown tool: {}", call.name));
+        };
+
+        // Models send an empty string for tools without parameters
+        let arguments = if call.arguments.trim().is_empty() {
+            serde_json::Value::Object(Default::default())
+        } else {
+            match serde_json::from_str(&call.arguments) {
+                Ok(arguments) => arguments,
+                Err(e) => return ToolOutput::error(format!("Arguments are not valid JSON: {}", e)),
+            }
+        };
+
+        match tokio::time::timeout(TOOL_TIMEOUT, tool.call(arguments)).await {
+            Ok(Ok(content)) => ToolOutput { content, is_error: false },
+            // Argument errors are meant for the model, without the HTTP-style prefix
+            Ok(Err(AppError::BadRequest(message))) => ToolOutput::error(message),
+            Ok(Err(e)) => ToolOutput::error(e.to_string()),
+            Err(_) => ToolOutput::error(format!("Tool {} timed out", call.name)),
+        }
+    }
+}
@@ -12,4 +12,21 @@ impl ToUuidV7 for DateTime<Utc> {
         let timestamp = Timestamp::from_unix(NoContext, seconds, nanoseconds);
         Uuid::new_v7(timestamp)
     }
-}
\ No newline at end of file
+}
+/// Treats whitespace-only text as unset.
+pub fn non_blank(text: Option<String>) -> Option<String> {
+    text.filter(|t| !t.trim().is_empty())
+}
+
+/// A random secret such as an API key: `prefix` followed by 40 letters and digits.
+pub fn generate_secret(prefix: &str) -> String {
+    use rand::{distr::Alphanumeric, Rng};
+    let random: String = rand::rng().sample_iter(&Alphanumeric).take(40).map(char::from).collect();
+    format!("{}{}", prefix, random)
+}
+
+/// Secrets are random enough that a plain SHA-256 is safe to store and fast to look up.
+pub fn hash_secret(secret: &str) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
@@ -0,0 +1,20 @@
+import type { ApiKey, CreateApiKeyRequest, CreatedApiKey } from "@/types/apiKey"
+import request from "@/utils/request"
+
+enum Api {
+    Keys = "/api/api-keys",
+    Key = "/api/api-keys/{id}",
+}
+
+export async function getApiKeysApi() {
+    const data = await request.get<{ items: ApiKey[] }>(Api.Keys)
+    return data.items
+}
+
+export function createApiKeyApi(data: CreateApiKeyRequest) {
+    return request.post<CreatedApiKey>(Api.Keys, data)
+}
+
+export function revokeApiKeyApi(id: string) {
+    return request.delete<never>(Api.Key.replace("{id}", id))
+}
@@ -0,0 +1,33 @@
+import request from "@/utils/request"
+import { useUserStore } from "@/stores"
+import type { Attachment } from "@/types/conversation"
+
+enum Api {
+    Attachments = "/api/attachments",
+    Attachment = "/api/attachments/{id}",
+}
+
+export function uploadAttachmentApi(file: File) {
+    const form = new FormData()
+    form.append("file", file)
+    return request.post<Attachment>(Api.Attachments, form, {
+        headers: { "Content-Type": "multipart/form-data" },
+        timeout: 60000,
+    })
+}
+
+export function deleteAttachmentApi(id: string) {
+    return request.delete<never>(Api.Attachment.replace("{id}", id))
+}
+
+/** Fetches the image itself, which is served raw rather than wrapped in an ApiResponse */
+export async function getAttachmentBlobApi(id: string) {
+    const userStore = useUserStore()
+    const response = await fetch(Api.Attachment.replace("{id}", id), {
+        headers: { "Authorization": `Bearer ${userStore.token}` },
+    })
+    if (!response.ok) {
+        throw new Error(`HTTP error ${response.status}`)
+    }
+    return response.blob()
+}
@@ -3,13 +3,34 @@ import { useUserStore } from "@/stores"
 import type {
     ConversationSessionsResponse,
     ConversationResponse,
-    SendMessageRequest
+    SendMessageRequest,
+    RetryMessageRequest,
+    CompareMessageRequest,
+    ComparisonEvent,
+    SwitchBranchRequest,
+    UpdateFallbackModelsRequest,
+    UpdateGenerationParamsRequest,
+    UpdateContextStrategyRequest,
+    SystemPrompt,
+    ConversationSession,
+    ConversationEvent
 } from "@/types/conversation"
 
 enum Api {
     Conversations = "/api/conversations",
     ConversationMessages = "/api/conversations/{id}/messages",
+    FollowGeneration = "/api/conversations/{id}/stream",
+    CompareMessage = "/api/conversations/{id}/compare",
     DeleteConversation = "/api/conversations/{id}",
+    CancelGeneration = "/api/conversations/{id}/cancel",
+    FallbackModels = "/api/conversations/{id}/fallbacks",
+    GenerationParams = "/api/conversations/{id}/params",
+    SystemPrompt = "/api/conversations/{id}/system-prompt",
+    ContextStrategy = "/api/conversations/{id}/context-strategy",
+    RetryMessage = "/api/conversations/{id}/messages/{messageId}/retry",
+    EditMessage = "/api/conversations/{id}/messages/{messageId}/edit",
+    RegenerateMessage = "/api/conversations/{id}/messages/{messageId}/regenerate",
+    SwitchBranch = "/api/conversations/{id}/messages/{messageId}/switch",
 }
 
 export function listConversationsApi() {
@@ -28,99 +49,264 @@ export function deleteConversationApi(conversationId: string) {
     return request.delete<ConversationResponse>(Api.DeleteConversation.replace("{id}", conversationId))
 }
 
+export function cancelGenerationApi(conversationId: string) {
+    return request.post<never>(Api.CancelGeneration.replace("{id}", conversationId))
+}
+
+export function switchBranchApi(conversationId: string, messageId: string, data: SwitchBranchRequest) {
+    return request.post<ConversationResponse>(
+        Api.SwitchBranch.replace("{id}", conversationId).replace("{messageId}", messageId),
+        data
+    )
+}
+
+export function updateFallbackModelsApi(conversationId: string, data: UpdateFallbackModelsRequest) {
+    return request.put<ConversationSession>(Api.FallbackModels.replace("{id}", conversationId), data)
+}
+
+export function updateGenerationParamsApi(conversationId: string, data: UpdateGenerationParamsRequest) {
+    return request.put<ConversationSession>(Api.GenerationParams.replace("{id}", conversationId), data)
+}
+
+export function updateContextStrategyApi(conversationId: string, data: UpdateContextStrategyRequest) {
+    return request.put<ConversationSession>(Api.ContextStrategy.replace("{id}", conversationId), data)
+}
+
+export function getSystemPromptApi(conversationId: string) {
+    return request.get<SystemPrompt>(Api.SystemPrompt.replace("{id}", conversationId))
+}
+
+export function updateSystemPromptApi(conversationId: string, data: SystemPrompt) {
+    return request.put<ConversationSession>(Api.SystemPrompt.replace("{id}", conversationId), data)
+}
+
 /**
  * Send a message and receive streaming response via SSE
  * @param conversationId - The conversation ID
  * @param data - The message data containing content and provider_model_id
- * @param onMessage - Callback function to handle each chunk of the streaming response
- * @param onError - Callback function to handle errors
+ * @param onEvent - Callback function to handle each typed event of the stream
+ * @param onError - Callback function to handle connection errors
  * @param onComplete - Callback function to handle completion
  * @returns A function to abort the connection
  */
 export function sendMessageApi(
     conversationId: string,
     data: SendMessageRequest,
-    onMessage: (chunk: string) => void,
+    onEvent: (event: ConversationEvent) => void,
     onError?: (error: Error) => void,
     onComplete?: () => void
 ): () => void {
-    const userStore = useUserStore()
     const url = Api.ConversationMessages.replace("{id}", conversationId)
+    return streamApi(url, data, onEvent, onError, onComplete, Api.FollowGeneration.replace("{id}", conversationId))
+}
+
+/**
+ * Send a message to several models at once and receive their replies, tagged by model, via SSE
+ * @returns A function to abort the connection
+ */
+export function compareMessageApi(
+    conversationId: string,
+    data: CompareMessageRequest,
+    onEvent: (event: ComparisonEvent) => void,
+    onError?: (error: Error) => void,
+    onComplete?: () => void
+): () => void {
+    const url = Api.CompareMessage.replace("{id}", conversationId)
+    return streamApi(url, data, onEvent, onError, onComplete)
+}
+
+/**
+ * Follow the session's latest generation, replaying its events after `lastEventId`, e.g. from another tab
+ * @returns A function to abort the connection
+ */
+export function followGenerationApi(
+    conversationId: string,
+    onEvent: (event: ConversationEvent) => void,
+    onError?: (error: Error) => void,
+    onComplete?: () => void,
+    lastEventId: string | null = null
+): () => void {
+    const url = Api.FollowGeneration.replace("{id}", conversationId)
+    return streamApi(url, undefined, onEvent, onError, onComplete, url, lastEventId)
+}
+
+/**
+ * Retry a failed or cancelled reply and receive streaming response via SSE
+ * @returns A function to abort the connection
+ */
+export function retryMessageApi(
+    conversationId: string,
+    messageId: string,
+    data: RetryMessageRequest,
+    onEvent: (event: ConversationEvent) => void,
+    onError?: (error: Error) => void,
+    onComplete?: () => void
+): () => void {
+    const url = Api.RetryMessage.replace("{id}", conversationId).replace("{messageId}", messageId)
+    return streamApi(url, data, onEvent, onError, onComplete, Api.FollowGeneration.replace("{id}", conversationId))
+}
+
+/**
+ * Send a new version of an earlier prompt, branching the conversation, and receive streaming response via SSE
+ * @returns A function to abort the connection
+ */
+export function editMessageApi(
+    conversationId: string,
+    messageId: string,
+    data: SendMessageRequest,
+    onEvent: (event: ConversationEvent) => void,
+    onError?: (error: Error) => void,
+    onComplete?: () => void
+): () => void {
+    const url = Api.EditMessage.replace("{id}", conversationId).replace("{messageId}", messageId)
+    return streamApi(url, data, onEvent, onError, onComplete, Api.FollowGeneration.replace("{id}", conversationId))
+}
+
+/**
+ * Generate another answer alongside an existing reply and receive streaming response via SSE
+ * @returns A function to abort the connection
+ */
+export function regenerateMessageApi(
+    conversationId: string,
+    messageId: string,
+    data: RetryMessageRequest,
+    onEvent: (event: ConversationEvent) => void,
+    onError?: (error: Error) => void,
+    onComplete?: () => void
+): () => void {
+    const url = Api.RegenerateMessage.replace("{id}", conversationId).replace("{messageId}", messageId)
+    return streamApi(url, data, onEvent, onError, onComplete, Api.FollowGeneration.replace("{id}", conversationId))
+}
 
+/** How often a dropped stream is resumed before giving up */
+const MAX_RESUME_ATTEMPTS = 3
+
+/**
+ * Streams SSE events from `url`, posting `data`, or with a GET when there is none.
+ * When the connection drops mid-stream and `resumeUrl` is set, the stream is
+ * resumed there after the last event received.
+ */
+function streamApi<E = ConversationEvent>(
+    url: string,
+    data: unknown,
+    onEvent: (event: E) => void,
+    onError?: (error: Error) => void,
+    onComplete?: () => void,
+    resumeUrl?: string,
+    lastEventId: string | null = null
+): () => void {
+    const userStore = useUserStore()
     const controller = new AbortController()
 
-    fetch(url, {
-        method: "POST",
-        headers: {
-            "Content-Type": "application/json",
-            "Authorization": `Bearer ${userStore.token}`,
-        },
-        body: JSON.stringify(data),
-        signal: controller.signal,
-    })
-        .then(async (response) => {
-            if (!response.ok) {
-                throw new Error(`HTTP error ${response.status}`)
+    const open = (target: string, body: unknown) => {
+        const headers: Record<string, string> = { "Authorization": `Bearer ${userStore.token}` }
+        if (body !== undefined) {
+            headers["Content-Type"] = "application/json"
+        }
+        if (lastEventId) {
+            headers["Last-Event-ID"] = lastEventId
+        }
+        return fetch(target, {
+            method: body === undefined ? "GET" : "POST",
+            headers,
+            body: body === undefined ? undefined : JSON.stringify(body),
+            signal: controller.signal,
+        })
+    }
+
+    const read = async (response: Response) => {
+        if (!response.ok) {
+            const body = await response.json().catch(() => null)
+            throw new Error(body?.error || `HTTP error ${response.status}`)
+        }
+
+        const reader = response.body?.getReader()
+        const decoder = new TextDecoder()
+
+        if (!reader) {
+            throw new Error("Response body is not readable")
+        }
+
+        let currentEventData = ""
+        let currentEventId: string | null = null
+        let hasEventData = false
+        let buffer = ""
+
+        // Every event carries its type in the JSON payload, so the `event:` line is not needed
+        const dispatch = () => {
+            try {
+                onEvent(JSON.parse(currentEventData) as E)
+            } catch {
+                console.error("Malformed stream event", currentEventData)
             }
+            if (currentEventId) {
+                lastEventId = currentEventId
+            }
+        }
 
-            const reader = response.body?.getReader()
-            const decoder = new TextDecoder()
+        while (true) {
+            const { done, value } = await reader.read()
 
-            if (!reader) {
-                throw new Error("Response body is not readable")
+            if (done) {
+                if (hasEventData) {
+                    dispatch()
+                }
+                break
             }
 
-            try {
-                let currentEventData = ""
-                let hasEventData = false
-                let buffer = ""
-
-                while (true) {
-                    const { done, value } = await reader.read()
-
-                    if (done) {
-                        if (hasEventData) {
-                             onMessage(currentEventData) 
-                        }
-                        onComplete?.()
-                        break
-                    }
+            buffer += decoder.decode(value, { stream: true })
+            const lines = buffer.split("\n")
+            buffer = lines.pop() || ""
 
-                    buffer += decoder.decode(value, { stream: true })
-                    const lines = buffer.split("\n")
-                    buffer = lines.pop() || ""
-
-                    for (const line of lines) {
-                        if (line === "") {
-                            // Empty line indicates end of event
-                            if (hasEventData) {
-                                onMessage(currentEventData)
-                                currentEventData = ""
-                                hasEventData = false
-                            }
-                        } else if (line.startsWith("data: ")) {
-                            const data = line.slice(6)
-                            if (hasEventData) {
-                                currentEventData += "\n"
-                            }
-                            currentEventData += data
-                            hasEventData = true
-                        } else if (line.startsWith("event: error")) {
-                            console.error("Received error event from stream")
-                        }
+            for (const line of lines) {
+                if (line === "") {
+                    // Empty line indicates end of event
+                    if (hasEventData) {
+                        dispatch()
+                        currentEventData = ""
+                        hasEventData = false
+                    }
+                    currentEventId = null
+                } else if (line.startsWith("id: ")) {
+                    currentEventId = line.slice(4)
+                } else if (line.startsWith("data: ")) {
+                    const data = line.slice(6)
+                    if (hasEventData) {
+                        currentEventData += "\n"
                     }
+                    currentEventData += data
+                    hasEventData = true
                 }
+            }
+        }
+    }
+
+    const run = async () => {
+        let target = url
+        let body = data
+        for (let attempt = 0; ; attempt++) {
+            try {
+                await read(await open(target, body))
+                onComplete?.()
+                return
             } catch (error) {
-                if (error instanceof Error && error.name !== "AbortError") {
-                    onError?.(error)
+                // Only a stream that got under way can be resumed
+                const resumable = resumeUrl && lastEventId && attempt < MAX_RESUME_ATTEMPTS
+                if (!resumable || (error instanceof Error && error.name === "AbortError")) {
+                    throw error
                 }
+                await new Promise(resolve => setTimeout(resolve, 1000 * (attempt + 1)))
+                target = resumeUrl
+                body = undefined
             }
-        })
-        .catch((error) => {
-            if (error.name !== "AbortError") {
-                onError?.(error)
-            }
-        })
+        }
+    }
+
+    run().catch((error) => {
+        if (error.name !== "AbortError") {
+            onError?.(error)
+        }
+    })
 
     return () => controller.abort()
 }
@@ -0,0 +1,30 @@
+import type { CreateMcpServerRequest, McpServer, McpTool, UpdateMcpServerRequest } from "@/types/mcp"
+import request from "@/utils/request"
+
+enum Api {
+    Servers = "/api/mcp-servers",
+    Server = "/api/mcp-servers/{id}",
+    Tools = "/api/mcp-servers/{id}/tools",
+}
+
+export async function getMcpServersApi() {
+    const data = await request.get<{ items: McpServer[] }>(Api.Servers)
+    return data.items
+}
+
+export function createMcpServerApi(data: CreateMcpServerRequest) {
+    return request.post<McpServer>(Api.Servers, data)
+}
+
+export function updateMcpServerApi(id: string, data: UpdateMcpServerRequest) {
+    return request.put<McpServer>(Api.Server.replace("{id}", id), data)
+}
+
+export function deleteMcpServerApi(id: string) {
+    return request.delete<never>(Api.Server.replace("{id}", id))
+}
+
+export async function getMcpServerToolsApi(id: string) {
+    const data = await request.get<{ items: McpTool[] }>(Api.Tools.replace("{id}", id))
+    return data.items
+}
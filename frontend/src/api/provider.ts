@@ -1,10 +1,12 @@
 import type { CreateProviderRequest, UserProvider, ProviderModel, UpdateProviderRequest } from "@/types/provider"
+import type { SpendingBudget } from "@/types/user"
 import request from "@/utils/request"
 
 enum Api {
     UserProviders = "/api/providers",
     UserProvider = "/api/providers/{id}",
-    Check = "/api/providers/check/{id}"
+    Check = "/api/providers/check/{id}",
+    Budget = "/api/providers/{id}/budget",
 }
 
 interface ProviderWithModels {
@@ -38,4 +40,8 @@ export function deleteUserProviderApi(id: string) {
 
 export function checkUserProviderApi(id: string) {
     return request.post<never>(Api.Check.replace("{id}", id))
-}
\ No newline at end of file
+}
+
+export function updateUserProviderBudgetApi(id: string, budget: SpendingBudget | null) {
+    return request.put<UserProvider>(Api.Budget.replace("{id}", id), { budget })
+}
@@ -0,0 +1,20 @@
+import type { CreatePersonalAccessTokenRequest, CreatedPersonalAccessToken, PersonalAccessToken } from "@/types/token"
+import request from "@/utils/request"
+
+enum Api {
+    Tokens = "/api/tokens",
+    Token = "/api/tokens/{id}",
+}
+
+export async function getTokensApi() {
+    const data = await request.get<{ items: PersonalAccessToken[] }>(Api.Tokens)
+    return data.items
+}
+
+export function createTokenApi(data: CreatePersonalAccessTokenRequest) {
+    return request.post<CreatedPersonalAccessToken>(Api.Tokens, data)
+}
+
+export function revokeTokenApi(id: string) {
+    return request.delete<never>(Api.Token.replace("{id}", id))
+}
@@ -1,10 +1,12 @@
-import type { AuthResponse, LoginRequest } from "@/types/user"
+import type { AuthResponse, CustomInstructions, LoginRequest, UserPreferences } from "@/types/user"
 import request from "@/utils/request"
 
 enum Api {
     Register = "/api/auth/register",
     Login = "/api/auth/login",
     Logout = "/api/auth/logout",
+    Preferences = "/api/users/me/preferences",
+    CustomInstructions = "/api/users/me/instructions",
 }
 
 export function registerApi(data: LoginRequest) {
@@ -18,3 +20,19 @@ export function loginApi(data: LoginRequest) {
 export function logoutApi() {
     return request.post<AuthResponse>(Api.Logout)
 }
+
+export function getPreferencesApi() {
+    return request.get<UserPreferences>(Api.Preferences)
+}
+
+export function updatePreferencesApi(data: UserPreferences) {
+    return request.put<UserPreferences>(Api.Preferences, data)
+}
+
+export function getCustomInstructionsApi() {
+    return request.get<CustomInstructions>(Api.CustomInstructions)
+}
+
+export function updateCustomInstructionsApi(data: CustomInstructions) {
+    return request.put<CustomInstructions>(Api.CustomInstructions, data)
+}
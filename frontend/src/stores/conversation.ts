@@ -1,21 +1,34 @@
 import { defineStore } from "pinia"
 import { ref } from "vue"
-import { type ConversationSession, type ConversationMessage, ChatRole } from "@/types/conversation"
+import { type Attachment, type ConversationSession, type ConversationMessage, type ConversationEvent, type ConversationResponse, ChatRole } from "@/types/conversation"
 import { 
     listConversationsApi, 
     createConversationApi, 
     listMessagesApi, 
     sendMessageApi,
-    deleteConversationApi
+    deleteConversationApi,
+    cancelGenerationApi,
+    retryMessageApi,
+    editMessageApi,
+    regenerateMessageApi,
+    switchBranchApi,
+    compareMessageApi,
+    followGenerationApi
 } from "@/api/conversation"
+import { useToast } from "@/composables/useToast"
 
 export const useConversationStore = defineStore("conversation", () => {
+    const toast = useToast()
     const conversations = ref<ConversationSession[]>([])
     const currentConversationId = ref<string | null>(null)
     const messages = ref<ConversationMessage[]>([])
+    /** Side-by-side replies to the last prompt, in the order the models were picked */
+    const comparison = ref<ConversationMessage[]>([])
     const isLoading = ref(false)
     const isStreaming = ref(false)
     const abortStream = ref<(() => void) | null>(null)
+    /** Whether the current stream only watches a reply, rather than one this tab started */
+    let following = false
 
     const fetchConversations = async () => {
         isLoading.value = true
@@ -42,16 +55,84 @@ export const useConversationStore = defineStore("conversation", () => {
     }
 
     const selectConversation = async (id: string) => {
+        if (following) {
+            // Watching stops with the conversation; the generation carries on
+            abortStream.value?.()
+            stopFollowing()
+        }
         currentConversationId.value = id
+        comparison.value = []
         isLoading.value = true
         try {
-            messages.value = (await listMessagesApi(id)).items
+            showBranch(await listMessagesApi(id))
         } finally {
             isLoading.value = false
         }
+        if (messages.value.at(-1)?.status === "streaming" && !isStreaming.value) {
+            followGeneration(id)
+        }
+    }
+
+    /** Follows a reply that is still being generated, e.g. one started in another tab */
+    const followGeneration = (conversationId: string) => {
+        let current: ConversationMessage | null = null
+        isStreaming.value = true
+        following = true
+
+        abortStream.value = followGenerationApi(
+            conversationId,
+            (event) => {
+                if (event.type === "start") {
+                    // The replay starts from the first event; drop what was loaded of this reply
+                    const index = messages.value.findIndex(m => m.id === event.assistant_message_id)
+                    if (index >= 0) {
+                        messages.value.splice(index)
+                    }
+                    current = appendMessage({
+                        id: event.assistant_message_id,
+                        session_id: conversationId,
+                        role: ChatRole.Assistant,
+                        content: '',
+                        created_at: new Date().toISOString(),
+                        updated_at: new Date().toISOString()
+                    })
+                }
+                if (current) {
+                    current = applyEvent(event, current)
+                }
+            },
+            (error) => {
+                console.error("Stream error", error)
+                stopFollowing()
+            },
+            () => {
+                stopFollowing()
+                refreshBranch(conversationId)
+            }
+        )
+    }
+
+    const stopFollowing = () => {
+        following = false
+        isStreaming.value = false
+        abortStream.value = null
+    }
+
+    const showBranch = (res: ConversationResponse) => {
+        messages.value = res.items.map(message => ({
+            ...message,
+            attachments: res.attachments.filter(a => a.message_id === message.id)
+        }))
+    }
+
+    /** Reloads the active branch so sibling counts include the reply just generated */
+    const refreshBranch = async (conversationId: string) => {
+        if (currentConversationId.value === conversationId) {
+            showBranch(await listMessagesApi(conversationId))
+        }
     }
 
-    const sendMessage = async (content: string, modelId: string) => {
+    const sendMessage = async (content: string, modelId: string, attachments: Attachment[] = []) => {
         if (!currentConversationId.value) {
             currentConversationId.value = await createConversation()
         }
@@ -63,6 +144,7 @@ export const useConversationStore = defineStore("conversation", () => {
             session_id: conversationId,
             role: ChatRole.User,
             content: content,
+            attachments,
             created_at: new Date().toISOString(),
             updated_at: new Date().toISOString()
         }
@@ -86,29 +168,18 @@ export const useConversationStore = defineStore("conversation", () => {
             return
         }
 
+        const userMsgReactive = messages.value[messages.value.length - 2]!
+
         abortStream.value = sendMessageApi(
             conversationId,
-            { content, provider_model_id: modelId },
-            (chunk) => {
-                try {
-                    if (chunk.startsWith('"') && chunk.endsWith('"')) {
-                        try {
-                        const parsed = JSON.parse(chunk)
-                        assistantMsgReactive.content += parsed
-                        } catch {
-                        assistantMsgReactive.content += chunk
-                        }
-                    } else {
-                        assistantMsgReactive.content += chunk
-                    }
-                } catch (e) {
-                    assistantMsgReactive.content += chunk
-                }
-            },
+            { content, attachment_ids: attachments.map(a => a.id), provider_model_id: modelId },
+            eventHandler(assistantMsgReactive, userMsgReactive),
             (error) => {
                 console.error("Stream error", error)
                 isStreaming.value = false
                 abortStream.value = null
+                assistantMsgReactive.status = "error"
+                assistantMsgReactive.error = error.message
                 assistantMsgReactive.content += "\n[Error generating response]"
             },
             () => {
@@ -119,7 +190,288 @@ export const useConversationStore = defineStore("conversation", () => {
         )
     }
 
+    const retryMessage = (message: ConversationMessage, modelId?: string) => {
+        const conversationId = currentConversationId.value
+        if (!conversationId) {
+            return
+        }
+
+        message.content = ''
+        message.status = "streaming"
+        message.error = null
+        isStreaming.value = true
+
+        abortStream.value = retryMessageApi(
+            conversationId,
+            message.id,
+            { provider_model_id: modelId },
+            eventHandler(message),
+            (error) => {
+                console.error("Stream error", error)
+                isStreaming.value = false
+                abortStream.value = null
+                message.status = "error"
+                message.error = error.message
+            },
+            () => {
+                isStreaming.value = false
+                abortStream.value = null
+            }
+        )
+    }
+
+    /** Sends a prompt to several models; the first reply continues the conversation until another is picked */
+    const compareMessage = async (content: string, modelIds: string[], attachments: Attachment[] = []) => {
+        if (!currentConversationId.value) {
+            currentConversationId.value = await createConversation()
+        }
+        const conversationId = currentConversationId.value!
+
+        const userMsg = appendMessage({
+            id: 'temp-' + Date.now(),
+            session_id: conversationId,
+            role: ChatRole.User,
+            content,
+            attachments,
+            created_at: new Date().toISOString(),
+            updated_at: new Date().toISOString()
+        })
+        comparison.value = modelIds.map((modelId, index) => ({
+            id: `temp-ai-${Date.now()}-${index}`,
+            session_id: conversationId,
+            role: ChatRole.Assistant,
+            content: '',
+            provider_model_id: modelId,
+            created_at: new Date().toISOString(),
+            updated_at: new Date().toISOString()
+        }))
+        const handlers = new Map(comparison.value.map(reply => [reply.provider_model_id!, eventHandler(reply, userMsg)]))
+        isStreaming.value = true
+
+        abortStream.value = compareMessageApi(
+            conversationId,
+            { content, attachment_ids: attachments.map(a => a.id), provider_model_ids: modelIds },
+            (event) => handlers.get(event.provider_model_id)?.(event),
+            (error) => {
+                console.error("Stream error", error)
+                isStreaming.value = false
+                abortStream.value = null
+                for (const reply of comparison.value) {
+                    reply.status = "error"
+                    reply.error = error.message
+                }
+            },
+            () => {
+                isStreaming.value = false
+                abortStream.value = null
+                fetchConversations()
+                refreshBranch(conversationId)
+            }
+        )
+    }
+
+    /** Continues the conversation with the comparison reply at `index` */
+    const pickComparisonReply = async (index: number) => {
+        const first = messages.value.find(m => m.id === comparison.value[0]?.id)
+        if (!first) {
+            return
+        }
+        await switchBranch(first, (first.sibling_index ?? 0) + index)
+        comparison.value = []
+    }
+
+    /** Sends a new version of `message`, replacing it and everything after it on screen */
+    const editMessage = (message: ConversationMessage, content: string, modelId: string, attachments: Attachment[] = []) => {
+        const conversationId = currentConversationId.value
+        const index = messages.value.indexOf(message)
+        if (!conversationId || index < 0) {
+            return
+        }
+        messages.value.splice(index)
+        const userMsg = appendMessage({
+            id: 'temp-' + Date.now(),
+            session_id: conversationId,
+            role: ChatRole.User,
+            content,
+            attachments,
+            created_at: new Date().toISOString(),
+            updated_at: new Date().toISOString()
+        })
+        const assistantMsg = appendMessage({
+            id: 'temp-ai-' + Date.now(),
+            session_id: conversationId,
+            role: ChatRole.Assistant,
+            content: '',
+            created_at: new Date().toISOString(),
+            updated_at: new Date().toISOString()
+        })
+        isStreaming.value = true
+
+        abortStream.value = editMessageApi(
+            conversationId,
+            message.id,
+            { content, attachment_ids: attachments.map(a => a.id), provider_model_id: modelId },
+            eventHandler(assistantMsg, userMsg),
+            (error) => {
+                console.error("Stream error", error)
+                isStreaming.value = false
+                abortStream.value = null
+                assistantMsg.status = "error"
+                assistantMsg.error = error.message
+            },
+            () => {
+                isStreaming.value = false
+                abortStream.value = null
+                refreshBranch(conversationId)
+            }
+        )
+    }
+
+    /** Generates another answer to the prompt `message` replied to, keeping `message` as a sibling */
+    const regenerateMessage = (message: ConversationMessage, modelId?: string) => {
+        const conversationId = currentConversationId.value
+        const index = messages.value.indexOf(message)
+        if (!conversationId || index < 0) {
+            return
+        }
+        const promptIndex = messages.value.slice(0, index).map(m => m.role).lastIndexOf(ChatRole.User)
+        messages.value.splice(promptIndex + 1)
+        const assistantMsg = appendMessage({
+            id: 'temp-ai-' + Date.now(),
+            session_id: conversationId,
+            role: ChatRole.Assistant,
+            content: '',
+            created_at: new Date().toISOString(),
+            updated_at: new Date().toISOString()
+        })
+        isStreaming.value = true
+
+        abortStream.value = regenerateMessageApi(
+            conversationId,
+            message.id,
+            { provider_model_id: modelId },
+            eventHandler(assistantMsg),
+            (error) => {
+                console.error("Stream error", error)
+                isStreaming.value = false
+                abortStream.value = null
+                assistantMsg.status = "error"
+                assistantMsg.error = error.message
+            },
+            () => {
+                isStreaming.value = false
+                abortStream.value = null
+                refreshBranch(conversationId)
+            }
+        )
+    }
+
+    /** Shows another version of `message` and the latest branch below it */
+    const switchBranch = async (message: ConversationMessage, siblingIndex: number) => {
+        const conversationId = currentConversationId.value
+        if (!conversationId || isStreaming.value) {
+            return
+        }
+        showBranch(await switchBranchApi(conversationId, message.id, { sibling_index: siblingIndex }))
+    }
+
+    /** Applies stream events, following the reply into new assistant messages after tool calls */
+    const eventHandler = (assistantMsg: ConversationMessage, userMsg?: ConversationMessage) => {
+        let current = assistantMsg
+        return (event: ConversationEvent) => {
+            current = applyEvent(event, current, userMsg)
+        }
+    }
+
+    const appendMessage = (message: ConversationMessage) => {
+        messages.value.push(message)
+        return messages.value[messages.value.length - 1]!
+    }
+
+    const applyEvent = (event: ConversationEvent, assistantMsg: ConversationMessage, userMsg?: ConversationMessage): ConversationMessage => {
+        switch (event.type) {
+            case "start":
+                assistantMsg.id = event.assistant_message_id
+                assistantMsg.status = "streaming"
+                assistantMsg.provider_model_id = event.model.id
+                if (userMsg && event.user_message_id) {
+                    userMsg.id = event.user_message_id
+                }
+                break
+            case "delta":
+                assistantMsg.content += event.text
+                break
+            case "reasoning":
+                assistantMsg.reasoning = (assistantMsg.reasoning ?? '') + event.text
+                break
+            case "tool_call":
+                assistantMsg.tool_calls = [
+                    ...(assistantMsg.tool_calls ?? []),
+                    { id: event.id, name: event.name, arguments: event.arguments }
+                ]
+                break
+            case "tool_result":
+                appendMessage({
+                    id: event.message_id,
+                    session_id: assistantMsg.session_id,
+                    role: ChatRole.Tool,
+                    content: event.content,
+                    status: "complete",
+                    error: event.is_error ? event.content : null,
+                    tool_call_id: event.tool_call_id,
+                    created_at: new Date().toISOString(),
+                    updated_at: new Date().toISOString()
+                })
+                break
+            case "step":
+                assistantMsg.status = "complete"
+                return appendMessage({
+                    id: event.assistant_message_id,
+                    session_id: assistantMsg.session_id,
+                    role: ChatRole.Assistant,
+                    content: '',
+                    status: "streaming",
+                    provider_model_id: assistantMsg.provider_model_id,
+                    created_at: new Date().toISOString(),
+                    updated_at: new Date().toISOString()
+                })
+            case "usage":
+                assistantMsg.prompt_tokens = event.prompt_tokens
+                assistantMsg.completion_tokens = event.completion_tokens
+                assistantMsg.reasoning_tokens = event.reasoning_tokens ?? null
+                assistantMsg.cost = event.cost
+                break
+            case "warning":
+                toast.warning(`${event.scope} ${event.period} budget: ${event.spent} of ${event.limit} spent`)
+                break
+            case "fallback":
+                assistantMsg.provider_model_id = event.to.id
+                toast.warning(`${event.from.name} failed, switching to ${event.to.name}`)
+                break
+            case "title": {
+                const session = conversations.value.find(c => c.id === currentConversationId.value)
+                if (session) {
+                    session.title = event.title
+                }
+                break
+            }
+            case "error":
+                console.error("Received error event from stream", event.code, event.message)
+                assistantMsg.error = event.message
+                break
+            case "done":
+                assistantMsg.status = event.finish_reason === "error"
+                    ? "error"
+                    : event.finish_reason === "cancelled" ? "cancelled" : "complete"
+                break
+        }
+        return assistantMsg
+    }
+
     const stopStreaming = () => {
+        if (currentConversationId.value) {
+            cancelGenerationApi(currentConversationId.value).catch(() => {})
+        }
         if (abortStream.value) {
             abortStream.value()
             abortStream.value = null
@@ -131,6 +483,7 @@ export const useConversationStore = defineStore("conversation", () => {
         conversations,
         currentConversationId,
         messages,
+        comparison,
         loading: isLoading,
         streaming: isStreaming,
         fetchConversations,
@@ -138,6 +491,12 @@ export const useConversationStore = defineStore("conversation", () => {
         deleteConversation,
         selectConversation,
         sendMessage,
+        retryMessage,
+        editMessage,
+        regenerateMessage,
+        switchBranch,
+        compareMessage,
+        pickComparisonReply,
         stopStreaming
     }
 })
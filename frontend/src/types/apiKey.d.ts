@@ -0,0 +1,27 @@
+export interface ApiKeyUsage {
+    requests: number
+    prompt_tokens: number
+    completion_tokens: number
+    /** Decimal, serialized as a string */
+    cost: string
+}
+
+export interface ApiKey {
+    id: string
+    user_id: string
+    name: string
+    /** The start of the secret, to tell keys apart */
+    prefix: string
+    last_used_at?: string | null
+    revoked_at?: string | null
+    created_at: string
+    updated_at: string
+    usage: ApiKeyUsage
+}
+
+export interface CreateApiKeyRequest {
+    name: string
+}
+
+/** Returned once on creation; the secret can't be retrieved later */
+export type CreatedApiKey = Omit<ApiKey, "usage"> & { secret: string }
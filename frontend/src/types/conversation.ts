@@ -1,22 +1,68 @@
+import type { GenerationParams } from "@/types/provider"
+
 export enum ChatRole {
     System = "System",
     User = "User",
     Assistant = "Assistant",
+    Tool = "Tool",
+}
+
+export type MessageStatus = "streaming" | "complete" | "error" | "cancelled"
+
+export interface ToolCall {
+    id: string
+    name: string
+    arguments: string
+}
+
+export interface Attachment {
+    id: string
+    message_id?: string | null
+    file_name: string
+    mime_type: string
+    size: number
+    created_at: string
 }
 
 export interface ConversationMessage {
     id: string
     session_id: string
+    parent_id?: string | null
     role: ChatRole
     content: string
+    reasoning?: string | null
+    status?: MessageStatus
+    error?: string | null
+    provider_model_id?: string | null
+    prompt_tokens?: number | null
+    completion_tokens?: number | null
+    reasoning_tokens?: number | null
+    cost?: string | null
+    tool_calls?: ToolCall[] | null
+    tool_call_id?: string | null
+    generation_params?: GenerationParams | null
+    attachments?: Attachment[]
+    /** Position among the versions of this message, for switching branches */
+    sibling_index?: number
+    sibling_count?: number
     created_at: string
     updated_at: string
 }
 
+/** How history that outgrows the model's context window is shortened. */
+export type ContextStrategy =
+    | { type: "drop_oldest" }
+    | { type: "keep_last"; messages: number }
+    | { type: "summarize" }
+
 export interface ConversationSession {
     id: string
     user_id: string
     title: string | null
+    system_prompt?: string | null
+    fallback_model_ids?: string[] | null
+    generation_params?: GenerationParams | null
+    context_strategy?: ContextStrategy | null
     created_at: string
     updated_at: string
 }
@@ -28,9 +74,76 @@ export interface ConversationSessionsResponse {
 export interface ConversationResponse {
     id: string
     items: ConversationMessage[]
+    attachments: Attachment[]
 }
 
-export interface SendMessageRequest {
+export interface SendMessageRequest extends GenerationParams {
     content: string
+    attachment_ids?: string[]
+    status?: MessageStatus
+    error?: string | null
     provider_model_id: string
 }
+
+export interface CompareMessageRequest extends GenerationParams {
+    content: string
+    attachment_ids?: string[]
+    provider_model_ids: string[]
+}
+
+export interface RetryMessageRequest extends GenerationParams {
+    provider_model_id?: string
+}
+
+export interface SwitchBranchRequest {
+    sibling_index: number
+}
+
+export interface UpdateFallbackModelsRequest {
+    fallback_model_ids: string[] | null
+}
+
+export interface SystemPrompt {
+    system_prompt: string | null
+}
+
+export interface UpdateContextStrategyRequest {
+    context_strategy: ContextStrategy | null
+}
+
+export interface UpdateGenerationParamsRequest {
+    generation_params: GenerationParams | null
+}
+
+export type FinishReason = "stop" | "length" | "content_filter" | "tool_calls" | "cancelled" | "error"
+
+export interface EventModel {
+    id: string
+    model_id: string
+    name: string
+}
+
+/** Events of the streaming protocol, see `EVENT_PROTOCOL_VERSION` on the server */
+export type ConversationEvent =
+    | {
+        type: "start"
+        version: number
+        session_id: string
+        user_message_id: string | null
+        assistant_message_id: string
+        model: EventModel
+    }
+    | { type: "delta"; text: string }
+    | { type: "reasoning"; text: string }
+    | { type: "tool_call"; message_id: string; id: string; name: string; arguments: string }
+    | { type: "tool_result"; message_id: string; tool_call_id: string; content: string; is_error: boolean }
+    | { type: "step"; assistant_message_id: string }
+    | { type: "usage"; prompt_tokens: number; completion_tokens: number; reasoning_tokens?: number; cost: string }
+    | { type: "warning"; scope: string; period: string; spent: string; limit: string }
+    | { type: "fallback"; from: EventModel; to: EventModel; reason: string }
+    | { type: "title"; title: string }
+    | { type: "done"; finish_reason: FinishReason }
+    | { type: "error"; code: string; message: string }
+
+/** An event of one of the side-by-side replies, tagged with the model producing it */
+export type ComparisonEvent = ConversationEvent & { provider_model_id: string }
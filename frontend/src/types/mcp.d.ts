@@ -0,0 +1,47 @@
+export type McpTransport = "stdio" | "http"
+
+export interface McpServer {
+    id: string
+    user_id: string
+    name: string
+    transport: McpTransport
+    command?: string | null
+    args?: string[] | null
+    env?: Record<string, string> | null
+    url?: string | null
+    headers?: Record<string, string> | null
+    enabled: boolean
+    allowed_tools?: string[] | null
+    created_at: string
+    updated_at: string
+}
+
+export interface CreateMcpServerRequest {
+    name: string
+    transport: McpTransport
+    command?: string
+    args?: string[]
+    env?: Record<string, string>
+    url?: string
+    headers?: Record<string, string>
+    enabled?: boolean
+    allowed_tools?: string[]
+}
+
+export interface UpdateMcpServerRequest {
+    name?: string
+    transport?: McpTransport
+    command?: string | null
+    args?: string[] | null
+    env?: Record<string, string> | null
+    url?: string | null
+    headers?: Record<string, string> | null
+    enabled?: boolean
+    allowed_tools?: string[] | null
+}
+
+export interface McpTool {
+    name: string
+    description?: string | null
+    allowed: boolean
+}
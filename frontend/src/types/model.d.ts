@@ -1,3 +1,5 @@
+import type { GenerationParams } from "@/types/provider"
+
 export interface Model {
     id: string
     name: string
@@ -13,6 +15,8 @@ export interface CreateProviderModelRequest {
 export interface UpdateProviderModelRequest {
     model_id?: string
     name?: string
+    generation_params?: GenerationParams | null
+    context_length?: number | null
 }
 
 export interface ProviderModelIdResponse {
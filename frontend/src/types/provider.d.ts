@@ -1,3 +1,5 @@
+import type { SpendingBudget } from "@/types/user"
+
 export interface CreateProviderRequest {
     name: string
     provider_type: string
@@ -19,11 +21,26 @@ export interface UserProvider {
     provider_type: string
     url: string
     key?: string
+    budget?: SpendingBudget | null
     created_at: string
     updated_at: string
     models?: ProviderModel[]
 }
 
+export type ReasoningEffort = "low" | "medium" | "high"
+
+/** Sampling settings; unset fields fall back to the session, then the model, then the provider. */
+export interface GenerationParams {
+    temperature?: number
+    top_p?: number
+    max_tokens?: number
+    stop?: string[]
+    presence_penalty?: number
+    frequency_penalty?: number
+    seed?: number
+    reasoning_effort?: ReasoningEffort
+}
+
 export interface ProviderModel {
     id: string
     provider_id: string
@@ -31,6 +48,8 @@ export interface ProviderModel {
     name: string
     input_price_per_million: number
     output_price_per_million: number
+    generation_params?: GenerationParams | null
+    context_length?: number | null
     created_at: string
     updated_at: string
 }
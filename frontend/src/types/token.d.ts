@@ -0,0 +1,25 @@
+export type TokenScope = "chat" | "read_conversations" | "manage_providers"
+
+export interface PersonalAccessToken {
+    id: string
+    user_id: string
+    name: string
+    /** The start of the secret, to tell tokens apart */
+    prefix: string
+    scopes: TokenScope[]
+    /** Never expires when null */
+    expires_at?: string | null
+    last_used_at?: string | null
+    created_at: string
+    updated_at: string
+}
+
+export interface CreatePersonalAccessTokenRequest {
+    name: string
+    scopes: TokenScope[]
+    /** Omit for a token that never expires */
+    expires_in_days?: number
+}
+
+/** Returned once on creation; the secret can't be retrieved later */
+export type CreatedPersonalAccessToken = PersonalAccessToken & { secret: string }
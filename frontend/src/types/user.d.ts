@@ -1,3 +1,5 @@
+import type { ContextStrategy } from "@/types/conversation"
+
 export interface RegisterRequest {
     name: string,
     email: string,
@@ -12,4 +14,22 @@ export interface LoginRequest {
 
 export interface AuthResponse {
     token: string,
-}
\ No newline at end of file
+}
+
+export interface SpendingBudget {
+    daily_limit?: string | number | null
+    monthly_limit?: string | number | null
+    warn_at_percent?: number | null
+}
+
+export interface UserPreferences {
+    budget?: SpendingBudget | null
+    fallback_model_ids?: string[] | null
+    custom_instructions?: string | null
+    context_strategy?: ContextStrategy | null
+    [key: string]: unknown
+}
+
+export interface CustomInstructions {
+    custom_instructions: string | null
+}